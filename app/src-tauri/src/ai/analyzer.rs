@@ -1,16 +1,28 @@
 use anyhow::{Result, anyhow};
 use std::collections::HashMap;
 use serde_json::json;
+use crate::csv_engine::data_types::{DataTypeDetector, ParsedValue};
+use crate::csv_engine::locale::LocaleFormat;
 
 use super::{AnalysisType, TargetScope, Visualization};
 
 /// Performs data analysis on CSV data
 #[derive(Debug)]
-pub struct DataAnalyzer;
+pub struct DataAnalyzer {
+    locale: LocaleFormat,
+}
 
 impl DataAnalyzer {
     pub fn new() -> Self {
-        Self
+        Self {
+            locale: LocaleFormat::default(),
+        }
+    }
+
+    /// Read numbers and dates written in `locale`
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        self.locale = locale;
+        self
     }
 
     pub fn analyze(
@@ -46,11 +58,25 @@ impl DataAnalyzer {
             }
 
             let column_name = &headers[col_idx];
+
+            // Currency, percentages, times and durations count by their
+            // parsed amount, fraction or seconds
+            let sample: Vec<String> = rows
+                .iter()
+                .take(100)
+                .filter_map(|row| row.get(col_idx).map(|v| v.trim().to_string()))
+                .collect();
+            let column_type = DataTypeDetector::new()
+                .with_locale(self.locale.clone())
+                .detect_column_type(&sample);
+
             let values: Vec<f64> = rows
                 .iter()
                 .filter_map(|row| {
-                    row.get(col_idx)
-                        .and_then(|v| v.trim().parse::<f64>().ok())
+                    let value = row.get(col_idx)?.trim();
+                    self.locale.parse_number(value).or_else(|| {
+                        ParsedValue::parse(value, &column_type)?.as_number()
+                    })
                 })
                 .collect();
 
@@ -82,6 +108,7 @@ impl DataAnalyzer {
             details.insert(
                 column_name.clone(),
                 json!({
+                    "data_type": column_type,
                     "count": count,
                     "sum": sum,
                     "mean": mean,
@@ -181,7 +208,7 @@ impl DataAnalyzer {
                     .iter()
                     .filter_map(|row| {
                         row.get(col_idx)
-                            .and_then(|v| v.trim().parse::<f64>().ok())
+                            .and_then(|v| self.locale.parse_number(v))
                     })
                     .collect();
 
@@ -277,7 +304,7 @@ impl DataAnalyzer {
                 .enumerate()
                 .filter_map(|(row_idx, row)| {
                     row.get(col_idx)
-                        .and_then(|v| v.trim().parse::<f64>().ok())
+                        .and_then(|v| self.locale.parse_number(v))
                         .map(|val| (row_idx, val))
                 })
                 .collect();
@@ -469,7 +496,7 @@ impl DataAnalyzer {
                 .collect();
 
             let numeric_count = sample_values.iter()
-                .filter(|v| v.trim().parse::<f64>().is_ok())
+                .filter(|v| self.locale.parse_number(v).is_some())
                 .count();
 
             let empty_count = sample_values.iter()
@@ -40,7 +40,15 @@ pub enum TransformOperation {
     FormatDates { target_format: String },
     FormatNumbers { decimal_places: usize },
     RemoveDuplicates,
-    FillMissing { strategy: String },
+    /// `group_by` names the key column for group-wise strategies and
+    /// `order_by` the date column interpolation follows
+    FillMissing {
+        strategy: String,
+        #[serde(default)]
+        group_by: Option<String>,
+        #[serde(default)]
+        order_by: Option<String>,
+    },
     Capitalize,
     Lowercase,
     Uppercase,
@@ -155,7 +163,7 @@ fn detect_transform_operation(prompt: &str, prompt_lower: &str) -> Option<Transf
     if (prompt_lower.contains("fill") || prompt_lower.contains("replace")) &&
        (prompt_lower.contains("missing") || prompt_lower.contains("null")) {
         let strategy = extract_fill_strategy(prompt_lower).unwrap_or("mean".to_string());
-        return Some(TransformOperation::FillMissing { strategy });
+        return Some(TransformOperation::FillMissing { strategy, group_by: None, order_by: None });
     }
 
     // Text case transformations
@@ -254,6 +262,12 @@ fn extract_fill_strategy(prompt: &str) -> Option<String> {
         Some("zero".to_string())
     } else if prompt.contains("forward") {
         Some("forward".to_string())
+    } else if prompt.contains("backward") {
+        Some("backward".to_string())
+    } else if prompt.contains("interpolat") {
+        Some("interpolate".to_string())
+    } else if prompt.contains("pattern") {
+        Some("pattern".to_string())
     } else {
         None
     }
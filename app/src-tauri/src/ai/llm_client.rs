@@ -71,7 +71,9 @@ For TRANSFORMATION requests:
   "parameters": {
     "target_format": "YYYY-MM-DD" (for format_dates),
     "decimal_places": 2 (for format_numbers),
-    "strategy": "mean" | "median" | "mode" | "zero" | "forward" (for fill_missing),
+    "strategy": "mean" | "median" | "mode" | "zero" | "forward" | "backward" | "interpolate" | "pattern" (for fill_missing),
+    "group_by": "key column name" (optional, for fill_missing mean/median/mode/pattern),
+    "order_by": "date column name" (optional, for fill_missing interpolate),
     "from": "text", "to": "replacement" (for replace)
   },
   "target_scope": {
@@ -767,7 +769,17 @@ fn parse_llm_response(json_str: &str) -> Result<Intent> {
                         .and_then(|v| v.as_str())
                         .unwrap_or("mean")
                         .to_string();
-                    TransformOperation::FillMissing { strategy }
+                    let column = |key: &str| {
+                        params
+                            .and_then(|p| p.get(key))
+                            .and_then(|v| v.as_str())
+                            .map(String::from)
+                    };
+                    TransformOperation::FillMissing {
+                        strategy,
+                        group_by: column("group_by"),
+                        order_by: column("order_by"),
+                    }
                 }
                 Some("capitalize") => TransformOperation::Capitalize,
                 Some("lowercase") => TransformOperation::Lowercase,
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, anyhow};
 use std::collections::HashMap;
+use crate::csv_engine::locale::LocaleFormat;
 
 mod intent;
 mod analyzer;
@@ -39,6 +40,13 @@ impl AiAssistant {
         }
     }
 
+    /// Analyze and transform numbers and dates written in `locale`
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        self.analyzer = self.analyzer.with_locale(locale.clone());
+        self.transformer = self.transformer.with_locale(locale);
+        self
+    }
+
     fn create_llm_client(config: &AiConfig) -> Option<Box<dyn LlmClient>> {
         // Check if external API is configured
         if let Some(api_key) = config.get_api_key() {
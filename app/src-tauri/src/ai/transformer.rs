@@ -2,14 +2,24 @@ use anyhow::{Result, anyhow};
 use chrono::NaiveDate;
 
 use super::{TransformOperation, TargetScope, DataChange};
+use crate::csv_engine::cleansing::{impute, ImputationMethod};
+use crate::csv_engine::locale::LocaleFormat;
 
 /// Performs data transformations on CSV data
 #[derive(Debug)]
-pub struct DataTransformer;
+pub struct DataTransformer {
+    locale: LocaleFormat,
+}
 
 impl DataTransformer {
     pub fn new() -> Self {
-        Self
+        Self { locale: LocaleFormat::default() }
+    }
+
+    /// Read numbers and dates written in `locale`
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        self.locale = locale;
+        self
     }
 
     pub fn transform(
@@ -30,8 +40,8 @@ impl DataTransformer {
             TransformOperation::RemoveDuplicates => {
                 self.remove_duplicates(headers, rows, scope)
             }
-            TransformOperation::FillMissing { strategy } => {
-                self.fill_missing(headers, rows, scope, strategy)
+            TransformOperation::FillMissing { strategy, group_by, order_by } => {
+                self.fill_missing(headers, rows, scope, strategy, group_by.as_deref(), order_by.as_deref())
             }
             TransformOperation::Capitalize => self.capitalize(headers, rows, scope),
             TransformOperation::Lowercase => self.lowercase(headers, rows, scope),
@@ -58,7 +68,7 @@ impl DataTransformer {
                 .enumerate()
                 .filter_map(|(_, row)| {
                     row.get(col_idx)
-                        .and_then(|v| v.trim().parse::<f64>().ok())
+                        .and_then(|v| self.locale.parse_number(v))
                 })
                 .collect();
 
@@ -76,9 +86,9 @@ impl DataTransformer {
 
             for (row_idx, row) in rows.iter().enumerate() {
                 if let Some(old_value) = row.get(col_idx) {
-                    if let Ok(value) = old_value.trim().parse::<f64>() {
+                    if let Some(value) = self.locale.parse_number(old_value) {
                         let normalized = (value - min) / range;
-                        let new_value = format!("{:.6}", normalized);
+                        let new_value = self.format_number(normalized, 6);
 
                         if old_value != &new_value {
                             changes.push(DataChange {
@@ -166,8 +176,8 @@ impl DataTransformer {
         for &col_idx in &target_columns {
             for (row_idx, row) in rows.iter().enumerate() {
                 if let Some(old_value) = row.get(col_idx) {
-                    if let Ok(number) = old_value.trim().parse::<f64>() {
-                        let new_value = format!("{:.prec$}", number, prec = decimal_places);
+                    if let Some(number) = self.locale.parse_number(old_value) {
+                        let new_value = self.format_number(number, decimal_places);
                         if old_value.trim() != new_value {
                             changes.push(DataChange {
                                 row_index: row_idx,
@@ -184,6 +194,11 @@ impl DataTransformer {
         Ok(changes)
     }
 
+    /// `number` with `decimal_places` digits and the locale's decimal separator
+    fn format_number(&self, number: f64, decimal_places: usize) -> String {
+        format!("{:.prec$}", number, prec = decimal_places).replace('.', &self.locale.decimal_separator)
+    }
+
     fn remove_duplicates(
         &self,
         _headers: &[String],
@@ -218,51 +233,23 @@ impl DataTransformer {
         rows: &[Vec<String>],
         scope: &TargetScope,
         strategy: &str,
+        group_by: Option<&str>,
+        order_by: Option<&str>,
     ) -> Result<Vec<DataChange>> {
         let target_columns = self.get_target_column_indices(headers, scope)?;
+        let group_by = group_by.map(|name| self.column_index(headers, name)).transpose()?;
+        let order_by = order_by.map(|name| self.column_index(headers, name)).transpose()?;
+        let method = ImputationMethod::parse(strategy, None, group_by, order_by)
+            .ok_or_else(|| anyhow!("Unknown fill strategy '{}'", strategy))?;
         let mut changes = Vec::new();
-
         for &col_idx in &target_columns {
-            let fill_value = match strategy {
-                "mean" => self.calculate_mean(rows, col_idx),
-                "median" => self.calculate_median(rows, col_idx),
-                "mode" => self.calculate_mode(rows, col_idx),
-                "zero" => Some("0".to_string()),
-                "forward" => None, // Forward fill handled separately
-                _ => None,
-            };
-
-            if strategy == "forward" {
-                let mut last_valid = String::new();
-                for (row_idx, row) in rows.iter().enumerate() {
-                    if let Some(value) = row.get(col_idx) {
-                        if value.trim().is_empty() {
-                            if !last_valid.is_empty() {
-                                changes.push(DataChange {
-                                    row_index: row_idx,
-                                    column_index: col_idx,
-                                    old_value: value.clone(),
-                                    new_value: last_valid.clone(),
-                                });
-                            }
-                        } else {
-                            last_valid = value.clone();
-                        }
-                    }
-                }
-            } else if let Some(fill) = fill_value {
-                for (row_idx, row) in rows.iter().enumerate() {
-                    if let Some(old_value) = row.get(col_idx) {
-                        if old_value.trim().is_empty() {
-                            changes.push(DataChange {
-                                row_index: row_idx,
-                                column_index: col_idx,
-                                old_value: old_value.clone(),
-                                new_value: fill.clone(),
-                            });
-                        }
-                    }
-                }
+            for (row_idx, new_value) in impute(rows, col_idx, &method, &self.locale) {
+                changes.push(DataChange {
+                    row_index: row_idx,
+                    column_index: col_idx,
+                    old_value: rows[row_idx][col_idx].clone(),
+                    new_value,
+                });
             }
         }
 
@@ -424,64 +411,6 @@ impl DataTransformer {
         Ok(changes)
     }
 
-    fn calculate_mean(&self, rows: &[Vec<String>], col_idx: usize) -> Option<String> {
-        let values: Vec<f64> = rows
-            .iter()
-            .filter_map(|row| {
-                row.get(col_idx)
-                    .and_then(|v| v.trim().parse::<f64>().ok())
-            })
-            .collect();
-
-        if values.is_empty() {
-            return None;
-        }
-
-        let sum: f64 = values.iter().sum();
-        let mean = sum / values.len() as f64;
-        Some(format!("{:.2}", mean))
-    }
-
-    fn calculate_median(&self, rows: &[Vec<String>], col_idx: usize) -> Option<String> {
-        let mut values: Vec<f64> = rows
-            .iter()
-            .filter_map(|row| {
-                row.get(col_idx)
-                    .and_then(|v| v.trim().parse::<f64>().ok())
-            })
-            .collect();
-
-        if values.is_empty() {
-            return None;
-        }
-
-        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        let median = if values.len() % 2 == 0 {
-            (values[values.len() / 2 - 1] + values[values.len() / 2]) / 2.0
-        } else {
-            values[values.len() / 2]
-        };
-
-        Some(format!("{:.2}", median))
-    }
-
-    fn calculate_mode(&self, rows: &[Vec<String>], col_idx: usize) -> Option<String> {
-        let mut frequency = std::collections::HashMap::new();
-
-        for row in rows {
-            if let Some(value) = row.get(col_idx) {
-                if !value.trim().is_empty() {
-                    *frequency.entry(value.clone()).or_insert(0) += 1;
-                }
-            }
-        }
-
-        frequency
-            .into_iter()
-            .max_by_key(|(_, count)| *count)
-            .map(|(value, _)| value)
-    }
-
     fn get_target_column_indices(
         &self,
         headers: &[String],
@@ -489,12 +418,7 @@ impl DataTransformer {
     ) -> Result<Vec<usize>> {
         match scope {
             TargetScope::AllData => Ok((0..headers.len()).collect()),
-            TargetScope::Column { name } => {
-                headers.iter()
-                    .position(|h| h.to_lowercase() == name.to_lowercase())
-                    .map(|idx| vec![idx])
-                    .ok_or_else(|| anyhow!("Column '{}' not found", name))
-            }
+            TargetScope::Column { name } => self.column_index(headers, name).map(|idx| vec![idx]),
             TargetScope::Columns { names } => {
                 let indices: Vec<usize> = names.iter()
                     .filter_map(|name| {
@@ -512,4 +436,84 @@ impl DataTransformer {
             TargetScope::Selection { columns, .. } => Ok(columns.clone()),
         }
     }
+
+    fn column_index(&self, headers: &[String], name: &str) -> Result<usize> {
+        headers.iter()
+            .position(|h| h.to_lowercase() == name.to_lowercase())
+            .ok_or_else(|| anyhow!("Column '{}' not found", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_transforms_read_and_write_the_locale() {
+        let headers = vec!["amount".to_string()];
+        let rows = vec![vec!["1,5".to_string()], vec!["2,25".to_string()], vec!["n/a".to_string()]];
+        let transformer = DataTransformer::new().with_locale(LocaleFormat::for_language_tag("de-DE"));
+        let scope = TargetScope::Column { name: "amount".to_string() };
+
+        let changes = transformer
+            .transform(&TransformOperation::FormatNumbers { decimal_places: 1 }, &headers, &rows, &scope)
+            .unwrap();
+        let values: Vec<(usize, &str)> = changes.iter().map(|c| (c.row_index, c.new_value.as_str())).collect();
+        assert_eq!(values, vec![(1, "2,2")]);
+
+        let changes = transformer.transform(&TransformOperation::Normalize, &headers, &rows, &scope).unwrap();
+        let values: Vec<&str> = changes.iter().map(|c| c.new_value.as_str()).collect();
+        assert_eq!(values, vec!["0,000000", "1,000000"]);
+    }
+
+    #[test]
+    fn test_fill_missing_by_group_and_order_in_the_locale() {
+        let headers: Vec<String> = ["date", "region", "amount"].iter().map(|h| h.to_string()).collect();
+        let rows: Vec<Vec<String>> = [
+            ["2024-01-03", "north", "3,5"],
+            ["2024-01-01", "north", "1,5"],
+            ["2024-01-02", "south", ""],
+            ["2024-01-05", "south", "5,5"],
+            ["2024-01-04", "north", ""],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+        let transformer = DataTransformer::new().with_locale(LocaleFormat::for_language_tag("de-DE"));
+        let scope = TargetScope::Column { name: "amount".to_string() };
+        let fill = |strategy: &str, group_by: Option<&str>, order_by: Option<&str>| {
+            let operation = TransformOperation::FillMissing {
+                strategy: strategy.to_string(),
+                group_by: group_by.map(str::to_string),
+                order_by: order_by.map(str::to_string),
+            };
+            transformer
+                .transform(&operation, &headers, &rows, &scope)
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.row_index, c.new_value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            fill("median", Some("region"), None),
+            vec![(2, "5,50".to_string()), (4, "2,50".to_string())]
+        );
+        assert_eq!(
+            fill("interpolate", None, Some("date")),
+            vec![(2, "2,50".to_string()), (4, "4,50".to_string())]
+        );
+        assert!(DataTransformer::new()
+            .transform(
+                &TransformOperation::FillMissing {
+                    strategy: "mean".to_string(),
+                    group_by: Some("missing".to_string()),
+                    order_by: None,
+                },
+                &headers,
+                &rows,
+                &scope,
+            )
+            .is_err());
+    }
 }
@@ -0,0 +1,143 @@
+//! Benchmarks for the parallel code paths on a generated 1M-row file.
+//! Each one runs the same work on a single-thread rayon pool and on the
+//! default pool, checks both give the same answer and fails when the
+//! parallel run is not enough faster.
+//!
+//! They take a while, so they are ignored by default:
+//!
+//!     cargo test --release benches -- --ignored --nocapture --test-threads 1
+//!
+//! `BENCH_MIN_SPEEDUP` replaces the speedup each benchmark requires; set
+//! it to 0 on machines too busy for timings to mean anything.
+
+use std::fmt::Debug;
+use std::io::Write;
+use std::time::Instant;
+use tempfile::NamedTempFile;
+use crate::commands::csv::{search_rows, FindOptions};
+use crate::csv_engine::data_types::DataTypeDetector;
+use crate::csv_engine::quality::QualityAnalyzer;
+use crate::csv_engine::reader::{CsvData, CsvReader};
+
+const FIXTURE_ROWS: usize = 1_000_000;
+
+/// The fixture file, deleted when the returned handle is dropped
+fn fixture_file() -> NamedTempFile {
+    let mut file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+    let mut text = String::from("id,name,email,amount,joined,note\n");
+    for i in 0..FIXTURE_ROWS {
+        text.push_str(&format!(
+            "{},User {},user{}@example.com,{}.{:02},2024-{:02}-{:02},\"note {}, with a comma\"\n",
+            i,
+            i % 5000,
+            i,
+            (i * 7919) % 100_000,
+            i % 100,
+            i % 12 + 1,
+            i % 28 + 1,
+            i,
+        ));
+    }
+    file.write_all(text.as_bytes()).unwrap();
+    file
+}
+
+fn fixture() -> CsvData {
+    let file = fixture_file();
+    CsvReader::new().read_file(file.path()).unwrap()
+}
+
+/// Time `work` on one thread and on the default pool, assert the parallel
+/// run agrees and is at least `min_speedup` times faster. Nothing is
+/// required of the timings when the default pool has a single thread.
+fn compare<T: PartialEq + Debug + Send>(name: &str, min_speedup: f64, work: impl Fn() -> T + Send + Sync) {
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+    let started = Instant::now();
+    let sequential = single.install(&work);
+    let sequential_time = started.elapsed();
+
+    let started = Instant::now();
+    let parallel = work();
+    let parallel_time = started.elapsed();
+
+    let speedup = sequential_time.as_secs_f64() / parallel_time.as_secs_f64();
+    println!(
+        "{}: 1 thread {:?}, {} threads {:?}, speedup {:.2}x",
+        name,
+        sequential_time,
+        rayon::current_num_threads(),
+        parallel_time,
+        speedup,
+    );
+
+    assert_eq!(sequential, parallel);
+
+    let min_speedup = std::env::var("BENCH_MIN_SPEEDUP")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(min_speedup);
+    if rayon::current_num_threads() > 1 {
+        assert!(
+            speedup >= min_speedup,
+            "{}: speedup {:.2}x is below the required {:.2}x",
+            name,
+            speedup,
+            min_speedup,
+        );
+    }
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_read_file() {
+    let file = fixture_file();
+    compare("read_file", 1.2, || {
+        let data = CsvReader::new().read_file(file.path()).unwrap();
+        (data.rows.len(), data.rows[FIXTURE_ROWS - 1].clone())
+    });
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_quality_analyze() {
+    let data = &fixture();
+    compare("QualityAnalyzer::analyze", 1.5, || {
+        let report = QualityAnalyzer::analyze(&data.rows, &data.headers);
+        let columns: Vec<(usize, usize, String)> = report
+            .column_reports
+            .iter()
+            .map(|c| (c.empty_count, c.unique_count, c.dominant_type.clone()))
+            .collect();
+        (columns, report.duplicates.total_duplicates, report.outliers.total_outliers)
+    });
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_detect_column_types() {
+    let data = &fixture();
+    let detector = DataTypeDetector::new();
+    compare("detect_column_types", 1.5, || {
+        detector.detect_column_types(&data.rows, data.headers.len(), usize::MAX)
+    });
+}
+
+#[test]
+#[ignore = "benchmark"]
+fn bench_find_in_csv() {
+    let data = &fixture();
+    let options = FindOptions {
+        search_text: "USER 42".to_string(),
+        case_sensitive: false,
+        whole_word: false,
+        regex: false,
+        column_index: None,
+    };
+    compare("find_in_csv", 1.5, || {
+        search_rows(&data.rows, &options, None)
+            .iter()
+            .map(|r| (r.row_index, r.column_index))
+            .collect::<Vec<_>>()
+    });
+}
@@ -3,7 +3,10 @@ use crate::ai_script::{Script, ExecutionContext, ResultPayload, ScriptType, Colu
 use crate::ai_script::generator::ScriptGenerator;
 use crate::state::{ScriptExecutorState, AppState};
 use crate::chat::ChatHistory;
-use crate::csv_engine::data_types::{DataTypeDetector, DataType};
+use crate::csv_engine::data_types::{ColumnTypeOverride, DataTypeDetector, DataType};
+use crate::metadata::{CsvMetadata, MetadataManager};
+use crate::commands::csv::resolve_locale;
+use crate::commands::settings::SettingsState;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use tauri::{State, Window};
@@ -32,6 +35,9 @@ pub struct ExecuteAiRequest {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<String>>,
     pub max_rows: Option<usize>, // Limit data sent for processing
+    /// Metadata of the open file, for its number and date locale
+    #[serde(default)]
+    pub metadata: Option<CsvMetadata>,
 }
 
 /// Response with AI execution results
@@ -114,8 +120,10 @@ pub async fn ai_detect_intent(
 #[tauri::command]
 pub async fn ai_execute(
     request: ExecuteAiRequest,
+    settings: State<'_, SettingsState>,
 ) -> Result<ExecuteAiResponse, String> {
-    let assistant = AiAssistant::new();
+    let locale = resolve_locale(request.metadata.as_ref(), &settings).await;
+    let assistant = AiAssistant::new().with_locale(locale);
 
     // Check if CSV data is provided
     if request.headers.is_empty() || request.rows.is_empty() {
@@ -217,9 +225,10 @@ fn generate_intent_description(intent: &crate::ai::Intent) -> String {
                     &format!("Format numbers to {} decimal places", decimal_places)
                 }
                 crate::ai::TransformOperation::RemoveDuplicates => "Remove duplicate rows",
-                crate::ai::TransformOperation::FillMissing { strategy } => {
-                    &format!("Fill missing values using {} strategy", strategy)
-                }
+                crate::ai::TransformOperation::FillMissing { strategy, group_by, .. } => match group_by {
+                    Some(column) => &format!("Fill missing values using {} strategy per '{}'", strategy, column),
+                    None => &format!("Fill missing values using {} strategy", strategy),
+                },
                 crate::ai::TransformOperation::Capitalize => "Capitalize each word",
                 crate::ai::TransformOperation::Lowercase => "Convert to lowercase",
                 crate::ai::TransformOperation::Uppercase => "Convert to uppercase",
@@ -263,10 +272,22 @@ pub struct GenerateScriptResponse {
     pub requires_approval: bool,
 }
 
-/// Analyze CSV data to extract column information (types, formats, samples)
+/// Types declared in the file's metadata, if the context names a saved file
+fn declared_column_types(csv_context: &ExecutionContext) -> Vec<Option<ColumnTypeOverride>> {
+    csv_context
+        .csv_path
+        .as_ref()
+        .and_then(|path| MetadataManager::new().load_metadata(std::path::Path::new(path)).ok())
+        .map(|metadata| metadata.column_type_overrides(&csv_context.headers))
+        .unwrap_or_default()
+}
+
+/// Analyze CSV data to extract column information (types, formats, samples).
+/// Declared column types are reported as they are instead of being detected.
 fn analyze_csv_columns(
     headers: &[String],
     sample_rows: &[Vec<String>],
+    declared: &[Option<ColumnTypeOverride>],
 ) -> Vec<ColumnInfo> {
     let detector = DataTypeDetector::new();
     let mut column_info = Vec::new();
@@ -281,6 +302,17 @@ fn analyze_csv_columns(
             .cloned()
             .collect();
 
+        if let Some(Some(declared)) = declared.get(index) {
+            column_info.push(ColumnInfo {
+                column_index: index,
+                column_name: header.clone(),
+                detected_type: format!("{:?}", declared.data_type).to_lowercase(),
+                format: declared.date_format.clone().or_else(|| declared.number_format.clone()),
+                sample_values: column_values,
+            });
+            continue;
+        }
+
         if column_values.is_empty() {
             column_info.push(ColumnInfo {
                 column_index: index,
@@ -303,6 +335,13 @@ fn analyze_csv_columns(
             DataType::Email => "email",
             DataType::Url => "url",
             DataType::Json => "json",
+            DataType::Currency => "currency",
+            DataType::Percentage => "percentage",
+            DataType::Time => "time",
+            DataType::Duration => "duration",
+            DataType::Uuid => "uuid",
+            DataType::IpAddress => "ipaddress",
+            DataType::Phone => "phone",
             DataType::Text => "text",
         };
 
@@ -335,7 +374,8 @@ pub async fn generate_script(
     // Analyze column information if sample rows are provided
     let mut context_with_info = csv_context.clone();
     if let Some(rows) = sample_rows {
-        let column_info = analyze_csv_columns(&csv_context.headers, &rows);
+        let declared = declared_column_types(&csv_context);
+        let column_info = analyze_csv_columns(&csv_context.headers, &rows, &declared);
         context_with_info.column_info = Some(column_info);
         log::info!("[COMMAND] Analyzed {} columns with sample data", context_with_info.column_info.as_ref().unwrap().len());
     }
@@ -386,7 +426,8 @@ pub async fn fix_script(
     // Analyze column information if sample rows are provided
     let mut context_with_info = csv_context.clone();
     if let Some(rows) = sample_rows {
-        let column_info = analyze_csv_columns(&csv_context.headers, &rows);
+        let declared = declared_column_types(&csv_context);
+        let column_info = analyze_csv_columns(&csv_context.headers, &rows, &declared);
         context_with_info.column_info = Some(column_info);
         log::info!("[COMMAND] Analyzed {} columns for script fix", context_with_info.column_info.as_ref().unwrap().len());
     }
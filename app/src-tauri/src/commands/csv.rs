@@ -1,24 +1,63 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::State;
 use crate::csv_engine::{reader::CsvReader, writer::CsvWriter};
 use crate::csv_engine::reader::CsvData;
-use crate::csv_engine::data_types::{DataType, DataTypeDetector};
+use crate::csv_engine::data_types::{ColumnTypeOverride, DataType, DataTypeDetector, TypeAmbiguity, TypeCandidate};
 use crate::csv_engine::validation::{ValidationRule, Validator, ValidationError as CustomValidationError};
-use crate::csv_engine::quality::QualityAnalyzer;
-use crate::csv_engine::cleansing::{DataCleanser, CleansingOptions, CleansingResult};
+use crate::csv_engine::quality::{QualityAnalyzer, QualityConfig, QualityReport};
+use crate::csv_engine::profiler::{ColumnProfile, DataProfiler, ProfileOptions};
+use crate::csv_engine::streaming::StreamingReader;
+use crate::csv_engine::quality_trend::{quality_trend, record_snapshot, FileVersion, QualitySnapshot, QualityTrend, TrendThresholds};
+use crate::csv_engine::cleansing::{DataCleanser, CleansingAction, CleansingOptions, CleansingResult, ModificationDetail};
+use crate::csv_engine::dedup::{merge_clusters, Blocking, DuplicateCluster, FuzzyDuplicateReport, FuzzyMatchOptions, FuzzyMatcher, SurvivorPolicy};
 use crate::csv_engine::export::{Exporter, ExportOptions};
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::encoding;
+use crate::csv_engine::compression::{self, Compression};
+use crate::csv_engine::locale::LocaleFormat;
+use crate::csv_engine::expression::Expression;
+use crate::csv_engine::live_validation::{LiveValidator, ValidationDiff};
+use crate::csv_engine::report::{ReportEntry, ReportFormat, ValidationReport};
+use crate::csv_engine::schema::{SchemaFormat, SchemaValidation, TableSchema};
+use crate::csv_engine::column_store::ColumnStore;
+use crate::csv_engine::sniffer::SniffResult;
+use crate::csv_engine::fixed_width::{FixedWidthReader, FixedWidthSpec, FixedWidthWriter};
 use crate::metadata::{CsvMetadata, ViewState};
-use crate::state::AppState;
+use crate::csv_engine::loader::{BackgroundLoader, LoadEvent};
+use crate::state::{AppState, AppStateInner, CsvLoadState};
+use crate::commands::settings::SettingsState;
 use crate::utils::AppError;
 use encoding_rs::{UTF_8, SHIFT_JIS, EUC_JP};
 use chrono::Local;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// Optional overrides for `open_csv_file`; anything left unset is detected
+/// from the file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OpenOptions {
+    pub has_headers: Option<bool>,
+    /// Extra delimiters to consider during detection, e.g. `^` or `||`
+    pub delimiter_candidates: Vec<String>,
+    pub lenient: bool,
+    /// Encoding label such as `windows-1252`; re-decodes instead of detecting
+    pub encoding: Option<String>,
+    /// CSV member to open when the file is a zip archive
+    pub archive_member: Option<String>,
+}
+
 #[tauri::command]
 pub async fn open_csv_file(
     path: String,
+    options: Option<OpenOptions>,
+    window: tauri::Window,
     state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<CsvData, AppError> {
     let path = Path::new(&path);
 
@@ -29,19 +68,493 @@ pub async fn open_csv_file(
         ));
     }
 
-    // Always detect encoding and delimiter from the file itself
-    let mut reader = CsvReader::new();
+    let mut state = state.lock().await;
+
+    // Load metadata for user preferences (not for encoding/delimiter)
+    let saved_metadata = state.metadata_manager.load_metadata(path).ok();
+
+    // Files saved with a fixed-width spec are reopened with that layout
+    let mut csv_data = match saved_metadata.as_ref().and_then(|m| m.fixed_width_spec.clone()) {
+        Some(spec) => FixedWidthReader::new().with_spec(spec).read_file(path)?,
+        None => {
+            let options = options.unwrap_or_default();
+            let mut reader = build_reader(&options, saved_metadata.as_ref(), &settings).await?;
+            reader.read_file(path)?
+        }
+    };
+
+    if let Some(saved) = saved_metadata {
+        csv_data.metadata.keep_saved_settings(saved, &csv_data.headers);
+    }
+
+    state.current_file = Some(path.to_path_buf());
+    state.column_store = None;
+    state.live_validator = None;
+    drop(state);
+
+    let locale = resolve_locale(Some(&csv_data.metadata), &settings).await;
+    emit_schema_validation(&window, path, csv_data.metadata.schema_path.as_deref(), &csv_data, &locale);
+
+    Ok(csv_data)
+}
+
+/// Reader configured from open options, falling back to what was saved for
+/// the file and then to the app settings
+async fn build_reader(
+    options: &OpenOptions,
+    saved_metadata: Option<&CsvMetadata>,
+    settings: &State<'_, SettingsState>,
+) -> Result<CsvReader, AppError> {
+    let dialect = resolve_dialect(saved_metadata, settings).await;
+
+    // Detect delimiter (and encoding, unless forced) from the file itself
+    let mut reader = CsvReader::new()
+        .with_dialect(dialect)
+        .with_delimiter_candidates(options.delimiter_candidates.clone())
+        .with_lenient(options.lenient);
+
+    if let Some(has_headers) = options.has_headers {
+        reader = reader.with_headers(has_headers);
+    }
+
+    // An explicit encoding re-decodes the file instead of detecting one
+    if let Some(label) = options.encoding.as_deref() {
+        let forced = encoding::for_label(label).ok_or_else(|| {
+            AppError::new(format!("Unknown encoding: {}", label), "INVALID_ENCODING")
+        })?;
+        reader = reader.with_encoding(forced);
+    }
+
+    // Reopen the same member of a zip archive unless another one is asked for
+    let archive_member = options
+        .archive_member
+        .clone()
+        .or_else(|| saved_metadata.and_then(|m| m.archive_member.clone()));
+    if let Some(member) = archive_member {
+        reader = reader.with_archive_member(member);
+    }
+
+    Ok(reader)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadStarted {
+    pub load_id: String,
+    pub headers: Vec<String>,
+    /// The first screenful; later rows arrive as `csv-load-rows` events
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Start loading a file in the background. Returns as soon as the first
+/// screenful is parsed; the rest arrives as `csv-load-rows` events with
+/// `csv-load-progress` updates, ending in `csv-load-complete`,
+/// `csv-load-cancelled` or `csv-load-error`.
+#[tauri::command]
+pub async fn start_csv_load(
+    path: String,
+    options: Option<OpenOptions>,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+    loads: State<'_, CsvLoadState>,
+) -> Result<LoadStarted, AppError> {
+    let path = PathBuf::from(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    let (reader, saved_metadata, locale) = {
+        let mut state = state.lock().await;
+        let saved_metadata = state.metadata_manager.load_metadata(&path).ok();
+        let reader = build_reader(&options.unwrap_or_default(), saved_metadata.as_ref(), &settings).await?;
+        let locale = resolve_locale(saved_metadata.as_ref(), &settings).await;
+        (reader, saved_metadata, locale)
+    };
+
+    let load_id = uuid::Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    loads.0.lock().await.insert(load_id.clone(), cancelled.clone());
+
+    let (first_tx, first_rx) = tokio::sync::oneshot::channel::<Result<(Vec<String>, Vec<Vec<String>>), AppError>>();
+    let active_loads = loads.0.clone();
+    let task_load_id = load_id.clone();
+    let task_path = path.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let load_id = task_load_id;
+        let mut first_tx = Some(first_tx);
+
+        let result = BackgroundLoader::new(reader).load(&task_path, &cancelled, |event| match event {
+            LoadEvent::FirstRows { headers, rows } => {
+                if let Some(tx) = first_tx.take() {
+                    let _ = tx.send(Ok((headers.to_vec(), rows.to_vec())));
+                }
+            }
+            LoadEvent::Rows { start_row, rows } => {
+                let _ = window.emit("csv-load-rows", serde_json::json!({
+                    "loadId": load_id,
+                    "startRow": start_row,
+                    "rows": rows,
+                }));
+            }
+            LoadEvent::Progress(progress) => {
+                let _ = window.emit("csv-load-progress", serde_json::json!({
+                    "loadId": load_id,
+                    "progress": progress,
+                }));
+            }
+        });
+
+        match result {
+            Ok(Some(mut data)) => {
+                if let Some(saved) = saved_metadata {
+                    data.metadata.keep_saved_settings(saved, &data.headers);
+                }
+                emit_schema_validation(&window, &task_path, data.metadata.schema_path.as_deref(), &data, &locale);
+
+                // Rows were already delivered in batches
+                let _ = window.emit("csv-load-complete", serde_json::json!({
+                    "loadId": load_id,
+                    "metadata": data.metadata,
+                    "sniff": data.sniff,
+                    "malformedRows": data.malformed_rows,
+                    "encodingDetection": data.encoding_detection,
+                }));
+            }
+            Ok(None) => {
+                let _ = window.emit("csv-load-cancelled", serde_json::json!({ "loadId": load_id }));
+            }
+            Err(e) => {
+                let error = AppError::new(format!("Failed to load file: {}", e), "LOAD_ERROR");
+                match first_tx.take() {
+                    Some(tx) => {
+                        let _ = tx.send(Err(error));
+                    }
+                    None => {
+                        let _ = window.emit("csv-load-error", serde_json::json!({
+                            "loadId": load_id,
+                            "error": error,
+                        }));
+                    }
+                }
+            }
+        }
+
+        active_loads.blocking_lock().remove(&load_id);
+    });
+
+    // A load cancelled before its first screenful drops the sender
+    let (headers, rows) = first_rx.await.map_err(|_| {
+        AppError::new("Load was cancelled".to_string(), "LOAD_CANCELLED")
+    })??;
+
+    let mut state = state.lock().await;
+    state.current_file = Some(path);
+    state.column_store = None;
+    state.live_validator = None;
+
+    Ok(LoadStarted { load_id, headers, rows })
+}
+
+#[tauri::command]
+pub async fn cancel_csv_load(
+    load_id: String,
+    loads: State<'_, CsvLoadState>,
+) -> Result<bool, AppError> {
+    match loads.0.lock().await.get(&load_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Quoting rules saved for this file take precedence over the app defaults
+async fn resolve_dialect(
+    saved_metadata: Option<&CsvMetadata>,
+    settings: &State<'_, SettingsState>,
+) -> CsvDialect {
+    match saved_metadata.and_then(|m| m.dialect.clone()) {
+        Some(dialect) => dialect,
+        None => CsvDialect::from_settings(settings.0.lock().await.get_settings()),
+    }
+}
+
+/// Number and date format saved for the file, else the app settings
+pub(crate) async fn resolve_locale(
+    saved_metadata: Option<&CsvMetadata>,
+    settings: &State<'_, SettingsState>,
+) -> LocaleFormat {
+    match saved_metadata.and_then(|m| m.locale.clone()) {
+        Some(locale) => locale,
+        None => LocaleFormat::from_settings(settings.0.lock().await.get_settings()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MappedFileInfo {
+    pub headers: Vec<String>,
+    pub metadata: CsvMetadata,
+    /// Bytes held for the offset index and edits, excluding the mapped file
+    pub memory_usage: u64,
+}
+
+/// Open a large file with the memory-mapped column store instead of
+/// loading every cell. Rows are then fetched with `get_mapped_rows`.
+#[tauri::command]
+pub async fn open_csv_file_mapped(
+    path: String,
+    options: Option<OpenOptions>,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+) -> Result<MappedFileInfo, AppError> {
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    let saved_metadata = state.lock().await.metadata_manager.load_metadata(path).ok();
+    let options = options.unwrap_or_default();
+    let dialect = resolve_dialect(saved_metadata.as_ref(), &settings).await;
+
+    // Sniffing and indexing read the whole file, so they run off the async
+    // runtime without holding the state
+    let task_path = path.to_path_buf();
+    let (store, delimiter, has_headers) = tokio::task::spawn_blocking(move || {
+        let sniff = CsvReader::new()
+            .with_dialect(dialect.clone())
+            .with_delimiter_candidates(options.delimiter_candidates)
+            .detect_format(&task_path)?;
+        let has_headers = options.has_headers.unwrap_or(sniff.has_headers);
+
+        let delimiter = match sniff.delimiter.as_bytes() {
+            [delimiter] => *delimiter,
+            _ => {
+                return Err(AppError::new(
+                    format!("Delimiter {:?} is not supported for memory-mapped files", sniff.delimiter),
+                    "UNSUPPORTED_DELIMITER",
+                ))
+            }
+        };
+
+        let store = ColumnStore::open(&task_path, delimiter, has_headers, dialect)?;
+        Ok((store, sniff.delimiter, has_headers))
+    })
+    .await
+    .map_err(|e| AppError::new(format!("Indexing failed: {}", e), "MAPPED_OPEN_ERROR"))??;
+
+    let mut metadata = CsvMetadata::new(path)?;
+    metadata.delimiter = delimiter;
+    metadata.has_headers = has_headers;
+    metadata.dialect = Some(store.dialect().clone());
+    metadata.update_counts(store.row_count(), store.column_count());
+    if let Some(saved) = saved_metadata {
+        metadata.keep_saved_settings(saved, store.headers());
+    }
+
+    let info = MappedFileInfo {
+        headers: store.headers().to_vec(),
+        metadata,
+        memory_usage: store.memory_usage(),
+    };
+
+    let mut state = state.lock().await;
+    state.column_store = Some(store);
+    state.current_file = Some(path.to_path_buf());
+
+    Ok(info)
+}
+
+fn mapped_store_missing() -> AppError {
+    AppError::new("No memory-mapped file is open".to_string(), "NO_MAPPED_FILE")
+}
+
+#[tauri::command]
+pub async fn get_mapped_rows(
+    start_row: usize,
+    end_row: usize,
+    state: State<'_, AppState>,
+) -> Result<Vec<Vec<String>>, AppError> {
+    let state = state.lock().await;
+    let store = state.column_store.as_ref().ok_or_else(mapped_store_missing)?;
+
+    Ok(store.rows(start_row, end_row))
+}
+
+#[tauri::command]
+pub async fn update_mapped_cell(
+    row_index: usize,
+    column_index: usize,
+    value: String,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let mut state = state.lock().await;
+    let store = state.column_store.as_mut().ok_or_else(mapped_store_missing)?;
+    store.set_cell(row_index, column_index, value.clone())?;
+    state.has_unsaved_changes = true;
+    live_validate_edits(&mut state, [(row_index, column_index, value)], &window)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn detect_mapped_column_types(
+    state: State<'_, AppState>,
+) -> Result<Vec<ColumnTypeInfo>, AppError> {
+    let state = state.lock().await;
+    let store = state.column_store.as_ref().ok_or_else(mapped_store_missing)?;
+    let detector = DataTypeDetector::new();
+
+    let column_types = store
+        .headers()
+        .par_iter()
+        .enumerate()
+        .map(|(index, header)| {
+            let inference = store.infer_column_type(index, &detector, 100);
+            ColumnTypeInfo {
+                column_index: index,
+                column_name: header.clone(),
+                detected_type: inference.data_type,
+                sample_values: store.column(index).take(5).map(|v| v.into_owned()).collect(),
+                declared_type: None,
+                confidence: inference.confidence,
+                candidates: inference.candidates,
+                nonconforming_rows: inference.nonconforming_rows,
+                ambiguities: inference.ambiguities,
+            }
+        })
+        .collect();
+
+    Ok(column_types)
+}
+
+/// Write edits back, to the mapped file itself unless `path` is given
+#[tauri::command]
+pub async fn save_mapped_file(
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let mut state = state.lock().await;
+    let store = state.column_store.as_mut().ok_or_else(mapped_store_missing)?;
+    let target = path.map(PathBuf::from).unwrap_or_else(|| store.path().to_path_buf());
+
+    store.save(&target)?;
+    state.current_file = Some(target);
+    state.has_unsaved_changes = false;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn open_fixed_width_file(
+    path: String,
+    spec: Option<FixedWidthSpec>,
+    has_headers: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<CsvData, AppError> {
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    // Without an explicit spec, boundaries are inferred from blank columns
+    let mut reader = match spec {
+        Some(spec) => FixedWidthReader::new().with_spec(spec),
+        None => FixedWidthReader::new().with_headers(has_headers.unwrap_or(true)),
+    };
     let csv_data = reader.read_file(path)?;
 
     let mut state = state.lock().await;
     state.current_file = Some(path.to_path_buf());
 
-    // Load metadata for user preferences (not for encoding/delimiter)
-    state.metadata_manager.load_metadata(path).ok();
+    // Remember the spec so the file reopens with the same layout
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+    metadata.fixed_width_spec = csv_data.metadata.fixed_width_spec.clone();
+    metadata.encoding = csv_data.metadata.encoding.clone();
+    state.metadata_manager.save_metadata(path, &metadata)?;
 
     Ok(csv_data)
 }
 
+#[tauri::command]
+pub async fn infer_fixed_width_spec(
+    path: String,
+    has_headers: Option<bool>,
+) -> Result<FixedWidthSpec, AppError> {
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    let spec = FixedWidthReader::new()
+        .with_headers(has_headers.unwrap_or(true))
+        .infer_spec(path)?;
+
+    Ok(spec)
+}
+
+#[tauri::command]
+pub async fn list_archive_members(path: String) -> Result<Vec<String>, AppError> {
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    if Compression::detect(path)? != Compression::Zip {
+        return Err(AppError::new(
+            format!("Not a zip archive: {}", path.display()),
+            "NOT_AN_ARCHIVE",
+        ));
+    }
+
+    Ok(compression::zip_members(path)?)
+}
+
+#[tauri::command]
+pub async fn sniff_csv_file(
+    path: String,
+    delimiter_candidates: Option<Vec<String>>,
+) -> Result<SniffResult, AppError> {
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err(AppError::new(
+            format!("File not found: {}", path.display()),
+            "FILE_NOT_FOUND",
+        ));
+    }
+
+    let mut reader = CsvReader::new()
+        .with_delimiter_candidates(delimiter_candidates.unwrap_or_default());
+    reader.detect_encoding(path)?;
+    let result = reader.detect_format(path)?;
+
+    Ok(result)
+}
+
 #[tauri::command]
 pub async fn parse_csv_from_text(
     text: String,
@@ -59,31 +572,52 @@ pub async fn parse_csv_from_text(
 #[tauri::command]
 pub async fn save_csv_file(
     path: String,
-    data: CsvData,
+    mut data: CsvData,
+    window: tauri::Window,
     state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<(), AppError> {
     let path = Path::new(&path);
+    let encoding = encoding::for_label(&data.metadata.encoding).unwrap_or(UTF_8);
 
-    let writer = CsvWriter::new()
-        .with_delimiter(data.metadata.delimiter.as_bytes()[0]);
-
-    writer.write_file(path, &data)?;
+    if let Some(spec) = &data.metadata.fixed_width_spec {
+        FixedWidthWriter::new(spec.clone())
+            .with_encoding(encoding)
+            .write_file(path, &data).map_err(write_error)?;
+    } else {
+        // Files are written back in the encoding they were opened with
+        let writer = CsvWriter::new()
+            .with_delimiter_str(&data.metadata.delimiter)
+            .with_encoding(encoding)
+            .with_dialect(data.metadata.dialect.clone().unwrap_or_default())
+            .with_compression(data.metadata.compression)
+            .with_archive_member(data.metadata.archive_member.clone());
+
+        writer.write_file(path, &data).map_err(write_error)?;
+    }
 
     let mut state = state.lock().await;
     state.current_file = Some(path.to_path_buf());
-    state.metadata_manager.save_metadata(path, &data.metadata)?;
+    state.metadata_manager.save_editor_metadata(path, &mut data.metadata, &data.headers)?;
+    drop(state);
+
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    emit_schema_validation(&window, path, data.metadata.schema_path.as_deref(), &data, &locale);
 
     Ok(())
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn save_csv_file_as(
     path: String,
     data: CsvData,
     format: Option<String>,
     encoding: Option<String>,
     create_backup: bool,
+    window: tauri::Window,
     state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<(), AppError> {
     let path = Path::new(&path);
 
@@ -104,30 +638,74 @@ pub async fn save_csv_file_as(
     let (encoding_type, encoding_name) = match encoding.as_deref() {
         Some("shift_jis") => (SHIFT_JIS, "Shift_JIS"),
         Some("euc_jp") => (EUC_JP, "EUC-JP"),
-        Some("utf8") | _ => (UTF_8, "UTF-8"),
+        Some("utf8") | None => (UTF_8, "UTF-8"),
+        Some(label) => match encoding::for_label(label) {
+            Some(encoding) => (encoding, encoding.name()),
+            None => {
+                return Err(AppError::new(
+                    format!("Unknown encoding: {}", label),
+                    "INVALID_ENCODING",
+                ))
+            }
+        },
     };
 
+    // The target's extension decides compression, e.g. `export.csv.gz`
+    let target_compression = Compression::from_extension(path);
+
     let writer = CsvWriter::new()
         .with_delimiter(delimiter)
-        .with_encoding(encoding_type);
+        .with_encoding(encoding_type)
+        .with_dialect(data.metadata.dialect.clone().unwrap_or_default())
+        .with_compression(target_compression);
 
-    writer.write_file(path, &data)?;
+    writer.write_file(path, &data).map_err(write_error)?;
 
     // Update metadata with the actual encoding used
     let mut updated_metadata = data.metadata.clone();
     updated_metadata.encoding = encoding_name.to_string();
+    updated_metadata.compression = target_compression;
+    updated_metadata.archive_member = match target_compression {
+        Compression::Zip => Some(compression::inner_file_name(path)),
+        _ => None,
+    };
     updated_metadata.delimiter = match delimiter {
         b'\t' => "\t".to_string(),
         _ => String::from_utf8(vec![delimiter]).unwrap_or(",".to_string()),
     };
 
     let mut state = state.lock().await;
+    // Settings stored for the original file go with the copy
+    if !data.metadata.path.is_empty() {
+        if let Ok(saved) = state.metadata_manager.load_metadata(Path::new(&data.metadata.path)) {
+            updated_metadata.keep_saved_settings(saved, &data.headers);
+        }
+    }
+    // A schema next to the original file is referenced by its full path
+    updated_metadata.schema_path = updated_metadata.schema_path.as_deref().map(|schema_path| {
+        resolve_schema_path(Path::new(&data.metadata.path), schema_path)
+            .to_string_lossy()
+            .into_owned()
+    });
     state.current_file = Some(path.to_path_buf());
     state.metadata_manager.save_metadata(path, &updated_metadata)?;
+    drop(state);
+
+    let locale = resolve_locale(Some(&updated_metadata), &settings).await;
+    emit_schema_validation(&window, path, updated_metadata.schema_path.as_deref(), &data, &locale);
 
     Ok(())
 }
 
+/// Characters the target encoding cannot hold get their own error code, so
+/// the user can be told which ones to change or pick another encoding
+fn write_error(error: anyhow::Error) -> AppError {
+    match error.downcast_ref::<encoding::UnencodableError>() {
+        Some(unencodable) => AppError::new(unencodable.to_string(), "UNENCODABLE_CHARACTERS"),
+        None => error.into(),
+    }
+}
+
 fn create_backup_path(original_path: &Path) -> Result<PathBuf, AppError> {
     let timestamp = Local::now().format("%Y%m%d_%H%M%S");
     let file_stem = original_path.file_stem()
@@ -161,6 +739,8 @@ pub async fn get_csv_chunk(
     path: String,
     start_row: usize,
     end_row: usize,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<Vec<Vec<String>>, AppError> {
     let path = Path::new(&path);
 
@@ -171,7 +751,11 @@ pub async fn get_csv_chunk(
         ));
     }
 
-    let mut reader = CsvReader::new();
+    // Configured and prepared like `open_csv_file`, so the delimiter, header
+    // row, encoding and skipped lines give the same row offsets
+    let saved_metadata = state.lock().await.metadata_manager.load_metadata(path).ok();
+    let mut reader = build_reader(&OpenOptions::default(), saved_metadata.as_ref(), &settings).await?;
+    reader.prepare(path)?;
     let chunk = reader.read_chunk(path, start_row, end_row)?;
 
     Ok(chunk)
@@ -208,19 +792,24 @@ pub async fn validate_csv_file(path: String) -> Result<bool, AppError> {
         ));
     }
 
-    // Check file extension
-    if let Some(extension) = path.extension() {
-        let ext = extension.to_string_lossy().to_lowercase();
-        if !["csv", "tsv"].contains(&ext.as_str()) {
-            return Ok(false);
+    // Check file extension, looking through compression suffixes such as
+    // `.csv.gz`; zip archives are accepted and their CSV member is read
+    if Compression::from_extension(path) != Compression::Zip {
+        let inner_name = compression::inner_file_name(path);
+        match Path::new(&inner_name).extension() {
+            Some(extension) => {
+                let ext = extension.to_string_lossy().to_lowercase();
+                if !["csv", "tsv"].contains(&ext.as_str()) {
+                    return Ok(false);
+                }
+            }
+            None => return Ok(false),
         }
-    } else {
-        return Ok(false);
     }
 
     // Try to read first few lines to validate CSV format
     let mut reader = CsvReader::new();
-    match reader.read_chunk(path, 0, 5) {
+    match reader.prepare(path).and_then(|_| reader.read_chunk(path, 0, 5)) {
         Ok(_) => Ok(true),
         Err(_) => Ok(false),
     }
@@ -231,6 +820,7 @@ pub async fn add_column(
     mut data: CsvData,
     column_name: String,
     position: Option<usize>,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     // Add to headers
     let position = position.unwrap_or(data.headers.len());
@@ -250,6 +840,8 @@ pub async fn add_column(
     // Update metadata
     data.metadata.column_count = data.headers.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -257,6 +849,7 @@ pub async fn add_column(
 pub async fn delete_column(
     mut data: CsvData,
     column_index: usize,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     if column_index >= data.headers.len() {
         return Err(AppError::new(
@@ -265,8 +858,9 @@ pub async fn delete_column(
         ));
     }
 
-    // Remove from headers
-    data.headers.remove(column_index);
+    // Remove from headers, with the type declared for the column
+    let removed = data.headers.remove(column_index);
+    data.metadata.column_types.remove(&removed);
 
     // Remove from all rows
     for row in &mut data.rows {
@@ -278,6 +872,8 @@ pub async fn delete_column(
     // Update metadata
     data.metadata.column_count = data.headers.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -294,7 +890,11 @@ pub async fn rename_column(
         ));
     }
 
-    data.headers[column_index] = new_name;
+    // A declared type follows the column to its new name
+    let old_name = std::mem::replace(&mut data.headers[column_index], new_name.clone());
+    if let Some(column_type) = data.metadata.column_types.remove(&old_name) {
+        data.metadata.column_types.insert(new_name, column_type);
+    }
 
     Ok(data)
 }
@@ -303,6 +903,7 @@ pub async fn rename_column(
 pub async fn add_row(
     mut data: CsvData,
     row_index: Option<usize>,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     let new_row = vec![String::new(); data.headers.len()];
     let position = row_index.unwrap_or(data.rows.len());
@@ -317,6 +918,8 @@ pub async fn add_row(
     data.rows.insert(position, new_row);
     data.metadata.row_count = data.rows.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -324,6 +927,7 @@ pub async fn add_row(
 pub async fn delete_row(
     mut data: CsvData,
     row_index: usize,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     if row_index >= data.rows.len() {
         return Err(AppError::new(
@@ -335,6 +939,8 @@ pub async fn delete_row(
     data.rows.remove(row_index);
     data.metadata.row_count = data.rows.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -342,6 +948,7 @@ pub async fn delete_row(
 pub async fn duplicate_row(
     mut data: CsvData,
     row_index: usize,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     if row_index >= data.rows.len() {
         return Err(AppError::new(
@@ -354,6 +961,8 @@ pub async fn duplicate_row(
     data.rows.insert(row_index + 1, row_to_duplicate);
     data.metadata.row_count = data.rows.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -363,37 +972,56 @@ pub struct ColumnTypeInfo {
     pub column_name: String,
     pub detected_type: DataType,
     pub sample_values: Vec<String>,
+    /// Type declared in the metadata, which takes precedence over `detected_type`
+    #[serde(default)]
+    pub declared_type: Option<ColumnTypeOverride>,
+    /// Share of sampled values valid as `detected_type`
+    #[serde(default)]
+    pub confidence: f64,
+    #[serde(default)]
+    pub candidates: Vec<TypeCandidate>,
+    /// Sampled rows whose value does not fit `detected_type`
+    #[serde(default)]
+    pub nonconforming_rows: Vec<usize>,
+    #[serde(default)]
+    pub ambiguities: Vec<TypeAmbiguity>,
 }
 
 #[tauri::command]
 pub async fn detect_column_types(
     data: CsvData,
+    settings: State<'_, SettingsState>,
 ) -> Result<Vec<ColumnTypeInfo>, AppError> {
-    let detector = DataTypeDetector::new();
-    let mut column_types = Vec::new();
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let detector = DataTypeDetector::new().with_locale(locale);
 
-    for (index, header) in data.headers.iter().enumerate() {
-        let column_values: Vec<String> = data.rows
-            .iter()
-            .take(100) // Sample first 100 rows for type detection
-            .filter_map(|row| row.get(index))
-            .cloned()
-            .collect();
+    // Sample first 100 rows for type detection
+    let inferences = detector.infer_column_types(&data.rows, data.headers.len(), 100);
+    let declared = data.metadata.column_type_overrides(&data.headers);
 
-        let detected_type = detector.detect_column_type(&column_values);
-
-        let sample_values = column_values
-            .into_iter()
-            .take(5)
-            .collect();
-
-        column_types.push(ColumnTypeInfo {
+    let column_types = data.headers
+        .iter()
+        .zip(inferences)
+        .zip(declared)
+        .enumerate()
+        .map(|(index, ((header, inference), declared_type))| ColumnTypeInfo {
             column_index: index,
             column_name: header.clone(),
-            detected_type,
-            sample_values,
-        });
-    }
+            detected_type: inference.data_type,
+            sample_values: data.rows
+                .iter()
+                .take(100)
+                .filter_map(|row| row.get(index))
+                .take(5)
+                .cloned()
+                .collect(),
+            declared_type,
+            confidence: inference.confidence,
+            candidates: inference.candidates,
+            nonconforming_rows: inference.nonconforming_rows,
+            ambiguities: inference.ambiguities,
+        })
+        .collect();
 
     Ok(column_types)
 }
@@ -417,26 +1045,41 @@ pub struct ValidationError {
 pub async fn validate_data_types(
     data: CsvData,
     column_types: Vec<(usize, DataType)>,
+    settings: State<'_, SettingsState>,
 ) -> Result<ValidationResult, AppError> {
-    let detector = DataTypeDetector::new();
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let detector = DataTypeDetector::new().with_locale(locale);
     let mut errors = Vec::new();
 
+    // Requested types use the column's declared formats when the declaration
+    // agrees; declared columns that were not requested are checked as well
+    let declared = data.metadata.column_type_overrides(&data.headers);
+    let mut checks: Vec<(usize, ColumnTypeOverride)> = column_types
+        .into_iter()
+        .map(|(column_index, expected_type)| match declared.get(column_index) {
+            Some(Some(declared)) if declared.data_type == expected_type => (column_index, declared.clone()),
+            _ => (column_index, ColumnTypeOverride::new(expected_type)),
+        })
+        .collect();
+    for (column_index, declared) in declared.into_iter().enumerate() {
+        if let Some(declared) = declared {
+            if !checks.iter().any(|(index, _)| *index == column_index) {
+                checks.push((column_index, declared));
+            }
+        }
+    }
+
     for (row_index, row) in data.rows.iter().enumerate() {
-        for (column_index, expected_type) in &column_types {
-            if let Some(value) = row.get(*column_index) {
-                if !value.is_empty() && !detector.validate_value(value, expected_type) {
-                    errors.push(ValidationError {
-                        row_index,
-                        column_index: *column_index,
-                        value: value.clone(),
-                        expected_type: expected_type.clone(),
-                        message: format!(
-                            "Value '{}' is not a valid {}",
-                            value,
-                            format!("{:?}", expected_type).to_lowercase()
-                        ),
-                    });
-                }
+        for (column_index, expected) in &checks {
+            let value = row.get(*column_index).map(|v| v.as_str()).unwrap_or_default();
+            if let Some(message) = expected.check(value, &detector) {
+                errors.push(ValidationError {
+                    row_index,
+                    column_index: *column_index,
+                    value: value.to_string(),
+                    expected_type: expected.data_type.clone(),
+                    message,
+                });
             }
         }
     }
@@ -469,8 +1112,6 @@ pub async fn find_in_csv(
     data: CsvData,
     options: FindOptions,
 ) -> Result<Vec<SearchResult>, AppError> {
-    let mut results = Vec::new();
-
     let search_regex = if options.regex {
         match regex::Regex::new(&options.search_text) {
             Ok(re) => Some(re),
@@ -483,54 +1124,61 @@ pub async fn find_in_csv(
         None
     };
 
-    for (row_index, row) in data.rows.iter().enumerate() {
+    Ok(search_rows(&data.rows, &options, search_regex.as_ref()))
+}
+
+/// Cells matching `options`, in row order. Rows are searched in parallel.
+pub fn search_rows(rows: &[Vec<String>], options: &FindOptions, search_regex: Option<&regex::Regex>) -> Vec<SearchResult> {
+    let lowercase_search = options.search_text.to_lowercase();
+
+    rows.par_iter().enumerate().flat_map_iter(|(row_index, row)| {
         let columns_to_search = if let Some(col_idx) = options.column_index {
             vec![col_idx]
         } else {
             (0..row.len()).collect()
         };
-
-        for column_index in columns_to_search {
-            if let Some(value) = row.get(column_index) {
-                let matches = if let Some(ref regex) = search_regex {
-                    regex.is_match(value)
-                } else if options.whole_word {
-                    let search = if options.case_sensitive {
-                        &options.search_text
-                    } else {
-                        &options.search_text.to_lowercase()
-                    };
-                    let val = if options.case_sensitive {
-                        value.clone()
-                    } else {
-                        value.to_lowercase()
-                    };
-                    val.split_whitespace().any(|word| word == search)
-                } else if options.case_sensitive {
-                    value.contains(&options.search_text)
-                } else {
-                    value.to_lowercase().contains(&options.search_text.to_lowercase())
-                };
-
-                if matches {
-                    let context = if value.len() > 100 {
-                        format!("{}...", &value[..100])
-                    } else {
-                        value.clone()
-                    };
-
-                    results.push(SearchResult {
-                        row_index,
-                        column_index,
-                        value: value.clone(),
-                        context,
-                    });
-                }
+
+        let lowercase_search = &lowercase_search;
+        columns_to_search.into_iter().filter_map(move |column_index| {
+            let value = row.get(column_index)?;
+            let matches = if let Some(regex) = search_regex {
+                regex.is_match(value)
+            } else if options.whole_word {
+                let search = if options.case_sensitive {
+                    &options.search_text
+                } else {
+                    lowercase_search
+                };
+                let val = if options.case_sensitive {
+                    value.clone()
+                } else {
+                    value.to_lowercase()
+                };
+                val.split_whitespace().any(|word| word == search)
+            } else if options.case_sensitive {
+                value.contains(&options.search_text)
+            } else {
+                value.to_lowercase().contains(lowercase_search.as_str())
+            };
+
+            if !matches {
+                return None;
             }
-        }
-    }
 
-    Ok(results)
+            let context = if value.len() > 100 {
+                format!("{}...", &value[..100])
+            } else {
+                value.clone()
+            };
+
+            Some(SearchResult {
+                row_index,
+                column_index,
+                value: value.clone(),
+                context,
+            })
+        })
+    }).collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -576,6 +1224,8 @@ pub struct SortState {
 pub async fn replace_in_csv(
     mut data: CsvData,
     options: ReplaceOptions,
+    window: tauri::Window,
+    state: State<'_, AppState>,
 ) -> Result<ReplaceResult, AppError> {
     let mut preview = Vec::new();
     let mut replaced_count = 0;
@@ -661,6 +1311,11 @@ pub async fn replace_in_csv(
         }
     }
 
+    if !options.preview_only {
+        let edits = preview.iter().map(|p| (p.row_index, p.column_index, p.new_value.clone()));
+        live_validate_edits(&mut *state.lock().await, edits, &window)?;
+    }
+
     Ok(ReplaceResult {
         replaced_count,
         data: if options.preview_only { None } else { Some(data) },
@@ -668,11 +1323,27 @@ pub async fn replace_in_csv(
     })
 }
 
-fn compare_values(a: &str, b: &str, direction: &SortDirection) -> std::cmp::Ordering {
+fn compare_values(
+    a: &str,
+    b: &str,
+    direction: &SortDirection,
+    declared: Option<&ColumnTypeOverride>,
+    locale: &LocaleFormat,
+) -> std::cmp::Ordering {
     use std::cmp::Ordering;
 
+    // A declared type decides how values compare, so zip codes declared
+    // as text keep their text order
+    if let Some(declared) = declared {
+        let cmp = declared.compare(a, b);
+        return match direction {
+            SortDirection::Ascending => cmp,
+            SortDirection::Descending => cmp.reverse(),
+        };
+    }
+
     // Try to parse as numbers first
-    if let (Ok(num_a), Ok(num_b)) = (a.parse::<f64>(), b.parse::<f64>()) {
+    if let (Some(num_a), Some(num_b)) = (locale.parse_number(a), locale.parse_number(b)) {
         let cmp = num_a.partial_cmp(&num_b).unwrap_or(Ordering::Equal);
         return match direction {
             SortDirection::Ascending => cmp,
@@ -681,10 +1352,7 @@ fn compare_values(a: &str, b: &str, direction: &SortDirection) -> std::cmp::Orde
     }
 
     // Try to parse as dates
-    if let (Ok(date_a), Ok(date_b)) = (
-        chrono::NaiveDate::parse_from_str(a, "%Y-%m-%d").or_else(|_| chrono::NaiveDate::parse_from_str(a, "%m/%d/%Y")),
-        chrono::NaiveDate::parse_from_str(b, "%Y-%m-%d").or_else(|_| chrono::NaiveDate::parse_from_str(b, "%m/%d/%Y")),
-    ) {
+    if let (Some(date_a), Some(date_b)) = (locale.parse_date(a), locale.parse_date(b)) {
         let cmp = date_a.cmp(&date_b);
         return match direction {
             SortDirection::Ascending => cmp,
@@ -704,6 +1372,8 @@ fn compare_values(a: &str, b: &str, direction: &SortDirection) -> std::cmp::Orde
 pub async fn sort_csv_data(
     mut data: CsvData,
     sort_state: SortState,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<CsvData, AppError> {
     if sort_state.columns.is_empty() {
         return Ok(data);
@@ -719,6 +1389,27 @@ pub async fn sort_csv_data(
         }
     }
 
+    // Sort columns without a declared type that hold currency, times, IP
+    // addresses and the like compare by their parsed values
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let mut declared = data.metadata.column_type_overrides(&data.headers);
+    let detector = DataTypeDetector::new().with_locale(locale.clone());
+    for sort_col in &sort_state.columns {
+        let index = sort_col.column_index;
+        if declared[index].is_none() {
+            let sample: Vec<String> = data.rows
+                .iter()
+                .take(100)
+                .filter_map(|row| row.get(index))
+                .cloned()
+                .collect();
+            let detected = detector.detect_column_type(&sample);
+            if detected.has_parsed_form() {
+                declared[index] = Some(ColumnTypeOverride::new(detected));
+            }
+        }
+    }
+
     // Create vector of (original_index, row) tuples for stable sorting
     let mut rows_with_indices: Vec<(usize, &Vec<String>)> = data.rows
         .iter()
@@ -731,7 +1422,13 @@ pub async fn sort_csv_data(
             let val_a = a.1.get(sort_col.column_index).map(|s| s.as_str()).unwrap_or("");
             let val_b = b.1.get(sort_col.column_index).map(|s| s.as_str()).unwrap_or("");
 
-            let cmp = compare_values(val_a, val_b, &sort_col.direction);
+            let cmp = compare_values(
+                val_a,
+                val_b,
+                &sort_col.direction,
+                declared[sort_col.column_index].as_ref(),
+                &locale,
+            );
             if cmp != std::cmp::Ordering::Equal {
                 return cmp;
             }
@@ -747,6 +1444,8 @@ pub async fn sort_csv_data(
         .map(|(_, row)| row.clone())
         .collect();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -785,6 +1484,66 @@ pub async fn load_sort_state(
     Ok(metadata.sort_state)
 }
 
+/// Declare the type of a column, or with `None` go back to detection.
+/// Returns every declaration now stored for the file.
+#[tauri::command]
+pub async fn set_column_type(
+    path: String,
+    column_name: String,
+    column_type: Option<ColumnTypeOverride>,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, ColumnTypeOverride>, AppError> {
+    let path = Path::new(&path);
+
+    let mut state = state.lock().await;
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+
+    match column_type {
+        Some(column_type) => {
+            metadata.column_types.insert(column_name, column_type);
+        }
+        None => {
+            metadata.column_types.remove(&column_name);
+        }
+    }
+
+    state.metadata_manager.save_metadata(path, &metadata)?;
+
+    Ok(metadata.column_types)
+}
+
+/// Set the number and date format of one file, or with `None` go back to
+/// the app settings. Returns the format the file is now read with.
+#[tauri::command]
+pub async fn set_file_locale(
+    path: String,
+    locale: Option<LocaleFormat>,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+) -> Result<LocaleFormat, AppError> {
+    let path = Path::new(&path);
+
+    let mut state = state.lock().await;
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+    metadata.locale = locale;
+    state.metadata_manager.save_metadata(path, &metadata)?;
+
+    Ok(resolve_locale(Some(&metadata), &settings).await)
+}
+
+#[tauri::command]
+pub async fn load_column_types(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, ColumnTypeOverride>, AppError> {
+    let path = Path::new(&path);
+
+    let mut state = state.lock().await;
+    let metadata = state.metadata_manager.load_metadata(path)?;
+
+    Ok(metadata.column_types)
+}
+
 #[tauri::command]
 pub async fn save_view_state(
     path: String,
@@ -825,6 +1584,7 @@ pub async fn move_row(
     mut data: CsvData,
     from_index: usize,
     to_index: usize,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     if from_index >= data.rows.len() || to_index > data.rows.len() {
         return Err(AppError::new(
@@ -850,6 +1610,8 @@ pub async fn move_row(
     // Insert the row at the new position
     data.rows.insert(insert_index, row_to_move);
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -858,6 +1620,7 @@ pub async fn move_column(
     mut data: CsvData,
     from_index: usize,
     to_index: usize,
+    state: State<'_, AppState>,
 ) -> Result<CsvData, AppError> {
     if from_index >= data.headers.len() || to_index > data.headers.len() {
         return Err(AppError::new(
@@ -896,6 +1659,8 @@ pub async fn move_column(
     // Update metadata
     data.metadata.column_count = data.headers.len();
 
+    reset_live_validation(&state).await;
+
     Ok(data)
 }
 
@@ -904,31 +1669,478 @@ pub async fn move_column(
 pub async fn validate_with_rules(
     data: CsvData,
     rules: Vec<ValidationRule>,
+    settings: State<'_, SettingsState>,
 ) -> Result<Vec<CustomValidationError>, AppError> {
-    let validator = Validator::new(rules);
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let validator = Validator::new(rules).with_locale(locale);
     let errors = validator.validate(&data.rows, &data.headers);
     Ok(errors)
 }
 
+/// Check a custom rule expression against the headers before it is saved.
+/// Returns the names of the columns it reads.
+#[tauri::command]
+pub async fn check_rule_expression(
+    expression: String,
+    headers: Vec<String>,
+) -> Result<Vec<String>, AppError> {
+    let expression = Expression::compile(&expression, &headers)
+        .map_err(|e| AppError::new(e.to_string(), "INVALID_EXPRESSION"))?;
+    Ok(expression.columns().iter().map(|&i| headers[i].clone()).collect())
+}
+
+/// Register rules for the open document and return its current errors.
+/// Later edits are checked with `live_validate_cell`.
+#[tauri::command]
+pub async fn start_live_validation(
+    data: CsvData,
+    rules: Vec<ValidationRule>,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<CustomValidationError>, AppError> {
+    let base_dir = Path::new(&data.metadata.path).parent().map(Path::to_path_buf);
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+
+    // The initial pass validates every row and reads referenced files
+    let validator = tokio::task::spawn_blocking(move || {
+        LiveValidator::new(data.headers, data.rows, rules, base_dir, locale)
+    })
+    .await
+    .map_err(|e| AppError::new(format!("Live validation failed: {}", e), "LIVE_VALIDATION_ERROR"))?;
+    let errors = validator.errors();
+
+    let mut state = state.lock().await;
+    state.live_validator = Some(validator);
+    Ok(errors)
+}
+
+/// Re-check the rows a cell edit affects and emit the errors it added and
+/// cleared as a "validation-diff" event
+#[tauri::command]
+pub async fn live_validate_cell(
+    row_index: usize,
+    column_index: usize,
+    value: String,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<ValidationDiff, AppError> {
+    let mut state = state.lock().await;
+    let validator = state.live_validator.as_mut().ok_or_else(|| {
+        AppError::new("Live validation has not been started".to_string(), "LIVE_VALIDATION_NOT_STARTED")
+    })?;
+
+    let diff = validator.update_cell(row_index, column_index, value)?;
+    if !diff.is_empty() {
+        let _ = window.emit("validation-diff", &diff);
+    }
+    Ok(diff)
+}
+
+/// Feed cell edits made by a command into live validation, when it is
+/// running, and emit the errors they added and cleared together
+fn live_validate_edits(
+    state: &mut AppStateInner,
+    edits: impl IntoIterator<Item = (usize, usize, String)>,
+    window: &tauri::Window,
+) -> Result<(), AppError> {
+    let Some(validator) = state.live_validator.as_mut() else {
+        return Ok(());
+    };
+
+    let mut diff = ValidationDiff::default();
+    for (row_index, column_index, value) in edits {
+        diff.merge(validator.update_cell(row_index, column_index, value)?);
+    }
+    if !diff.is_empty() {
+        let _ = window.emit("validation-diff", &diff);
+    }
+    Ok(())
+}
+
+/// Rules and errors refer to row and column indices, so live validation
+/// stops when rows or columns are inserted, removed or reordered and is
+/// started again for the new layout
+async fn reset_live_validation(state: &State<'_, AppState>) {
+    state.lock().await.live_validator = None;
+}
+
+#[tauri::command]
+pub async fn stop_live_validation(state: State<'_, AppState>) -> Result<(), AppError> {
+    let mut state = state.lock().await;
+    state.live_validator = None;
+    Ok(())
+}
+
+/// Schema file path as stored in metadata, resolved against the CSV file
+fn resolve_schema_path(csv_path: &Path, schema_path: &str) -> PathBuf {
+    let schema_path = Path::new(schema_path);
+    match csv_path.parent() {
+        Some(dir) if schema_path.is_relative() => dir.join(schema_path),
+        _ => schema_path.to_path_buf(),
+    }
+}
+
+/// Validate `data` against its referenced schema, if any, and report the
+/// outcome as a `schema-validation` event
+fn emit_schema_validation(
+    window: &tauri::Window,
+    csv_path: &Path,
+    schema_path: Option<&str>,
+    data: &CsvData,
+    locale: &LocaleFormat,
+) {
+    let Some(schema_path) = schema_path else {
+        return;
+    };
+    let schema_path = resolve_schema_path(csv_path, schema_path);
+
+    let payload = match TableSchema::load(&schema_path) {
+        Ok(schema) => serde_json::json!({
+            "path": csv_path,
+            "schemaPath": schema_path,
+            "validation": schema.validate(&data.headers, &data.rows, locale, schema_path.parent()),
+        }),
+        Err(e) => serde_json::json!({
+            "path": csv_path,
+            "schemaPath": schema_path,
+            "error": AppError::new(format!("Failed to load schema: {}", e), "SCHEMA_ERROR"),
+        }),
+    };
+    let _ = window.emit("schema-validation", payload);
+}
+
+#[tauri::command]
+pub async fn load_schema(path: String) -> Result<TableSchema, AppError> {
+    Ok(TableSchema::load(Path::new(&path))?)
+}
+
+#[tauri::command]
+pub async fn save_schema(
+    path: String,
+    schema: TableSchema,
+    format: Option<SchemaFormat>,
+) -> Result<(), AppError> {
+    schema.save(Path::new(&path), format.unwrap_or_default())?;
+    Ok(())
+}
+
+/// Reference a schema file from the CSV file's metadata, or remove the
+/// reference when `schema_path` is `None`
+#[tauri::command]
+pub async fn set_schema_path(
+    path: String,
+    schema_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let path = Path::new(&path);
+
+    if let Some(schema_path) = &schema_path {
+        TableSchema::load(&resolve_schema_path(path, schema_path))?;
+    }
+
+    let mut state = state.lock().await;
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+    metadata.schema_path = schema_path;
+    state.metadata_manager.save_metadata(path, &metadata)?;
+
+    Ok(())
+}
+
+/// Draft schema from the data's detected types, to be edited and saved
+#[tauri::command]
+pub async fn generate_schema_draft(
+    data: CsvData,
+    settings: State<'_, SettingsState>,
+) -> Result<TableSchema, AppError> {
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let detector = DataTypeDetector::new().with_locale(locale);
+    Ok(TableSchema::draft(&data.headers, &data.rows, &detector))
+}
+
+/// Validate against `schema`, or the schema referenced by the file's metadata
+#[tauri::command]
+pub async fn validate_with_schema(
+    data: CsvData,
+    schema: Option<TableSchema>,
+    settings: State<'_, SettingsState>,
+) -> Result<SchemaValidation, AppError> {
+    let csv_path = Path::new(&data.metadata.path);
+    let (schema, schema_path) = match (schema, data.metadata.schema_path.as_deref()) {
+        (Some(schema), _) => (schema, csv_path.to_path_buf()),
+        (None, Some(schema_path)) => {
+            let schema_path = resolve_schema_path(csv_path, schema_path);
+            (TableSchema::load(&schema_path)?, schema_path)
+        }
+        (None, None) => {
+            return Err(AppError::new("No schema is set for this file".to_string(), "NO_SCHEMA"));
+        }
+    };
+
+    // Foreign keys resolve next to the schema, or the file for an unsaved schema
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    Ok(schema.validate(&data.headers, &data.rows, &locale, schema_path.parent()))
+}
+
 // Data Quality Report
 #[tauri::command]
 pub async fn generate_quality_report(
     data: CsvData,
-) -> Result<crate::csv_engine::quality::QualityReport, AppError> {
-    let report = QualityAnalyzer::analyze(&data.rows, &data.headers);
+    rules: Option<Vec<ValidationRule>>,
+    config: Option<QualityConfig>,
+    settings: State<'_, SettingsState>,
+) -> Result<QualityReport, AppError> {
+    // Scored with the given config, else the file's saved one
+    let config = config.or_else(|| data.metadata.quality_config.clone()).unwrap_or_default();
+    let declared = data.metadata.column_type_overrides(&data.headers);
+    let mut report =
+        QualityAnalyzer::analyze_with_outlier_methods(&data.rows, &data.headers, &declared, &config.outlier_methods);
+
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let errors = rules.as_ref().map(|rules| {
+        let mut validator = Validator::new(rules.clone()).with_locale(locale.clone());
+        if let Some(dir) = Path::new(&data.metadata.path).parent() {
+            validator = validator.with_base_dir(dir);
+        }
+        validator.validate(&data.rows, &data.headers)
+    });
+    let validation = rules.as_deref().zip(errors.as_deref());
+    QualityAnalyzer::score(&mut report, &data.rows, &declared, validation, &config, &locale);
+
     Ok(report)
 }
 
+/// Save the weights and thresholds quality reports for a file are scored with
+#[tauri::command]
+pub async fn set_quality_config(
+    path: String,
+    config: Option<QualityConfig>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let path = Path::new(&path);
+
+    let mut state = state.lock().await;
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+    metadata.quality_config = config;
+    state.metadata_manager.save_metadata(path, &metadata)?;
+
+    Ok(())
+}
+
+/// Keep a snapshot of `report` in the file's metadata, keyed by the
+/// version of `data`, the data it was made from, for `get_quality_trend`
+#[tauri::command]
+pub async fn record_quality_snapshot(
+    path: String,
+    data: CsvData,
+    report: QualityReport,
+    state: State<'_, AppState>,
+) -> Result<QualitySnapshot, AppError> {
+    let path = Path::new(&path);
+    let version = FileVersion::of_data(path, &data.headers, &data.rows);
+    let snapshot = QualitySnapshot::from_report(&report, version);
+
+    let mut state = state.lock().await;
+    let mut metadata = state.metadata_manager.load_metadata(path)?;
+    record_snapshot(&mut metadata.quality_history, snapshot.clone());
+    state.metadata_manager.save_metadata(path, &metadata)?;
+
+    Ok(snapshot)
+}
+
+/// Completeness, distinct count, type consistency and outlier count of
+/// each column across recorded versions, with significant changes flagged
+#[tauri::command]
+pub async fn get_quality_trend(
+    path: String,
+    thresholds: Option<TrendThresholds>,
+    state: State<'_, AppState>,
+) -> Result<QualityTrend, AppError> {
+    let mut state = state.lock().await;
+    let metadata = state.metadata_manager.load_metadata(Path::new(&path))?;
+    Ok(quality_trend(&metadata.quality_history, &thresholds.unwrap_or_default()))
+}
+
+/// Write a quality report as JSON or HTML, to attach to a data delivery
+#[tauri::command]
+pub async fn export_quality_report(
+    path: String,
+    report: QualityReport,
+    source: String,
+    format: ReportFormat,
+) -> Result<(), AppError> {
+    report
+        .write(Path::new(&path), &source, format)
+        .map_err(|e| AppError::new(e.to_string(), "EXPORT_ERROR"))?;
+    Ok(())
+}
+
+/// Profile every column of loaded data
+#[tauri::command]
+pub async fn profile_columns(
+    data: CsvData,
+    options: Option<ProfileOptions>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<ColumnProfile>, AppError> {
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let mut profiler = DataProfiler::new(data.headers, &options.unwrap_or_default()).with_locale(locale);
+    profiler.observe_rows(&data.rows);
+    Ok(profiler.finish())
+}
+
+/// Profile every column of a file in one streaming pass, without loading it
+#[tauri::command]
+pub async fn profile_file(
+    path: String,
+    options: Option<ProfileOptions>,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+) -> Result<Vec<ColumnProfile>, AppError> {
+    let path = PathBuf::from(&path);
+    let saved_metadata = state.lock().await.metadata_manager.load_metadata(&path).ok();
+    let locale = resolve_locale(saved_metadata.as_ref(), &settings).await;
+    let mut reader = build_reader(&OpenOptions::default(), saved_metadata.as_ref(), &settings).await?;
+    let options = options.unwrap_or_default();
+
+    // The whole file is read, so it is scanned off the async runtime
+    tokio::task::spawn_blocking(move || {
+        reader.prepare(&path)?;
+
+        let delimiter = match reader.delimiter().as_bytes() {
+            [delimiter] => *delimiter,
+            _ => {
+                return Err(AppError::new(
+                    format!("Delimiter {:?} is not supported for streaming", reader.delimiter()),
+                    "UNSUPPORTED_DELIMITER",
+                ))
+            }
+        };
+        let mut streaming = StreamingReader::new(&path)
+            .with_delimiter(delimiter)
+            .with_headers(false)
+            .with_encoding(reader.encoding())
+            .with_dialect(reader.dialect().clone())
+            .with_chunk_size(10_000);
+        if let Some(compression) = reader.compression() {
+            streaming = streaming.with_compression(compression);
+        }
+        if let Some(member) = reader.archive_member() {
+            streaming = streaming.with_archive_member(member.to_string());
+        }
+
+        // Headers come from the first row, or are numbered when there is none
+        let has_headers = reader.has_headers();
+        let mut profiler: Option<DataProfiler> = None;
+        streaming.stream_chunks(|mut rows| {
+            let profiler = match &mut profiler {
+                Some(profiler) => profiler,
+                None => {
+                    let new_profiler = match has_headers {
+                        true => DataProfiler::new(rows.remove(0), &options),
+                        false => DataProfiler::numbered(&options),
+                    };
+                    profiler.insert(new_profiler.with_locale(locale.clone()))
+                }
+            };
+            profiler.observe_rows(&rows);
+            Ok(true)
+        })?;
+
+        Ok(profiler.map(DataProfiler::finish).unwrap_or_default())
+    })
+    .await
+    .map_err(|e| AppError::new(format!("Profiling failed: {}", e), "PROFILE_ERROR"))?
+}
+
+/// Find clusters of near-duplicate rows on the chosen columns
+#[tauri::command]
+pub async fn find_fuzzy_duplicates(
+    data: CsvData,
+    options: FuzzyMatchOptions,
+) -> Result<FuzzyDuplicateReport, AppError> {
+    if let Some(column) = options.columns.iter().find(|c| c.column_index >= data.headers.len()) {
+        return Err(AppError::new(
+            format!("Column {} does not exist", column.column_index),
+            "INVALID_COLUMN",
+        ));
+    }
+    if let Some(Blocking::Prefix { column_index, .. } | Blocking::Tokens { column_index }) = &options.blocking {
+        if *column_index >= data.headers.len() {
+            return Err(AppError::new(
+                format!("Column {} does not exist", column_index),
+                "INVALID_COLUMN",
+            ));
+        }
+    }
+    Ok(FuzzyMatcher::new(options).find_duplicates(&data.rows))
+}
+
+/// Keep one row of each cluster found by `find_fuzzy_duplicates`
+#[tauri::command]
+pub async fn merge_fuzzy_duplicates(
+    mut data: CsvData,
+    clusters: Vec<DuplicateCluster>,
+    policy: SurvivorPolicy,
+    fill_empty: Option<bool>,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
+) -> Result<(CsvData, CleansingResult), AppError> {
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let (removed, merged) = merge_clusters(&mut data.rows, &clusters, &policy, fill_empty.unwrap_or(false), &locale);
+
+    let result = CleansingResult {
+        rows_affected: removed.len(),
+        cells_modified: merged.len(),
+        modifications: merged
+            .into_iter()
+            .map(|cell| ModificationDetail {
+                row_index: cell.row_index,
+                column_index: cell.column_index,
+                old_value: cell.old_value,
+                new_value: cell.value,
+            })
+            .collect(),
+        removed_rows: removed,
+        added_columns: Vec::new(),
+    };
+    data.metadata.row_count = data.rows.len();
+    reset_live_validation(&state).await;
+
+    Ok((data, result))
+}
+
 // Data Cleansing
 #[tauri::command]
 pub async fn cleanse_data(
     mut data: CsvData,
-    options: CleansingOptions,
+    mut options: CleansingOptions,
+    state: State<'_, AppState>,
+    settings: State<'_, SettingsState>,
 ) -> Result<(CsvData, CleansingResult), AppError> {
-    let result = DataCleanser::cleanse(&mut data.rows, &data.headers, &options);
+    // Standardizing must not touch columns declared as text, which would
+    // otherwise turn zip code 00501 into 501
+    if matches!(options.action, CleansingAction::StandardizeFormat) {
+        let declared = data.metadata.column_type_overrides(&data.headers);
+        let is_text = |index: &usize| {
+            matches!(declared.get(*index), Some(Some(d)) if d.data_type == DataType::Text)
+        };
+        if declared.iter().flatten().any(|d| d.data_type == DataType::Text) {
+            let indices = options
+                .column_indices
+                .take()
+                .unwrap_or_else(|| (0..data.headers.len()).collect());
+            options.column_indices = Some(indices.into_iter().filter(|i| !is_text(i)).collect());
+        }
+    }
+
+    let locale = resolve_locale(Some(&data.metadata), &settings).await;
+    let result = DataCleanser::cleanse_with_locale(&mut data.rows, &data.headers, &options, &locale);
+    data.headers.extend(result.added_columns.iter().cloned());
 
     // Update metadata
     data.metadata.row_count = data.rows.len();
+    data.metadata.column_count = data.headers.len();
+
+    reset_live_validation(&state).await;
 
     Ok((data, result))
 }
@@ -945,6 +2157,62 @@ pub async fn export_data(
     Ok(())
 }
 
+/// Rule errors and data type errors as report entries
+fn report_entries(
+    headers: &[String],
+    errors: &[CustomValidationError],
+    type_errors: &[ValidationError],
+) -> Vec<ReportEntry> {
+    let type_entries = type_errors.iter().map(|error| ReportEntry {
+        row_index: error.row_index,
+        column_index: error.column_index,
+        column_name: headers.get(error.column_index).cloned().unwrap_or_default(),
+        value: error.value.clone(),
+        rule: "type".to_string(),
+        message: error.message.clone(),
+        rule_error: false,
+    });
+    errors.iter().map(ReportEntry::from).chain(type_entries).collect()
+}
+
+/// Write the results of `validate_with_rules` and `validate_data_types` as
+/// a CSV, JSON or HTML report
+#[tauri::command]
+pub async fn export_validation_report(
+    path: String,
+    data: CsvData,
+    errors: Vec<CustomValidationError>,
+    type_errors: Option<Vec<ValidationError>>,
+    format: ReportFormat,
+) -> Result<(), AppError> {
+    let entries = report_entries(&data.headers, &errors, &type_errors.unwrap_or_default());
+    let source = Path::new(&data.metadata.path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| data.metadata.path.clone());
+
+    ValidationReport::new(&source, data.rows.len(), entries).write(Path::new(&path), format)?;
+    Ok(())
+}
+
+/// Export only the rows with validation or data type errors
+#[tauri::command]
+pub async fn export_failing_rows(
+    path: String,
+    data: CsvData,
+    errors: Vec<CustomValidationError>,
+    type_errors: Option<Vec<ValidationError>>,
+    options: ExportOptions,
+) -> Result<(), AppError> {
+    let entries = report_entries(&data.headers, &errors, &type_errors.unwrap_or_default());
+    let failing = ValidationReport::row_indices(&entries);
+    let rows = failing.into_iter().filter_map(|i| data.rows.get(i).cloned()).collect();
+
+    let failing_data = CsvData { rows, ..data };
+    Exporter::export(Path::new(&path), &failing_data, &options)?;
+    Ok(())
+}
+
 // Generate export preview
 #[tauri::command]
 pub async fn generate_export_preview(
@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use super::dedup::SurvivorPolicy;
+use super::locale::LocaleFormat;
+use super::outliers::{find_outliers, OutlierMethod};
+use super::profiler::shape_pattern;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CleansingAction {
@@ -24,6 +29,18 @@ pub struct CleansingResult {
     pub rows_affected: usize,
     pub cells_modified: usize,
     pub modifications: Vec<ModificationDetail>,
+    /// Rows dropped by the action, with their original index and values
+    #[serde(default)]
+    pub removed_rows: Vec<RemovedRow>,
+    /// Headers of columns the action appended to every row
+    #[serde(default)]
+    pub added_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemovedRow {
+    pub row_index: usize,
+    pub values: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +51,57 @@ pub struct ModificationDetail {
     pub new_value: String,
 }
 
+/// How `impute` fills the empty cells of a column. Group-wise methods
+/// take their statistic from the rows sharing the `group_by` column's
+/// value, or the whole column without one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImputationMethod {
+    Constant(String),
+    ForwardFill,
+    BackwardFill,
+    /// Linear between the nearest numbers before and after, ordered by the
+    /// dates in `order_by` or else by row. Cells before the first or after
+    /// the last number, or without a date, stay empty.
+    Interpolate { order_by: Option<usize> },
+    Mean { group_by: Option<usize> },
+    Median { group_by: Option<usize> },
+    Mode { group_by: Option<usize> },
+    /// The most frequent value among those shaped like most values (see
+    /// `shape_pattern`), so a malformed entry is never copied
+    PatternMode { group_by: Option<usize> },
+}
+
+impl ImputationMethod {
+    /// Method by name, as in the cleansing `method` parameter and the AI
+    /// `fill_missing` strategy; `value` is the constant for `custom`
+    pub fn parse(name: &str, value: Option<&str>, group_by: Option<usize>, order_by: Option<usize>) -> Option<Self> {
+        Some(match name {
+            "custom" => Self::Constant(value.unwrap_or_default().to_string()),
+            "zero" => Self::Constant("0".to_string()),
+            "forward_fill" | "forward" | "ffill" => Self::ForwardFill,
+            "backward_fill" | "backward" | "bfill" => Self::BackwardFill,
+            "interpolate" | "linear" => Self::Interpolate { order_by },
+            "mean" => Self::Mean { group_by },
+            "median" => Self::Median { group_by },
+            "mode" => Self::Mode { group_by },
+            "pattern_mode" | "pattern" => Self::PatternMode { group_by },
+            _ => return None,
+        })
+    }
+
+    /// Method from cleansing parameters: `method`, `value`, and the column
+    /// indices `group_by` and `date_column`
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Option<Self> {
+        let index = |key: &str| parameters.get(key).and_then(|v| v.trim().parse().ok());
+        Self::parse(
+            parameters.get("method")?,
+            parameters.get("value").map(String::as_str),
+            index("group_by"),
+            index("date_column"),
+        )
+    }
+}
+
 pub struct DataCleanser;
 
 impl DataCleanser {
@@ -41,13 +109,23 @@ impl DataCleanser {
         data: &mut Vec<Vec<String>>,
         headers: &[String],
         options: &CleansingOptions,
+    ) -> CleansingResult {
+        Self::cleanse_with_locale(data, headers, options, &LocaleFormat::default())
+    }
+
+    /// Like `cleanse`, reading numbers and dates written in `locale`
+    pub fn cleanse_with_locale(
+        data: &mut Vec<Vec<String>>,
+        headers: &[String],
+        options: &CleansingOptions,
+        locale: &LocaleFormat,
     ) -> CleansingResult {
         match options.action {
             CleansingAction::TrimWhitespace => Self::trim_whitespace(data, &options.column_indices),
-            CleansingAction::RemoveDuplicates => Self::remove_duplicates(data),
-            CleansingAction::FillMissingValues => Self::fill_missing_values(data, options),
-            CleansingAction::StandardizeFormat => Self::standardize_format(data, headers, options),
-            CleansingAction::RemoveOutliers => Self::remove_outliers(data, &options.column_indices),
+            CleansingAction::RemoveDuplicates => Self::remove_duplicates(data, options, locale),
+            CleansingAction::FillMissingValues => Self::fill_missing_values(data, options, locale),
+            CleansingAction::StandardizeFormat => Self::standardize_format(data, headers, options, locale),
+            CleansingAction::RemoveOutliers => Self::remove_outliers(data, headers, options, locale),
             CleansingAction::NormalizeText => Self::normalize_text(data, &options.column_indices),
         }
     }
@@ -85,116 +163,105 @@ impl DataCleanser {
             rows_affected: modifications.len(),
             cells_modified,
             modifications,
+            removed_rows: Vec::new(),
+            added_columns: Vec::new(),
         }
     }
 
-    fn remove_duplicates(data: &mut Vec<Vec<String>>) -> CleansingResult {
-        let mut seen = std::collections::HashSet::new();
-        let original_len = data.len();
-        let mut modifications = Vec::new();
-
-        data.retain(|row| {
-            let key = row.join("|");
-            if seen.contains(&key) {
-                false
-            } else {
-                seen.insert(key);
-                true
+    /// Drop rows repeating the key columns (all columns by default).
+    /// Parameters: `ignore_case` and `ignore_whitespace` (`true`/`false`),
+    /// `keep` (`first`, `last`, `most_complete`, `max_date`, `min_date`)
+    /// and `date_column`, the column index for the date policies.
+    fn remove_duplicates(
+        data: &mut Vec<Vec<String>>,
+        options: &CleansingOptions,
+        locale: &LocaleFormat,
+    ) -> CleansingResult {
+        let flag = |key: &str| options.parameters.get(key).is_some_and(|v| v == "true");
+        let (ignore_case, ignore_whitespace) = (flag("ignore_case"), flag("ignore_whitespace"));
+        let policy = SurvivorPolicy::from_parameters(&options.parameters);
+
+        let key = |row: &[String]| -> Vec<String> {
+            let cell = |value: &String| {
+                let value = match ignore_whitespace {
+                    true => value.split_whitespace().collect::<Vec<_>>().join(" "),
+                    false => value.clone(),
+                };
+                if ignore_case { value.to_lowercase() } else { value }
+            };
+            match &options.column_indices {
+                Some(indices) => indices.iter().map(|&i| row.get(i).map(cell).unwrap_or_default()).collect(),
+                None => row.iter().map(cell).collect(),
             }
-        });
+        };
 
-        let rows_removed = original_len - data.len();
+        let mut groups: HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        for (row_idx, row) in data.iter().enumerate() {
+            groups.entry(key(row)).or_default().push(row_idx);
+        }
+
+        let mut removed: Vec<usize> = groups
+            .values()
+            .filter(|rows| rows.len() > 1)
+            .flat_map(|rows| {
+                let survivor = policy.pick(rows, data, locale);
+                rows.iter().copied().filter(move |&row| row != survivor)
+            })
+            .collect();
+        removed.sort_unstable();
+
+        let removed_rows: Vec<RemovedRow> = removed
+            .iter()
+            .map(|&row_index| RemovedRow { row_index, values: data[row_index].clone() })
+            .collect();
+        let mut row_idx = 0;
+        data.retain(|_| {
+            row_idx += 1;
+            removed.binary_search(&(row_idx - 1)).is_err()
+        });
 
         CleansingResult {
-            rows_affected: rows_removed,
+            rows_affected: removed_rows.len(),
             cells_modified: 0,
-            modifications,
+            modifications: Vec::new(),
+            removed_rows,
+            added_columns: Vec::new(),
         }
     }
 
+    /// Fill empty cells with the method in `parameters` (see
+    /// `ImputationMethod::from_parameters`); without one nothing changes
     fn fill_missing_values(
         data: &mut Vec<Vec<String>>,
         options: &CleansingOptions,
+        locale: &LocaleFormat,
     ) -> CleansingResult {
         let mut modifications = Vec::new();
-        let mut cells_modified = 0;
-
-        let fill_method = options
-            .parameters
-            .get("method")
-            .map(|s| s.as_str())
-            .unwrap_or("empty");
-
-        let fill_value = options
-            .parameters
-            .get("value")
-            .map(|s| s.as_str())
-            .unwrap_or("");
-
-        let column_indices = &options.column_indices;
 
-        // Pre-calculate mean values for numeric columns if needed
-        let mut column_means = std::collections::HashMap::new();
-        if fill_method == "mean" {
-            let max_col = data.iter().map(|r| r.len()).max().unwrap_or(0);
-            for col_idx in 0..max_col {
-                let numeric_values: Vec<f64> = data
-                    .iter()
-                    .filter_map(|r| r.get(col_idx))
-                    .filter_map(|v| v.parse::<f64>().ok())
-                    .collect();
-
-                if !numeric_values.is_empty() {
-                    let mean = numeric_values.iter().sum::<f64>() / numeric_values.len() as f64;
-                    column_means.insert(col_idx, format!("{:.2}", mean));
-                }
-            }
-        }
-
-        // Collect previous values for forward_fill
-        let mut previous_values: std::collections::HashMap<usize, String> = std::collections::HashMap::new();
-
-        for (row_idx, row) in data.iter_mut().enumerate() {
-            for (col_idx, cell) in row.iter_mut().enumerate() {
-                if let Some(indices) = column_indices {
-                    if !indices.contains(&col_idx) {
-                        continue;
-                    }
-                }
-
-                if cell.trim().is_empty() {
-                    let new_value = match fill_method {
-                        "custom" => fill_value.to_string(),
-                        "forward_fill" => {
-                            previous_values.get(&col_idx).cloned().unwrap_or_default()
-                        }
-                        "mean" => {
-                            column_means.get(&col_idx).cloned().unwrap_or_default()
-                        }
-                        _ => String::new(),
-                    };
-
-                    if !new_value.is_empty() {
-                        modifications.push(ModificationDetail {
-                            row_index: row_idx,
-                            column_index: col_idx,
-                            old_value: cell.clone(),
-                            new_value: new_value.clone(),
-                        });
-                        *cell = new_value.clone();
-                        cells_modified += 1;
-                    }
-                } else {
-                    // Update previous value for forward_fill
-                    previous_values.insert(col_idx, cell.clone());
+        if let Some(method) = ImputationMethod::from_parameters(&options.parameters) {
+            let columns: Vec<usize> = match &options.column_indices {
+                Some(indices) => indices.clone(),
+                None => (0..data.iter().map(|r| r.len()).max().unwrap_or(0)).collect(),
+            };
+            for column in columns {
+                for (row_idx, new_value) in impute(data, column, &method, locale) {
+                    let cell = &mut data[row_idx][column];
+                    modifications.push(ModificationDetail {
+                        row_index: row_idx,
+                        column_index: column,
+                        old_value: std::mem::replace(cell, new_value.clone()),
+                        new_value,
+                    });
                 }
             }
         }
 
         CleansingResult {
             rows_affected: modifications.len(),
-            cells_modified,
+            cells_modified: modifications.len(),
             modifications,
+            removed_rows: Vec::new(),
+            added_columns: Vec::new(),
         }
     }
 
@@ -202,6 +269,7 @@ impl DataCleanser {
         data: &mut Vec<Vec<String>>,
         _headers: &[String],
         options: &CleansingOptions,
+        locale: &LocaleFormat,
     ) -> CleansingResult {
         let mut modifications = Vec::new();
         let mut cells_modified = 0;
@@ -227,8 +295,8 @@ impl DataCleanser {
                 }
 
                 let new_value = match format_type {
-                    "date" => Self::standardize_date(cell),
-                    "number" => Self::standardize_number(cell),
+                    "date" => Self::standardize_date(cell, locale),
+                    "number" => Self::standardize_number(cell, locale),
                     "phone" => Self::standardize_phone(cell),
                     "email" => Self::standardize_email(cell),
                     _ => None,
@@ -253,19 +321,22 @@ impl DataCleanser {
             rows_affected: modifications.len(),
             cells_modified,
             modifications,
+            removed_rows: Vec::new(),
+            added_columns: Vec::new(),
         }
     }
 
-    fn standardize_date(value: &str) -> Option<String> {
-        // Try different date formats and convert to ISO 8601
-        let formats = vec![
+    fn standardize_date(value: &str, locale: &LocaleFormat) -> Option<String> {
+        // Try the configured format, then different date formats, and convert to ISO 8601
+        let configured = (!locale.date_format.is_empty()).then_some(locale.date_format.as_str());
+        let formats = configured.into_iter().chain([
             "%Y-%m-%d",
             "%d/%m/%Y",
             "%m/%d/%Y",
             "%Y/%m/%d",
             "%d-%m-%Y",
             "%m-%d-%Y",
-        ];
+        ]);
 
         for format in formats {
             if let Ok(date) = NaiveDate::parse_from_str(value, format) {
@@ -273,10 +344,15 @@ impl DataCleanser {
             }
         }
 
-        None
+        // Japanese era dates and full-width digits
+        locale.parse_date(value).map(|date| date.format("%Y-%m-%d").to_string())
     }
 
-    fn standardize_number(value: &str) -> Option<String> {
+    fn standardize_number(value: &str, locale: &LocaleFormat) -> Option<String> {
+        if let Some(num) = locale.parse_number(value) {
+            return Some(num.to_string());
+        }
+
         // Remove commas and other formatting
         let cleaned = value.replace(",", "").replace(" ", "");
         if let Ok(num) = cleaned.parse::<f64>() {
@@ -309,71 +385,114 @@ impl DataCleanser {
         Some(value.trim().to_lowercase())
     }
 
+    /// Find outliers with the method in `parameters` (see
+    /// `OutlierMethod::from_parameters`; z-score 3 by default) and apply
+    /// `action`: `remove` the row (default), `flag` it in a new column
+    /// named by `flag_column`, `winsorize` the value to the method's
+    /// bounds, or `replace_empty`. Only the columns in `column_indices` are
+    /// searched; without them nothing changes, since most tables have ID
+    /// or code columns whose spread says nothing about bad values.
     fn remove_outliers(
         data: &mut Vec<Vec<String>>,
-        column_indices: &Option<Vec<usize>>,
+        headers: &[String],
+        options: &CleansingOptions,
+        locale: &LocaleFormat,
     ) -> CleansingResult {
-        let mut rows_to_remove = std::collections::HashSet::new();
-
-        if let Some(indices) = column_indices {
-            for &col_idx in indices {
-                let mut numeric_values = Vec::new();
+        let Some(columns) = &options.column_indices else {
+            return CleansingResult {
+                rows_affected: 0,
+                cells_modified: 0,
+                modifications: Vec::new(),
+                removed_rows: Vec::new(),
+                added_columns: Vec::new(),
+            };
+        };
+        let width = headers.len().max(data.iter().map(|r| r.len()).max().unwrap_or(0));
+        let method = OutlierMethod::from_parameters(&options.parameters);
+        let outliers = find_outliers(data, columns, &method, locale);
+        let outlier_rows: BTreeSet<usize> = outliers.iter().map(|o| o.row_index).collect();
 
-                for (row_idx, row) in data.iter().enumerate() {
-                    if let Some(cell) = row.get(col_idx) {
-                        if let Ok(num) = cell.parse::<f64>() {
-                            numeric_values.push((row_idx, num));
-                        }
+        let mut modifications = Vec::new();
+        let mut added_columns = Vec::new();
+        let mut removed_rows = Vec::new();
+
+        match options.parameters.get("action").map(String::as_str) {
+            Some("flag") => {
+                let name = options.parameters.get("flag_column").cloned().unwrap_or_else(|| "is_outlier".to_string());
+                for (row_idx, row) in data.iter_mut().enumerate() {
+                    row.resize(width, String::new());
+                    let flagged = outlier_rows.contains(&row_idx);
+                    row.push(if flagged { "true".to_string() } else { String::new() });
+                    if flagged {
+                        modifications.push(ModificationDetail {
+                            row_index: row_idx,
+                            column_index: width,
+                            old_value: String::new(),
+                            new_value: "true".to_string(),
+                        });
                     }
                 }
-
-                if numeric_values.len() < 3 {
-                    continue;
-                }
-
-                // Calculate mean and standard deviation
-                let mean = numeric_values.iter().map(|(_, n)| n).sum::<f64>()
-                    / numeric_values.len() as f64;
-                let variance = numeric_values
-                    .iter()
-                    .map(|(_, n)| (n - mean).powi(2))
-                    .sum::<f64>()
-                    / numeric_values.len() as f64;
-                let std_dev = variance.sqrt();
-
-                if std_dev == 0.0 {
-                    continue;
-                }
-
-                // Mark outliers (Z-score > 3)
-                for (row_idx, num) in numeric_values {
-                    let z_score = ((num - mean) / std_dev).abs();
-                    if z_score > 3.0 {
-                        rows_to_remove.insert(row_idx);
-                    }
+                added_columns.push(name);
+            }
+            Some(action @ ("winsorize" | "replace_empty")) => {
+                for outlier in &outliers {
+                    let new_value = match action {
+                        "winsorize" => {
+                            let Some(value) = locale.parse_number(&outlier.value) else {
+                                continue;
+                            };
+                            let capped = value.clamp(outlier.lower, outlier.upper);
+                            if capped == value {
+                                continue;
+                            }
+                            Self::format_bound(capped, &outlier.value, locale)
+                        }
+                        _ => String::new(),
+                    };
+                    let cell = &mut data[outlier.row_index][outlier.column_index];
+                    modifications.push(ModificationDetail {
+                        row_index: outlier.row_index,
+                        column_index: outlier.column_index,
+                        old_value: std::mem::replace(cell, new_value.clone()),
+                        new_value,
+                    });
                 }
             }
-        }
-
-        let original_len = data.len();
-        let mut sorted_indices: Vec<_> = rows_to_remove.into_iter().collect();
-        sorted_indices.sort_by(|a, b| b.cmp(a)); // Sort in reverse order
-
-        for idx in sorted_indices {
-            if idx < data.len() {
-                data.remove(idx);
+            _ => {
+                for &row_idx in outlier_rows.iter().rev() {
+                    removed_rows.push(RemovedRow { row_index: row_idx, values: data.remove(row_idx) });
+                }
+                removed_rows.reverse();
             }
         }
 
-        let rows_removed = original_len - data.len();
-
+        let rows_affected = match removed_rows.is_empty() {
+            true => modifications.iter().map(|m| m.row_index).collect::<BTreeSet<_>>().len(),
+            false => removed_rows.len(),
+        };
         CleansingResult {
-            rows_affected: rows_removed,
-            cells_modified: 0,
-            modifications: Vec::new(),
+            rows_affected,
+            cells_modified: modifications.len(),
+            modifications,
+            removed_rows,
+            added_columns,
         }
     }
 
+    /// A capped value written like the original: whole numbers stay whole,
+    /// and the locale's decimal separator is kept
+    fn format_bound(value: f64, original: &str, locale: &LocaleFormat) -> String {
+        let whole = locale.parse_integer(original).is_some();
+        let text = match whole {
+            true => format!("{}", value.round() as i64),
+            false => {
+                let decimals = original.rsplit(locale.decimal_separator.as_str()).next().map_or(2, |d| d.len());
+                format!("{:.*}", decimals, value)
+            }
+        };
+        text.replace('.', &locale.decimal_separator)
+    }
+
     fn normalize_text(
         data: &mut Vec<Vec<String>>,
         column_indices: &Option<Vec<usize>>,
@@ -411,8 +530,148 @@ impl DataCleanser {
             rows_affected: modifications.len(),
             cells_modified,
             modifications,
+            removed_rows: Vec::new(),
+            added_columns: Vec::new(),
+        }
+    }
+}
+
+/// New values for the empty cells of `column`, as row index and value in
+/// row order. Numbers are read in `locale` and written with two decimals.
+pub fn impute(
+    rows: &[Vec<String>],
+    column: usize,
+    method: &ImputationMethod,
+    locale: &LocaleFormat,
+) -> Vec<(usize, String)> {
+    let is_missing = |row: usize| rows[row].get(column).is_some_and(|v| v.trim().is_empty());
+    let value = |row: usize| rows[row].get(column).map(String::as_str).filter(|v| !v.trim().is_empty());
+    let number = |value: f64| format!("{:.2}", value).replace('.', &locale.decimal_separator);
+    let mean = |values: &[&str]| {
+        let numbers: Vec<f64> = values.iter().filter_map(|v| locale.parse_number(v)).collect();
+        (!numbers.is_empty()).then(|| number(numbers.iter().sum::<f64>() / numbers.len() as f64))
+    };
+    let median = |values: &[&str]| {
+        let mut numbers: Vec<f64> = values.iter().filter_map(|v| locale.parse_number(v)).collect();
+        numbers.sort_by(f64::total_cmp);
+        let mid = numbers.len() / 2;
+        match numbers.len() {
+            0 => None,
+            n if n % 2 == 0 => Some(number((numbers[mid - 1] + numbers[mid]) / 2.0)),
+            _ => Some(number(numbers[mid])),
+        }
+    };
+    let pattern_mode = |values: &[&str]| {
+        let dominant = most_frequent(values.iter().map(|v| shape_pattern(v)))?;
+        most_frequent(values.iter().filter(|v| shape_pattern(v) == dominant).map(|v| v.to_string()))
+    };
+
+    let mut fills: Vec<(usize, String)> = match method {
+        ImputationMethod::Constant(fill) => {
+            (0..rows.len()).filter(|&r| is_missing(r)).map(|r| (r, fill.clone())).collect()
+        }
+        ImputationMethod::ForwardFill => carry(0..rows.len(), is_missing, value),
+        ImputationMethod::BackwardFill => carry((0..rows.len()).rev(), is_missing, value),
+        ImputationMethod::Interpolate { order_by } => {
+            let mut order: Vec<(f64, usize)> = match order_by {
+                Some(date_column) => rows
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(r, row)| {
+                        let date = locale.parse_date(row.get(*date_column)?)?;
+                        Some((date.num_days_from_ce() as f64, r))
+                    })
+                    .collect(),
+                None => (0..rows.len()).map(|r| (r as f64, r)).collect(),
+            };
+            order.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+            let mut fills = Vec::new();
+            let mut previous: Option<(f64, f64)> = None;
+            let mut pending = Vec::new();
+            for (x, r) in order {
+                match value(r).and_then(|v| locale.parse_number(v)) {
+                    Some(y) => {
+                        if let Some((x0, y0)) = previous {
+                            for (px, pr) in pending.drain(..) {
+                                let t = if x > x0 { (px - x0) / (x - x0) } else { 0.0 };
+                                fills.push((pr, number(y0 + t * (y - y0))));
+                            }
+                        }
+                        pending.clear();
+                        previous = Some((x, y));
+                    }
+                    None if is_missing(r) => pending.push((x, r)),
+                    None => {}
+                }
+            }
+            fills
+        }
+        ImputationMethod::Mean { group_by } => by_group(rows, *group_by, is_missing, value, mean),
+        ImputationMethod::Median { group_by } => by_group(rows, *group_by, is_missing, value, median),
+        ImputationMethod::Mode { group_by } => by_group(rows, *group_by, is_missing, value, |values| {
+            most_frequent(values.iter().map(|v| v.to_string()))
+        }),
+        ImputationMethod::PatternMode { group_by } => by_group(rows, *group_by, is_missing, value, pattern_mode),
+    };
+
+    fills.retain(|(_, fill)| !fill.is_empty());
+    fills.sort_by_key(|(row, _)| *row);
+    fills
+}
+
+/// Fill each missing cell with the last value seen walking `order`
+fn carry<'a>(
+    order: impl Iterator<Item = usize>,
+    is_missing: impl Fn(usize) -> bool,
+    value: impl Fn(usize) -> Option<&'a str>,
+) -> Vec<(usize, String)> {
+    let mut last: Option<&str> = None;
+    let mut fills = Vec::new();
+    for row in order {
+        match value(row) {
+            Some(v) => last = Some(v),
+            None if is_missing(row) => fills.extend(last.map(|v| (row, v.to_string()))),
+            None => {}
         }
     }
+    fills
+}
+
+/// Fill each missing cell with `statistic` of the values in its group
+fn by_group<'a>(
+    rows: &[Vec<String>],
+    group_by: Option<usize>,
+    is_missing: impl Fn(usize) -> bool,
+    value: impl Fn(usize) -> Option<&'a str>,
+    statistic: impl Fn(&[&'a str]) -> Option<String>,
+) -> Vec<(usize, String)> {
+    let group = |row: usize| group_by.map(|g| rows[row].get(g).map_or("", |v| v.trim()));
+    let mut values: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+    for row in 0..rows.len() {
+        if let Some(v) = value(row) {
+            values.entry(group(row)).or_default().push(v);
+        }
+    }
+    let fills: HashMap<Option<&str>, Option<String>> =
+        values.into_iter().map(|(key, values)| (key, statistic(&values))).collect();
+
+    (0..rows.len())
+        .filter(|&row| is_missing(row))
+        .filter_map(|row| Some((row, fills.get(&group(row))?.clone()?)))
+        .collect()
+}
+
+/// The most frequent item, the first seen on ties
+fn most_frequent(items: impl Iterator<Item = String>) -> Option<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (position, item) in items.enumerate() {
+        counts.entry(item).or_insert((0, position)).0 += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|(_, (count_a, first_a)), (_, (count_b, first_b))| count_a.cmp(count_b).then(first_b.cmp(first_a)))
+        .map(|(item, _)| item)
 }
 
 #[cfg(test)]
@@ -456,15 +715,140 @@ mod tests {
         assert_eq!(data.len(), 2);
     }
 
+    #[test]
+    fn test_remove_duplicates_on_key_columns() {
+        let mut data = vec![
+            vec!["john ".to_string(), "".to_string(), "2024-01-01".to_string()],
+            vec!["Jane".to_string(), "30".to_string(), "2024-02-01".to_string()],
+            vec!["John".to_string(), "25".to_string(), "2023-06-01".to_string()],
+        ];
+        let parameters = [("ignore_case", "true"), ("ignore_whitespace", "true"), ("keep", "most_complete")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let options = CleansingOptions {
+            action: CleansingAction::RemoveDuplicates,
+            column_indices: Some(vec![0]),
+            parameters,
+        };
+
+        let result = DataCleanser::cleanse(&mut data.clone(), &[], &options);
+        assert_eq!(result.removed_rows.len(), 1);
+        assert_eq!(result.removed_rows[0].row_index, 0);
+
+        let mut options = options;
+        options.parameters.insert("keep".to_string(), "max_date".to_string());
+        options.parameters.insert("date_column".to_string(), "2".to_string());
+        let result = DataCleanser::cleanse(&mut data, &[], &options);
+        assert_eq!(result.removed_rows[0].values[1], "25");
+        assert_eq!(data[0][0], "john ");
+    }
+
     #[test]
     fn test_standardize_date() {
         assert_eq!(
-            DataCleanser::standardize_date("01/15/2024"),
+            DataCleanser::standardize_date("01/15/2024", &LocaleFormat::default()),
             Some("2024-01-15".to_string())
         );
         assert_eq!(
-            DataCleanser::standardize_date("2024-01-15"),
+            DataCleanser::standardize_date("2024-01-15", &LocaleFormat::default()),
             Some("2024-01-15".to_string())
         );
+        assert_eq!(
+            DataCleanser::standardize_date("令和6年1月15日", &LocaleFormat::default()),
+            Some("2024-01-15".to_string())
+        );
+    }
+
+    #[test]
+    fn test_standardize_number_with_locale() {
+        let german = LocaleFormat::for_language_tag("de-DE");
+        assert_eq!(DataCleanser::standardize_number("1.234,56", &german), Some("1234.56".to_string()));
+        assert_eq!(
+            DataCleanser::standardize_number("１，２３４", &LocaleFormat::default()),
+            Some("1234".to_string())
+        );
+    }
+
+    #[test]
+    fn test_outlier_actions() {
+        let mut data: Vec<Vec<String>> = (0..20).map(|i| vec![format!("{}", 10 + i % 3)]).collect();
+        data.push(vec!["95".to_string()]);
+        let options = |action: &str| CleansingOptions {
+            action: CleansingAction::RemoveOutliers,
+            column_indices: Some(vec![0]),
+            parameters: [("method", "iqr"), ("k", "1.5"), ("action", action)]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let headers = vec!["amount".to_string()];
+
+        let mut untouched = data.clone();
+        let result = DataCleanser::cleanse(
+            &mut untouched,
+            &headers,
+            &CleansingOptions { column_indices: None, ..options("remove") },
+        );
+        assert!(result.removed_rows.is_empty());
+        assert_eq!(untouched, data);
+
+        let mut winsorized = data.clone();
+        let result = DataCleanser::cleanse(&mut winsorized, &headers, &options("winsorize"));
+        assert_eq!(result.cells_modified, 1);
+        assert_eq!(winsorized[20][0], "15");
+
+        let mut flagged = data.clone();
+        let result = DataCleanser::cleanse(&mut flagged, &headers, &options("flag"));
+        assert_eq!(result.added_columns, vec!["is_outlier"]);
+        assert_eq!(flagged[20][1], "true");
+        assert_eq!(flagged[0][1], "");
+
+        let result = DataCleanser::cleanse(&mut data, &headers, &options("remove"));
+        assert_eq!(result.removed_rows[0].row_index, 20);
+        assert_eq!(data.len(), 20);
+    }
+
+    #[test]
+    fn test_impute_methods() {
+        let rows: Vec<Vec<String>> = [
+            ["2024-01-03", "north", "30", "AB-12"],
+            ["2024-01-01", "north", "10", "CD-34"],
+            ["2024-01-02", "south", "", "x"],
+            ["2024-01-05", "south", "50", "x"],
+            ["2024-01-04", "north", "", ""],
+            ["2024-01-06", "south", "", "EF-56"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+        let locale = LocaleFormat::default();
+        let fill = |column: usize, method: ImputationMethod| impute(&rows, column, &method, &locale);
+
+        assert_eq!(fill(2, ImputationMethod::BackwardFill), vec![(2, "50".to_string())]);
+        assert_eq!(
+            fill(2, ImputationMethod::Interpolate { order_by: Some(0) }),
+            vec![(2, "20.00".to_string()), (4, "40.00".to_string())]
+        );
+        assert_eq!(
+            fill(2, ImputationMethod::Mean { group_by: Some(1) }),
+            vec![(2, "50.00".to_string()), (4, "20.00".to_string()), (5, "50.00".to_string())]
+        );
+        assert_eq!(fill(3, ImputationMethod::PatternMode { group_by: None }), vec![(4, "AB-12".to_string())]);
+        assert_eq!(fill(3, ImputationMethod::Mode { group_by: None }), vec![(4, "x".to_string())]);
+
+        let mut data = rows.clone();
+        let options = CleansingOptions {
+            action: CleansingAction::FillMissingValues,
+            column_indices: Some(vec![2]),
+            parameters: [("method", "median"), ("group_by", "1")]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let result = DataCleanser::cleanse(&mut data, &[], &options);
+        assert_eq!(result.cells_modified, 3);
+        assert_eq!(data[4][2], "20.00");
     }
 }
@@ -0,0 +1,506 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context, bail};
+use encoding_rs::UTF_8;
+use memmap2::{Mmap, MmapMut};
+use crate::csv_engine::compression::Compression;
+use crate::csv_engine::data_types::{DataType, DataTypeDetector, TypeInference};
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::encoding;
+
+/// Storage backend for files too large to hold as `Vec<Vec<String>>`.
+/// The file is memory-mapped and only cell boundaries are kept, one offset
+/// array per column, so a column can be scanned without touching the
+/// others. Edited cells live in an overlay until the file is written.
+pub struct ColumnStore {
+    path: PathBuf,
+    mmap: Mmap,
+    delimiter: u8,
+    dialect: CsvDialect,
+    headers: Vec<String>,
+    /// Byte range of the header record, copied as-is when writing
+    header_range: Option<(usize, usize)>,
+    /// Absolute offset of each row in the file
+    row_starts: Vec<u64>,
+    /// `bounds[c][r]` is where column `c` of row `r` starts, relative to the
+    /// row start. One extra entry per row marks the end of the last column
+    /// plus one; cells missing from ragged rows start past that end.
+    bounds: Vec<Vec<u32>>,
+    /// Edited cells keyed by row, then column
+    edits: HashMap<usize, HashMap<usize, String>>,
+}
+
+impl ColumnStore {
+    pub fn open(path: &Path, delimiter: u8, has_headers: bool, dialect: CsvDialect) -> Result<Self> {
+        if Compression::detect(path)?.is_compressed() {
+            bail!("Compressed files cannot be memory-mapped");
+        }
+
+        let mmap = map_file(path)?;
+
+        let (detected, _) = encoding::detect(&mmap[..mmap.len().min(8192)]);
+        if detected != UTF_8 {
+            bail!("Memory-mapped storage requires UTF-8, but the file is {}", detected.name());
+        }
+
+        let mut store = Self {
+            path: path.to_path_buf(),
+            mmap,
+            delimiter,
+            dialect,
+            headers: Vec::new(),
+            header_range: None,
+            row_starts: Vec::new(),
+            bounds: Vec::new(),
+            edits: HashMap::new(),
+        };
+        store.build_index(has_headers);
+
+        Ok(store)
+    }
+
+    /// Index records the way `CsvReader` reads them with the same dialect:
+    /// comment lines are skipped, and so are rows of empty fields under
+    /// `skip_empty_rows`
+    fn build_index(&mut self, has_headers: bool) {
+        let data: &[u8] = &self.mmap;
+        let dialect = &self.dialect;
+        let mut pos = if data.starts_with(b"\xEF\xBB\xBF") { 3 } else { 0 };
+        let mut fields: Vec<u32> = Vec::new();
+        let mut column_count = None;
+
+        while pos < data.len() {
+            let start = pos;
+            if dialect.comment() == Some(data[start]) {
+                pos = data[start..].iter().position(|&b| b == b'\n').map_or(data.len(), |end| start + end + 1);
+                continue;
+            }
+
+            let (next, len) = scan_record(data, start, self.delimiter, dialect, &mut fields);
+            pos = next;
+
+            if len == 0 {
+                continue; // Blank line
+            }
+            let field_end = |c: usize| fields.get(c + 1).map(|b| *b as usize - 1).unwrap_or(len);
+            if dialect.skip_empty_rows
+                && (0..fields.len()).all(|c| {
+                    field_value(&data[start + fields[c] as usize..start + field_end(c)], dialect).trim().is_empty()
+                })
+            {
+                continue;
+            }
+
+            // The first record fixes the column count
+            let columns = *column_count.get_or_insert_with(|| {
+                self.bounds = vec![Vec::new(); fields.len() + 1];
+                fields.len()
+            });
+
+            if has_headers && self.header_range.is_none() {
+                self.header_range = Some((start, start + len));
+                self.headers = (0..columns)
+                    .map(|c| field_value(&data[start + fields[c] as usize..start + field_end(c)], dialect).into_owned())
+                    .collect();
+                continue;
+            }
+
+            let past_end = len as u32 + 1;
+            self.row_starts.push(start as u64);
+            for (c, bounds) in self.bounds.iter_mut().enumerate() {
+                bounds.push(fields.get(c).copied().unwrap_or(past_end));
+            }
+        }
+
+        if self.headers.is_empty() {
+            let columns = column_count.unwrap_or(0);
+            self.headers = (1..=columns).map(|i| format!("Column {}", i)).collect();
+        }
+
+        for bounds in &mut self.bounds {
+            bounds.shrink_to_fit();
+        }
+        self.row_starts.shrink_to_fit();
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    pub fn dialect(&self) -> &CsvDialect {
+        &self.dialect
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_starts.len()
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    fn raw_cell(&self, row: usize, column: usize) -> &[u8] {
+        let row_start = self.row_starts[row] as usize;
+        let start = self.bounds[column][row] as usize;
+        let end = (self.bounds[column + 1][row] as usize).saturating_sub(1);
+
+        if start >= end {
+            return &[];
+        }
+        &self.mmap[row_start + start..row_start + end]
+    }
+
+    /// Cell value, borrowed from the mapping unless it was edited or quoted
+    pub fn cell(&self, row: usize, column: usize) -> Option<Cow<'_, str>> {
+        if row >= self.row_count() || column >= self.column_count() {
+            return None;
+        }
+
+        if let Some(value) = self.edits.get(&row).and_then(|edits| edits.get(&column)) {
+            return Some(Cow::Borrowed(value.as_str()));
+        }
+
+        Some(field_value(self.raw_cell(row, column), &self.dialect))
+    }
+
+    /// Values of one column in row order
+    pub fn column(&self, column: usize) -> impl Iterator<Item = Cow<'_, str>> + '_ {
+        (0..self.row_count()).map(move |row| self.cell(row, column).unwrap_or_default())
+    }
+
+    /// Materialize rows `start..end` for display
+    pub fn rows(&self, start: usize, end: usize) -> Vec<Vec<String>> {
+        let end = end.min(self.row_count());
+        (start.min(end)..end)
+            .map(|row| {
+                (0..self.column_count())
+                    .map(|column| self.cell(row, column).unwrap_or_default().into_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn set_cell(&mut self, row: usize, column: usize, value: String) -> Result<()> {
+        if row >= self.row_count() || column >= self.column_count() {
+            bail!("Cell ({}, {}) is out of bounds", row, column);
+        }
+
+        self.edits.entry(row).or_default().insert(column, value);
+        Ok(())
+    }
+
+    pub fn has_edits(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Most common type among the first `sample_size` non-empty values,
+    /// without allocating a string per cell
+    pub fn detect_column_type(&self, column: usize, detector: &DataTypeDetector, sample_size: usize) -> DataType {
+        self.infer_column_type(column, detector, sample_size).data_type
+    }
+
+    /// Type of a column from its first `sample_size` non-empty values, with
+    /// confidence and ambiguities
+    pub fn infer_column_type(&self, column: usize, detector: &DataTypeDetector, sample_size: usize) -> TypeInference {
+        detector.infer_column_type(
+            self.column(column)
+                .enumerate()
+                .filter(|(_, value)| !value.is_empty())
+                .take(sample_size),
+        )
+    }
+
+    /// Bytes held in memory: the offset arrays, headers and edits. The
+    /// mapped file itself is paged in by the OS on demand.
+    pub fn memory_usage(&self) -> u64 {
+        let offsets = self.row_starts.capacity() * std::mem::size_of::<u64>()
+            + self.bounds.iter().map(|b| b.capacity() * std::mem::size_of::<u32>()).sum::<usize>();
+        let headers: usize = self.headers.iter().map(|h| h.capacity()).sum();
+        let edits: usize = self
+            .edits
+            .values()
+            .flat_map(|row| row.values())
+            .map(|value| value.capacity() + std::mem::size_of::<(usize, String)>())
+            .sum();
+
+        (offsets + headers + edits) as u64
+    }
+
+    /// Write the file with edits applied to a temporary file next to
+    /// `path`, returning its path. Unedited records are copied byte for byte.
+    fn write_temp(&self, path: &Path) -> Result<PathBuf> {
+        let temp_path = path.with_extension("csv.tmp");
+        let result = (|| -> Result<()> {
+            let file = File::create(&temp_path).context("Failed to create output file")?;
+            let mut output = BufWriter::new(file);
+
+            if let Some((start, end)) = self.header_range {
+                output.write_all(&self.mmap[start..end])?;
+                output.write_all(b"\n")?;
+            }
+
+            for row in 0..self.row_count() {
+                match self.edits.get(&row) {
+                    None => {
+                        let start = self.row_starts[row] as usize;
+                        let end = start + self.record_len(row);
+                        output.write_all(&self.mmap[start..end])?;
+                        output.write_all(b"\n")?;
+                    }
+                    Some(_) => {
+                        let record: Vec<Cow<str>> = (0..self.column_count())
+                            .map(|column| self.cell(row, column).unwrap_or_default())
+                            .collect();
+                        let mut writer = self.dialect
+                            .writer_builder(self.delimiter)
+                            .from_writer(&mut output);
+                        writer.write_record(record.iter().map(|v| v.as_bytes()))?;
+                        writer.flush()?;
+                    }
+                }
+            }
+
+            output.flush().context("Failed to write to file")
+        })();
+
+        match result {
+            Ok(()) => Ok(temp_path),
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Write to `path` and map the written file in place of this one. If the
+    /// file cannot be written or replaced, the store and its edits are kept.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let temp_path = self.write_temp(path)?;
+        let has_headers = self.header_range.is_some();
+
+        // A mapped file cannot be replaced on Windows, so the mapping is
+        // released first and restored if the rename fails
+        let in_place = fs::canonicalize(path).ok() == fs::canonicalize(&self.path).ok();
+        if in_place {
+            self.mmap = empty_mapping()?;
+        }
+        if let Err(e) = fs::rename(&temp_path, path) {
+            let _ = fs::remove_file(&temp_path);
+            if in_place {
+                match map_file(&self.path) {
+                    Ok(mmap) => self.mmap = mmap,
+                    Err(_) => self.clear_index(),
+                }
+            }
+            return Err(e).context("Failed to replace file");
+        }
+
+        match Self::open(path, self.delimiter, has_headers, self.dialect.clone()) {
+            Ok(store) => {
+                *self = store;
+                Ok(())
+            }
+            Err(e) => {
+                // The edits are in the file now, but the old index no longer
+                // matches the mapping
+                self.mmap = empty_mapping()?;
+                self.clear_index();
+                Err(e).context("Saved the file but failed to reopen it")
+            }
+        }
+    }
+
+    /// Forget every row so a stale index is never read
+    fn clear_index(&mut self) {
+        self.header_range = None;
+        self.row_starts.clear();
+        self.bounds.iter_mut().for_each(Vec::clear);
+        self.edits.clear();
+    }
+
+    /// Length of a record without its line terminator, extra fields included
+    fn record_len(&self, row: usize) -> usize {
+        let start = self.row_starts[row] as usize;
+        let next = self.row_starts.get(row + 1).map(|s| *s as usize).unwrap_or(self.mmap.len());
+        let mut end = next;
+        // Trailing blank lines and the terminator are not part of the record
+        while end > start && matches!(self.mmap[end - 1], b'\n' | b'\r') {
+            end -= 1;
+        }
+        end - start
+    }
+}
+
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).context("Failed to open CSV file")?;
+    // SAFETY: the mapping is read-only. If another process truncates the
+    // file while it is open, reads may fault; this is the accepted cost
+    // of not copying multi-gigabyte files into memory.
+    unsafe { Mmap::map(&file) }.context("Failed to map CSV file")
+}
+
+/// Placeholder mapping held while the file itself is being replaced
+fn empty_mapping() -> Result<Mmap> {
+    MmapMut::map_anon(1)?.make_read_only().context("Failed to release CSV file mapping")
+}
+
+/// Scan one record starting at `start`, filling `fields` with the start of
+/// each field relative to `start`. Returns where the next record begins and
+/// the record length without its line terminator.
+fn scan_record(data: &[u8], start: usize, delimiter: u8, dialect: &CsvDialect, fields: &mut Vec<u32>) -> (usize, usize) {
+    let quote = dialect.quote();
+    let escape = if dialect.double_quote { None } else { dialect.escape() };
+    fields.clear();
+    fields.push(0);
+    let mut field_start = start;
+    let mut in_quotes = false;
+    let mut i = start;
+
+    while i < data.len() {
+        let b = data[i];
+
+        if in_quotes {
+            if Some(b) == escape {
+                i += 2;
+                continue;
+            }
+            if b == quote {
+                if dialect.double_quote && data.get(i + 1) == Some(&quote) {
+                    i += 2;
+                    continue;
+                }
+                in_quotes = false;
+            }
+        } else if b == quote && i == field_start {
+            in_quotes = true;
+        } else if b == delimiter {
+            field_start = i + 1;
+            fields.push((field_start - start) as u32);
+        } else if b == b'\n' || b == b'\r' {
+            let next = if b == b'\r' && data.get(i + 1) == Some(&b'\n') { i + 2 } else { i + 1 };
+            return (next, i - start);
+        }
+        i += 1;
+    }
+
+    (data.len(), data.len() - start)
+}
+
+/// Value of a raw field: unquoted, then trimmed under `trim_whitespace`
+fn field_value<'a>(raw: &'a [u8], dialect: &CsvDialect) -> Cow<'a, str> {
+    let value = unquote(raw, dialect);
+    if !dialect.trim_whitespace {
+        return value;
+    }
+    match value {
+        Cow::Borrowed(text) => Cow::Borrowed(text.trim()),
+        Cow::Owned(text) => Cow::Owned(text.trim().to_string()),
+    }
+}
+
+/// Strip surrounding quotes and undouble inner ones (or drop the escape
+/// character in backslash mode), borrowing when possible
+fn unquote<'a>(raw: &'a [u8], dialect: &CsvDialect) -> Cow<'a, str> {
+    let quote = dialect.quote();
+    let inner = match raw {
+        [first, inner @ .., last] if *first == quote && *last == quote => inner,
+        _ => return String::from_utf8_lossy(raw),
+    };
+
+    let escape = if dialect.double_quote { quote } else { dialect.escape().unwrap_or(quote) };
+    if !inner.contains(&escape) {
+        return String::from_utf8_lossy(inner);
+    }
+
+    let mut unescaped = Vec::with_capacity(inner.len());
+    let mut i = 0;
+    while i < inner.len() {
+        if inner[i] == escape && i + 1 < inner.len() && (escape != quote || inner[i + 1] == quote) {
+            i += 1;
+        }
+        unescaped.push(inner[i]);
+        i += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&unescaped).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-store.csv", uuid::Uuid::new_v4()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_cells_ragged_rows_and_quotes() {
+        let path = write_temp("id,name,note\r\n1,\"Doe, \"\"J\"\"\",x\r\n\r\n2,Smith\r\n3,Lee,\"a\nb\"\r\n");
+        let store = ColumnStore::open(&path, b',', true, CsvDialect::default()).unwrap();
+
+        assert_eq!(store.headers(), ["id", "name", "note"]);
+        assert_eq!(store.row_count(), 3);
+        assert_eq!(store.cell(0, 1).unwrap(), "Doe, \"J\"");
+        assert_eq!(store.cell(1, 2).unwrap(), "");
+        assert_eq!(store.cell(2, 2).unwrap(), "a\nb");
+        assert_eq!(store.column(0).collect::<Vec<_>>(), ["1", "2", "3"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_index_follows_the_dialect() {
+        let contents = "# exported nightly\nid,name\n1,\"say \\\"hi\\\"\"\n , \n2,  Lee \n";
+        let path = write_temp(contents);
+        let dialect = CsvDialect {
+            escape_char: "\\".to_string(),
+            double_quote: false,
+            comment_prefix: "#".to_string(),
+            trim_whitespace: true,
+            skip_empty_rows: true,
+            ..CsvDialect::default()
+        };
+        let store = ColumnStore::open(&path, b',', true, dialect.clone()).unwrap();
+
+        let expected = crate::csv_engine::reader::CsvReader::new()
+            .with_dialect(dialect)
+            .with_delimiter(b',')
+            .with_headers(true)
+            .read_file(&path)
+            .unwrap();
+        assert_eq!(store.headers(), ["id", "name"]);
+        assert_eq!(store.rows(0, store.row_count()), vec![vec!["1", "say \"hi\""], vec!["2", "Lee"]]);
+        assert_eq!(store.rows(0, store.row_count()), expected.rows);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_edits_are_overlaid_and_written() {
+        let path = write_temp("id,name\n1,'a'\n2,b\n");
+        let dialect = CsvDialect { quote_char: "'".to_string(), ..CsvDialect::default() };
+        let mut store = ColumnStore::open(&path, b',', true, dialect).unwrap();
+
+        store.set_cell(1, 1, "x,y".to_string()).unwrap();
+        assert_eq!(store.cell(1, 1).unwrap(), "x,y");
+        assert!(store.set_cell(5, 0, String::new()).is_err());
+
+        // A failed save keeps the edits
+        assert!(store.save(&path.with_file_name("missing-dir").join("out.csv")).is_err());
+        assert_eq!(store.cell(1, 1).unwrap(), "x,y");
+
+        store.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "id,name\n1,'a'\n2,'x,y'\n");
+        assert_eq!(store.cell(1, 1).unwrap(), "x,y");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
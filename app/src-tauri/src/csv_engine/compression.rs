@@ -0,0 +1,295 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read, Seek, Write};
+use std::path::Path;
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+
+/// Container a CSV file is stored in. Detected from magic bytes when
+/// reading, remembered in `CsvMetadata` so saving writes the same format.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    /// A single CSV member of a zip archive
+    Zip,
+}
+
+impl Compression {
+    pub fn from_extension(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "gz" | "gzip" => Compression::Gzip,
+            "zst" | "zstd" => Compression::Zstd,
+            "bz2" => Compression::Bzip2,
+            "zip" => Compression::Zip,
+            _ => Compression::None,
+        }
+    }
+
+    /// Detect from the file's magic bytes, falling back to the extension
+    /// for files too short to carry a signature
+    pub fn detect(path: &Path) -> Result<Self> {
+        let mut magic = Vec::with_capacity(4);
+        File::open(path)
+            .context("Failed to open file")?
+            .take(4)
+            .read_to_end(&mut magic)?;
+
+        Ok(match magic.as_slice() {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            [0x28, 0xb5, 0x2f, 0xfd] => Compression::Zstd,
+            [b'B', b'Z', b'h', ..] => Compression::Bzip2,
+            [b'P', b'K', 0x03, 0x04] => Compression::Zip,
+            _ if magic.len() < 4 => Self::from_extension(path),
+            _ => Compression::None,
+        })
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        *self != Compression::None
+    }
+}
+
+/// Name of the file inside the compressed stream, e.g. `sales.csv` for
+/// `sales.csv.gz`. Used to check the real extension and to name zip members.
+pub fn inner_file_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match Compression::from_extension(path) {
+        Compression::None => name,
+        Compression::Zip => Path::new(&name)
+            .file_stem()
+            .map(|s| format!("{}.csv", s.to_string_lossy()))
+            .unwrap_or(name),
+        _ => Path::new(&name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or(name),
+    }
+}
+
+/// File names in a zip archive, directories excluded
+pub fn zip_members(path: &Path) -> Result<Vec<String>> {
+    let file = File::open(path).context("Failed to open zip archive")?;
+    let archive = zip::ZipArchive::new(BufReader::new(file)).context("Failed to read zip archive")?;
+
+    Ok(archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(String::from)
+        .collect())
+}
+
+/// The member opened when none is named: the first delimited text file,
+/// or the first file of any kind
+pub fn default_zip_member(path: &Path) -> Result<String> {
+    pick_zip_member(zip_members(path)?)
+}
+
+fn pick_zip_member(mut members: Vec<String>) -> Result<String> {
+    members.sort();
+
+    let is_text = |name: &String| {
+        let name = name.to_lowercase();
+        [".csv", ".tsv", ".txt"].iter().any(|ext| name.ends_with(ext))
+    };
+
+    members
+        .iter()
+        .find(|name| is_text(name))
+        .or_else(|| members.first())
+        .cloned()
+        .context("Zip archive contains no files")
+}
+
+/// Open a decompressing reader over the file. Zip members are extracted
+/// into memory because a member reader cannot outlive its archive.
+pub fn open(path: &Path, compression: Compression, member: Option<&str>) -> Result<Box<dyn Read>> {
+    let file = File::open(path).context("Failed to open file")?;
+    decompress(BufReader::new(file), compression, member)
+}
+
+/// Like `open`, over any seekable source such as one that counts bytes read
+pub fn decompress<R: Read + Seek + 'static>(
+    reader: R,
+    compression: Compression,
+    member: Option<&str>,
+) -> Result<Box<dyn Read>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Compression::Zip => {
+            let mut archive = zip::ZipArchive::new(reader).context("Failed to read zip archive")?;
+            let member = match member {
+                Some(member) => member.to_string(),
+                None => pick_zip_member(
+                    archive
+                        .file_names()
+                        .filter(|name| !name.ends_with('/'))
+                        .map(String::from)
+                        .collect(),
+                )?,
+            };
+            let mut entry = archive
+                .by_name(&member)
+                .with_context(|| format!("Zip archive has no member named {}", member))?;
+            let mut buffer = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut buffer)?;
+            Box::new(Cursor::new(buffer))
+        }
+    })
+}
+
+pub fn read_to_end(path: &Path, compression: Compression, member: Option<&str>) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    open(path, compression, member)?
+        .read_to_end(&mut buffer)
+        .context("Failed to decompress file")?;
+    Ok(buffer)
+}
+
+/// Write `bytes` to `path` in the given format. Other members of an
+/// existing zip archive are carried over unchanged.
+pub fn write(path: &Path, compression: Compression, member: Option<&str>, bytes: &[u8]) -> Result<()> {
+    match compression {
+        Compression::None => {
+            let mut file = File::create(path).context("Failed to create output file")?;
+            file.write_all(bytes).context("Failed to write to file")?;
+        }
+        Compression::Zip => write_zip_member(path, member, bytes)?,
+        _ => {
+            let file = File::create(path).context("Failed to create output file")?;
+            write_stream(file, compression, bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Append `bytes` as a new compressed stream. gzip, zstd and bzip2 readers
+/// decode concatenated streams as one, so the file stays readable.
+pub fn append(path: &Path, compression: Compression, bytes: &[u8]) -> Result<()> {
+    let file = fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .context("Failed to open file for appending")?;
+
+    match compression {
+        Compression::Zip => bail!("Appending to a zip archive is not supported"),
+        Compression::None => {
+            let mut file = file;
+            file.write_all(bytes).context("Failed to append rows")?;
+        }
+        _ => write_stream(file, compression, bytes)?,
+    }
+
+    Ok(())
+}
+
+fn write_stream(file: File, compression: Compression, bytes: &[u8]) -> Result<()> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish().context("Failed to finish gzip stream")?;
+        }
+        Compression::Zstd => {
+            let mut encoder = zstd::stream::write::Encoder::new(file, 0)?;
+            encoder.write_all(bytes)?;
+            encoder.finish().context("Failed to finish zstd stream")?;
+        }
+        Compression::Bzip2 => {
+            let mut encoder = bzip2::write::BzEncoder::new(file, bzip2::Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish().context("Failed to finish bzip2 stream")?;
+        }
+        Compression::None | Compression::Zip => unreachable!("not a stream format"),
+    }
+
+    Ok(())
+}
+
+fn write_zip_member(path: &Path, member: Option<&str>, bytes: &[u8]) -> Result<()> {
+    let member = member.map(String::from).unwrap_or_else(|| inner_file_name(path));
+    let temp_path = path.with_extension("zip.tmp");
+
+    {
+        let mut writer = zip::ZipWriter::new(File::create(&temp_path).context("Failed to create output file")?);
+
+        if Compression::detect(path).ok() == Some(Compression::Zip) {
+            let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+            for i in 0..archive.len() {
+                let entry = archive.by_index_raw(i)?;
+                if entry.name() != member {
+                    writer.raw_copy_file(entry)?;
+                }
+            }
+        }
+
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        writer.start_file(member.as_str(), options)?;
+        writer.write_all(bytes)?;
+        writer.finish().context("Failed to finish zip archive")?;
+    }
+
+    fs::rename(&temp_path, path).context("Failed to replace zip archive")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), name))
+    }
+
+    #[test]
+    fn test_stream_formats_round_trip_and_append() {
+        for (name, compression) in [
+            ("data.csv.gz", Compression::Gzip),
+            ("data.csv.zst", Compression::Zstd),
+            ("data.csv.bz2", Compression::Bzip2),
+        ] {
+            let path = temp_path(name);
+            write(&path, compression, None, b"a,b\n1,2\n").unwrap();
+            append(&path, compression, b"3,4\n").unwrap();
+
+            assert_eq!(Compression::detect(&path).unwrap(), compression);
+            assert_eq!(read_to_end(&path, compression, None).unwrap(), b"a,b\n1,2\n3,4\n");
+            assert!(inner_file_name(&path).ends_with("data.csv"));
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_zip_member_is_replaced_and_others_kept() {
+        let path = temp_path("archive.zip");
+
+        write(&path, Compression::Zip, Some("readme.md"), b"notes").unwrap();
+        write(&path, Compression::Zip, Some("orders.csv"), b"id\n1\n").unwrap();
+        write(&path, Compression::Zip, Some("orders.csv"), b"id\n2\n").unwrap();
+
+        let mut members = zip_members(&path).unwrap();
+        members.sort();
+        assert_eq!(members, vec!["orders.csv", "readme.md"]);
+        assert_eq!(default_zip_member(&path).unwrap(), "orders.csv");
+        assert_eq!(read_to_end(&path, Compression::Zip, None).unwrap(), b"id\n2\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,11 @@
-use chrono::{NaiveDate, NaiveDateTime};
+use std::cmp::Ordering;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rayon::prelude::*;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use super::locale::LocaleFormat;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
@@ -13,12 +18,562 @@ pub enum DataType {
     Email,
     Url,
     Json,
+    /// Amount with a currency symbol or ISO code, e.g. `$1,200.50` or `12 EUR`
+    Currency,
+    /// e.g. `45%`, normalized to the fraction 0.45
+    Percentage,
+    /// Time of day without a date, e.g. `14:30` or `2:30 PM`
+    Time,
+    /// ISO 8601 (`P3DT4H`) or unit form (`1h 30m`)
+    Duration,
+    Uuid,
+    IpAddress,
+    Phone,
     Text,
 }
 
+impl DataType {
+    /// Types beyond plain numbers that `ParsedValue` normalizes
+    pub fn has_parsed_form(&self) -> bool {
+        matches!(
+            self,
+            DataType::Currency
+                | DataType::Percentage
+                | DataType::Time
+                | DataType::Duration
+                | DataType::Uuid
+                | DataType::IpAddress
+                | DataType::Phone
+        )
+    }
+}
+
+/// A value of one of the richer types reduced to a form that can be
+/// compared and aggregated
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsedValue {
+    Number(f64),
+    /// The symbol or code is kept as written, `$` stays `$`
+    Currency { amount: f64, currency: String },
+    /// As a fraction, so `45%` is 0.45
+    Percentage(f64),
+    Time(NaiveTime),
+    /// Length in seconds; years count 365 days and months 30
+    Duration(f64),
+    /// Lowercase and hyphenated, without braces
+    Uuid(String),
+    IpAddress(IpAddr),
+    /// Digits only, with a leading `+` when written in international form
+    Phone(String),
+}
+
+impl ParsedValue {
+    /// Parse `value` as `data_type`. Types without a normalized form
+    /// (text, email, URL, JSON, dates) give `None`.
+    pub fn parse(value: &str, data_type: &DataType) -> Option<Self> {
+        let value = value.trim();
+        match data_type {
+            DataType::Integer | DataType::Float => value.parse::<f64>().ok().map(ParsedValue::Number),
+            DataType::Currency => parse_currency(value).map(|(amount, currency)| ParsedValue::Currency { amount, currency }),
+            DataType::Percentage => parse_percentage(value).map(ParsedValue::Percentage),
+            DataType::Time => parse_time(value).map(ParsedValue::Time),
+            DataType::Duration => parse_duration(value).map(ParsedValue::Duration),
+            DataType::Uuid => parse_uuid(value).map(ParsedValue::Uuid),
+            DataType::IpAddress => value.parse::<IpAddr>().ok().map(ParsedValue::IpAddress),
+            DataType::Phone => parse_phone(value).map(ParsedValue::Phone),
+            _ => None,
+        }
+    }
+
+    /// The value on a number line, for statistics: amounts, fractions,
+    /// seconds since midnight and seconds of duration
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ParsedValue::Number(n) | ParsedValue::Percentage(n) | ParsedValue::Duration(n) => Some(*n),
+            ParsedValue::Currency { amount, .. } => Some(*amount),
+            ParsedValue::Time(time) => {
+                Some(time.num_seconds_from_midnight() as f64 + time.nanosecond() as f64 / 1e9)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (ParsedValue::IpAddress(a), ParsedValue::IpAddress(b)) => Some(a.cmp(b)),
+            (ParsedValue::Uuid(a), ParsedValue::Uuid(b)) | (ParsedValue::Phone(a), ParsedValue::Phone(b)) => {
+                Some(a.cmp(b))
+            }
+            _ => self.as_number()?.partial_cmp(&other.as_number()?),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            ParsedValue::Time(time) => serde_json::json!(time.format("%H:%M:%S").to_string()),
+            ParsedValue::Uuid(text) | ParsedValue::Phone(text) => serde_json::json!(text),
+            ParsedValue::IpAddress(ip) => serde_json::json!(ip.to_string()),
+            _ => self
+                .as_number()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+        }
+    }
+}
+
+const CURRENCY_SYMBOLS: [&str; 9] = ["$", "€", "£", "¥", "₹", "₩", "₽", "₺", "R$"];
+
+const CURRENCY_CODES: [&str; 22] = [
+    "USD", "EUR", "GBP", "JPY", "CNY", "INR", "KRW", "CHF", "CAD", "AUD", "RUB",
+    "BRL", "MXN", "SEK", "NOK", "DKK", "PLN", "HKD", "SGD", "NZD", "ZAR", "TRY",
+];
+
+/// Date and time layouts read as `DataType::DateTime`, by detection and
+/// validation alike
+const DATETIME_FORMATS: [&str; 10] = [
+    "%Y-%m-%d %H:%M:%S", // 2025-10-01 09:00:00
+    "%Y-%m-%d %H:%M",    // 2025-10-01 09:00 (no seconds)
+    "%Y-%m-%dT%H:%M:%S", // 2025-10-01T09:00:00
+    "%Y-%m-%dT%H:%M",    // 2025-10-01T09:00 (no seconds)
+    "%Y/%m/%d %H:%M:%S", // 2025/10/01 09:00:00
+    "%Y/%m/%d %H:%M",    // 2025/10/01 09:00
+    "%d/%m/%Y %H:%M:%S", // 01/10/2025 09:00:00
+    "%d/%m/%Y %H:%M",    // 01/10/2025 09:00
+    "%m/%d/%Y %H:%M:%S", // 10/01/2025 09:00:00
+    "%m/%d/%Y %H:%M",    // 10/01/2025 09:00
+];
+
+fn ungrouped(locale: LocaleFormat) -> LocaleFormat {
+    LocaleFormat { thousands_separator: String::new(), ..locale }
+}
+
+fn is_boolean(value: &str) -> bool {
+    value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+}
+
+/// A plain or comma-grouped decimal number such as `1200.5` or `1,200.50`
+fn parse_grouped_number(text: &str) -> Option<f64> {
+    static GROUPED: OnceLock<Regex> = OnceLock::new();
+    let grouped = GROUPED.get_or_init(|| Regex::new(r"^(\d{1,3}(,\d{3})+|\d+)(\.\d+)?$|^\.\d+$").unwrap());
+
+    if !grouped.is_match(text) {
+        return None;
+    }
+    text.replace(',', "").parse::<f64>().ok()
+}
+
+/// Amount and currency of `$1,200.50`, `-€5`, `(£3.00)`, `USD 12` or `12.00 EUR`.
+/// A symbol or code is required; a bare number is not currency.
+fn parse_currency(value: &str) -> Option<(f64, String)> {
+    let mut text = value.trim();
+    let mut negative = false;
+
+    // Accounting style negatives
+    if let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        negative = true;
+        text = inner.trim();
+    }
+    if let Some(rest) = text.strip_prefix('-') {
+        negative = !negative;
+        text = rest.trim_start();
+    }
+
+    // Longest symbols first so `R$` is not read as `$`
+    let mut symbols = CURRENCY_SYMBOLS.to_vec();
+    symbols.sort_by_key(|s| std::cmp::Reverse(s.len()));
+
+    let mut currency = None;
+    for symbol in symbols.iter().chain(CURRENCY_CODES.iter()) {
+        let is_code = symbol.len() == 3 && symbol.chars().all(|c| c.is_ascii_uppercase());
+        if let Some(rest) = text.strip_prefix(symbol) {
+            if !is_code || rest.starts_with(' ') {
+                currency = Some(symbol.to_string());
+                text = rest.trim_start();
+                break;
+            }
+        }
+        if let Some(rest) = text.strip_suffix(symbol) {
+            if !is_code || rest.ends_with(' ') {
+                currency = Some(symbol.to_string());
+                text = rest.trim_end();
+                break;
+            }
+        }
+    }
+    let currency = currency?;
+
+    if let Some(rest) = text.strip_prefix('-') {
+        negative = !negative;
+        text = rest;
+    }
+
+    let amount = parse_grouped_number(text)?;
+    Some((if negative { -amount } else { amount }, currency))
+}
+
+fn parse_percentage(value: &str) -> Option<f64> {
+    let number = value.strip_suffix('%')?.trim_end();
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, number.strip_prefix('+').unwrap_or(number)),
+    };
+    let percent = parse_grouped_number(number)?;
+    Some(if negative { -percent } else { percent } / 100.0)
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    ["%H:%M", "%H:%M:%S", "%H:%M:%S%.f", "%I:%M %p", "%I:%M:%S %p", "%I:%M%p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+}
+
+/// Seconds in an ISO 8601 duration such as `P3DT4H` or `PT90S`, or a
+/// unit form such as `1h 30m` or `2d4h`
+fn parse_duration(value: &str) -> Option<f64> {
+    static ISO: OnceLock<Regex> = OnceLock::new();
+    static UNITS: OnceLock<Regex> = OnceLock::new();
+    static UNIT: OnceLock<Regex> = OnceLock::new();
+
+    let iso = ISO.get_or_init(|| {
+        Regex::new(
+            r"^P(?:(\d+(?:\.\d+)?)Y)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)W)?(?:(\d+(?:\.\d+)?)D)?(?:T(?:(\d+(?:\.\d+)?)H)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)S)?)?$",
+        )
+        .unwrap()
+    });
+
+    if let Some(captures) = iso.captures(value) {
+        const UNIT_SECONDS: [f64; 7] = [365.0 * 86400.0, 30.0 * 86400.0, 7.0 * 86400.0, 86400.0, 3600.0, 60.0, 1.0];
+        let mut seconds = 0.0;
+        let mut any = false;
+        for (i, unit) in UNIT_SECONDS.iter().enumerate() {
+            if let Some(amount) = captures.get(i + 1) {
+                seconds += amount.as_str().parse::<f64>().ok()? * unit;
+                any = true;
+            }
+        }
+        return any.then_some(seconds);
+    }
+
+    let units = UNITS.get_or_init(|| Regex::new(r"^(?:\d+(?:\.\d+)?\s*(?:ms|d|h|m|s)\s*)+$").unwrap());
+    if !units.is_match(value) {
+        return None;
+    }
+
+    let unit = UNIT.get_or_init(|| Regex::new(r"(\d+(?:\.\d+)?)\s*(ms|d|h|m|s)").unwrap());
+    let seconds = unit
+        .captures_iter(value)
+        .map(|c| {
+            let amount: f64 = c[1].parse().unwrap_or(0.0);
+            amount * match &c[2] {
+                "d" => 86400.0,
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                _ => 0.001,
+            }
+        })
+        .sum();
+    Some(seconds)
+}
+
+fn parse_uuid(value: &str) -> Option<String> {
+    let text = value
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .unwrap_or(value);
+
+    let groups: Vec<&str> = text.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let valid = lengths == [8, 4, 4, 4, 12]
+        && groups.iter().all(|g| g.chars().all(|c| c.is_ascii_hexdigit()));
+
+    valid.then(|| text.to_lowercase())
+}
+
+/// Phone numbers need a leading `+` or separators, so plain integers are
+/// not mistaken for them. Dots alone are not enough either, since they
+/// also group thousands.
+fn parse_phone(value: &str) -> Option<String> {
+    let international = value.starts_with('+');
+    let body = value.strip_prefix('+').unwrap_or(value);
+
+    if !body.chars().all(|c| c.is_ascii_digit() || " -.()".contains(c)) {
+        return None;
+    }
+    if !international && !body.chars().any(|c| " -()".contains(c)) {
+        return None;
+    }
+
+    let digits: String = body.chars().filter(|c| c.is_ascii_digit()).collect();
+    if !(7..=15).contains(&digits.len()) {
+        return None;
+    }
+
+    Some(if international { format!("+{}", digits) } else { digits })
+}
+
+/// A type declared by the user for one column. Declared types take the
+/// place of `DataTypeDetector`'s guess wherever values are interpreted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColumnTypeOverride {
+    pub data_type: DataType,
+    /// chrono format for date and datetime columns, e.g. `%d.%m.%Y`
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Number pattern such as `#,##0.00` or `#.##0,00`, giving the
+    /// grouping and decimal separators
+    #[serde(default)]
+    pub number_format: Option<String>,
+    /// BCP 47 tag, e.g. `de-DE`; supplies separators when no number format is set
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default = "default_nullable")]
+    pub nullable: bool,
+    /// Allowed values; empty allows anything of the declared type
+    #[serde(default)]
+    pub enum_values: Vec<String>,
+}
+
+fn default_nullable() -> bool {
+    true
+}
+
+impl ColumnTypeOverride {
+    pub fn new(data_type: DataType) -> Self {
+        Self {
+            data_type,
+            date_format: None,
+            number_format: None,
+            locale: None,
+            nullable: true,
+            enum_values: Vec::new(),
+        }
+    }
+
+    /// Separators from the number format, else the locale
+    fn locale_format(&self) -> LocaleFormat {
+        let Some(format) = &self.number_format else {
+            return LocaleFormat::for_language_tag(self.locale.as_deref().unwrap_or(""));
+        };
+
+        let marks: Vec<(usize, char)> = format
+            .char_indices()
+            .filter(|(_, c)| matches!(c, ',' | '.' | ' ' | '\''))
+            .collect();
+        let (grouping, decimal) = match (marks.first(), marks.last()) {
+            (Some(&(_, first)), Some(&(_, last))) if first != last => (Some(first), last),
+            // `#,##0` groups thousands, while `0.00` or `0,0` marks decimals
+            (Some(&(_, mark)), Some(&(position, _))) if format.len() - position == 4 => {
+                (Some(mark), if mark == ',' { '.' } else { ',' })
+            }
+            (Some(&(_, mark)), _) => (None, mark),
+            _ => (None, '.'),
+        };
+
+        LocaleFormat {
+            decimal_separator: decimal.to_string(),
+            thousands_separator: grouping.map(String::from).unwrap_or_default(),
+            date_format: String::new(),
+        }
+    }
+
+    /// Parse a number written with the declared separators
+    pub fn parse_number(&self, value: &str) -> Option<f64> {
+        self.locale_format().parse_number(value)
+    }
+
+    /// Parse a date or datetime with the declared format, or the
+    /// detector's formats when none is declared
+    pub fn parse_date(&self, value: &str) -> Option<NaiveDateTime> {
+        let value = value.trim();
+        let formats: Vec<&str> = match &self.date_format {
+            Some(format) => vec![format.as_str()],
+            None => vec!["%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"],
+        };
+
+        formats.into_iter().find_map(|format| {
+            NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .or_else(|| NaiveDate::parse_from_str(value, format).ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+        })
+    }
+
+    /// Why `value` does not fit the declaration, or `None` if it does
+    pub fn check(&self, value: &str, detector: &DataTypeDetector) -> Option<String> {
+        if value.trim().is_empty() {
+            return (!self.nullable).then(|| "Value is required".to_string());
+        }
+
+        if !self.enum_values.is_empty() && !self.enum_values.iter().any(|v| v == value) {
+            return Some(format!("Value '{}' is not one of: {}", value, self.enum_values.join(", ")));
+        }
+
+        // Without declared separators numbers are read like the detector reads them
+        let number = if self.number_format.is_none() && self.locale.is_none() {
+            detector.number_locale().parse_number(value)
+        } else {
+            self.parse_number(value)
+        };
+        let valid = match self.data_type {
+            DataType::Integer => number.is_some_and(|n| n.fract() == 0.0),
+            DataType::Float => number.is_some(),
+            DataType::Date | DataType::DateTime if self.date_format.is_some() => self.parse_date(value).is_some(),
+            _ => detector.validate_value(value, &self.data_type),
+        };
+
+        (!valid).then(|| format!(
+            "Value '{}' is not a valid {}",
+            value,
+            format!("{:?}", self.data_type).to_lowercase()
+        ))
+    }
+
+    /// Order two values by their declared type. Enumerations sort in the
+    /// declared order; values that do not parse fall back to text order.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let typed = match self.data_type {
+            DataType::Integer | DataType::Float => match (self.parse_number(a), self.parse_number(b)) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => None,
+            },
+            DataType::Date | DataType::DateTime => match (self.parse_date(a), self.parse_date(b)) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => None,
+            },
+            ref data_type if data_type.has_parsed_form() => {
+                match (ParsedValue::parse(a, data_type), ParsedValue::parse(b, data_type)) {
+                    (Some(a), Some(b)) => a.compare(&b),
+                    _ => None,
+                }
+            }
+            _ if !self.enum_values.is_empty() => {
+                let position = |v: &str| self.enum_values.iter().position(|e| e == v);
+                match (position(a), position(b)) {
+                    (Some(a), Some(b)) => Some(a.cmp(&b)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        typed.unwrap_or_else(|| a.cmp(b))
+    }
+
+    /// The value as JSON: numbers and booleans unquoted, empty cells as
+    /// null, everything else (zip codes declared as text included) as a string
+    pub fn to_json(&self, value: &str) -> serde_json::Value {
+        if value.trim().is_empty() && self.data_type != DataType::Text {
+            return serde_json::Value::Null;
+        }
+
+        match self.data_type {
+            DataType::Integer => match self.parse_number(value) {
+                Some(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => serde_json::json!(n as i64),
+                _ => serde_json::json!(value),
+            },
+            DataType::Float => self
+                .parse_number(value)
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number)
+                .unwrap_or_else(|| serde_json::json!(value)),
+            ref data_type if data_type.has_parsed_form() => {
+                ParsedValue::parse(value, data_type)
+                    .map(|parsed| parsed.to_json())
+                    .unwrap_or_else(|| serde_json::json!(value))
+            }
+            DataType::Boolean if value.eq_ignore_ascii_case("true") => serde_json::Value::Bool(true),
+            DataType::Boolean if value.eq_ignore_ascii_case("false") => serde_json::Value::Bool(false),
+            _ => serde_json::json!(value),
+        }
+    }
+}
+
+/// Whether some numeric dates read both ways (`01/02/2024`) and no value
+/// like `13/02/2024` or `02/13/2024` shows the order. Values showing both
+/// orders are ambiguous too.
+fn has_ambiguous_day_month(values: &[&str]) -> bool {
+    static NUMERIC_DATE: OnceLock<Regex> = OnceLock::new();
+    let numeric_date = NUMERIC_DATE.get_or_init(|| Regex::new(r"^(\d{1,2})[/.-](\d{1,2})[/.-]\d{2,4}\b").unwrap());
+
+    let (mut either, mut day_first, mut month_first) = (false, false, false);
+    for value in values {
+        let Some(captures) = numeric_date.captures(value.trim()) else {
+            continue;
+        };
+        let first: u32 = captures[1].parse().unwrap_or(0);
+        let second: u32 = captures[2].parse().unwrap_or(0);
+        match (first > 12, second > 12) {
+            (true, false) => day_first = true,
+            (false, true) => month_first = true,
+            (false, false) if first != second => either = true,
+            _ => {}
+        }
+    }
+
+    either && day_first == month_first
+}
+
+/// Whether plain digit strings look like codes rather than quantities
+fn looks_like_ids(values: &[&str]) -> bool {
+    let digits: Vec<&str> = values
+        .iter()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+    if digits.len() < 2 || digits.len() < values.len() {
+        return false;
+    }
+
+    if digits.iter().any(|v| v.len() > 1 && v.starts_with('0')) {
+        return true;
+    }
+
+    let width = digits[0].len();
+    let distinct: std::collections::HashSet<&&str> = digits.iter().collect();
+    width >= 5 && digits.iter().all(|v| v.len() == width) && distinct.len() == digits.len()
+}
+
+/// Share of a column's non-empty values detected as one type
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TypeCandidate {
+    pub data_type: DataType,
+    pub count: usize,
+    pub confidence: f64,
+}
+
+/// Reasons a detected type may not be what the column means
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TypeAmbiguity {
+    /// Dates like `01/02/2024` read either day-first or month-first, and
+    /// no value in the column settles which
+    DayMonthOrder,
+    /// Whole numbers that look like identifiers: leading zeros, or distinct
+    /// values of one fixed width. Treating them as numbers may lose meaning.
+    IntegerLikeId,
+}
+
+/// Detected type of a column with how sure the detection is
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TypeInference {
+    pub data_type: DataType,
+    /// Share of non-empty values that are valid as `data_type`
+    pub confidence: f64,
+    /// Every type seen, most frequent first
+    pub candidates: Vec<TypeCandidate>,
+    /// Rows whose non-empty value is not valid as `data_type`
+    pub nonconforming_rows: Vec<usize>,
+    pub ambiguities: Vec<TypeAmbiguity>,
+}
+
 pub struct DataTypeDetector {
     email_regex: Regex,
     url_regex: Regex,
+    locale: LocaleFormat,
+    /// `locale` for reading numbers, without its thousands separator unless
+    /// a locale other than the default was declared
+    numbers: LocaleFormat,
 }
 
 impl DataTypeDetector {
@@ -26,51 +581,124 @@ impl DataTypeDetector {
         Self {
             email_regex: Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap(),
             url_regex: Regex::new(r"^https?://[^\s/$.?#].[^\s]*$").unwrap(),
+            locale: LocaleFormat::default(),
+            numbers: ungrouped(LocaleFormat::default()),
         }
     }
 
+    /// Read numbers and dates written in `locale`. Grouped numbers such as
+    /// `1.234` are only read for a locale whose separators differ from the
+    /// default, so `1,234` stays text as it always has when none is set.
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        let default = LocaleFormat::default();
+        let declared = locale.decimal_separator != default.decimal_separator
+            || locale.thousands_separator != default.thousands_separator;
+        self.numbers = if declared { locale.clone() } else { ungrouped(locale.clone()) };
+        self.locale = locale;
+        self
+    }
+
+    pub fn locale(&self) -> &LocaleFormat {
+        &self.locale
+    }
+
+    /// The locale numbers are detected and validated with
+    pub fn number_locale(&self) -> &LocaleFormat {
+        &self.numbers
+    }
+
     pub fn detect_column_type(&self, values: &[String]) -> DataType {
-        if values.is_empty() {
-            return DataType::Text;
+        self.infer_column_type(values.iter().enumerate()).data_type
+    }
+
+    /// Infer the type of a column from `(row index, value)` pairs. The most
+    /// frequent type wins, ties going to the type seen first.
+    pub fn infer_column_type<S: AsRef<str>>(&self, values: impl IntoIterator<Item = (usize, S)>) -> TypeInference {
+        let values: Vec<(usize, S)> = values
+            .into_iter()
+            .filter(|(_, value)| !value.as_ref().is_empty())
+            .collect();
+
+        // Count occurrences of each type, in order of first appearance
+        let mut candidates: Vec<TypeCandidate> = Vec::new();
+        for (_, value) in &values {
+            let detected_type = self.detect_value_type(value.as_ref());
+            match candidates.iter_mut().find(|c| c.data_type == detected_type) {
+                Some(candidate) => candidate.count += 1,
+                None => candidates.push(TypeCandidate { data_type: detected_type, count: 1, confidence: 0.0 }),
+            }
         }
+        for candidate in &mut candidates {
+            candidate.confidence = candidate.count as f64 / values.len() as f64;
+        }
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.count));
 
-        // Count occurrences of each type
-        let mut type_counts = std::collections::HashMap::new();
+        let data_type = candidates
+            .first()
+            .map(|c| c.data_type.clone())
+            .unwrap_or(DataType::Text);
 
-        for value in values.iter().filter(|v| !v.is_empty()) {
-            let detected_type = self.detect_value_type(value);
-            *type_counts.entry(detected_type).or_insert(0) += 1;
+        let nonconforming_rows: Vec<usize> = values
+            .iter()
+            .filter(|(_, value)| !self.validate_value(value.as_ref(), &data_type))
+            .map(|(row, _)| *row)
+            .collect();
+        let confidence = if values.is_empty() {
+            0.0
+        } else {
+            (values.len() - nonconforming_rows.len()) as f64 / values.len() as f64
+        };
+
+        let texts: Vec<&str> = values.iter().map(|(_, value)| value.as_ref()).collect();
+        let mut ambiguities = Vec::new();
+        if matches!(data_type, DataType::Date | DataType::DateTime) && has_ambiguous_day_month(&texts) {
+            ambiguities.push(TypeAmbiguity::DayMonthOrder);
         }
+        if data_type == DataType::Integer && looks_like_ids(&texts) {
+            ambiguities.push(TypeAmbiguity::IntegerLikeId);
+        }
+
+        TypeInference {
+            data_type,
+            confidence,
+            candidates,
+            nonconforming_rows,
+            ambiguities,
+        }
+    }
 
-        // Find the most common type
-        type_counts
+    /// Detect the type of every column from the first `sample_size` rows,
+    /// one column per rayon task
+    pub fn detect_column_types(&self, rows: &[Vec<String>], column_count: usize, sample_size: usize) -> Vec<DataType> {
+        self.infer_column_types(rows, column_count, sample_size)
             .into_iter()
-            .max_by_key(|(_, count)| *count)
-            .map(|(data_type, _)| data_type)
-            .unwrap_or(DataType::Text)
+            .map(|inference| inference.data_type)
+            .collect()
+    }
+
+    /// Like `detect_column_types`, keeping the confidence and ambiguities
+    pub fn infer_column_types(&self, rows: &[Vec<String>], column_count: usize, sample_size: usize) -> Vec<TypeInference> {
+        let sample = &rows[..rows.len().min(sample_size)];
+
+        (0..column_count)
+            .into_par_iter()
+            .map(|index| {
+                self.infer_column_type(
+                    sample
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(row, values)| Some((row, values.get(index)?.as_str()))),
+                )
+            })
+            .collect()
     }
 
     /// Detect the actual datetime format used in a value
     pub fn detect_datetime_format(&self, value: &str) -> Option<String> {
-        let datetime_formats = [
-            ("%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M:%S"),
-            ("%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M"),
-            ("%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M:%S"),
-            ("%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M"),
-            ("%Y/%m/%d %H:%M:%S", "%Y/%m/%d %H:%M:%S"),
-            ("%Y/%m/%d %H:%M", "%Y/%m/%d %H:%M"),
-            ("%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M:%S"),
-            ("%d/%m/%Y %H:%M", "%d/%m/%Y %H:%M"),
-            ("%m/%d/%Y %H:%M:%S", "%m/%d/%Y %H:%M:%S"),
-            ("%m/%d/%Y %H:%M", "%m/%d/%Y %H:%M"),
-        ];
-        
-        for (format, _) in &datetime_formats {
-            if NaiveDateTime::parse_from_str(value, format).is_ok() {
-                return Some(format.to_string());
-            }
-        }
-        None
+        DATETIME_FORMATS
+            .iter()
+            .find(|format| NaiveDateTime::parse_from_str(value, format).is_ok())
+            .map(|format| format.to_string())
     }
 
     /// Detect the most common datetime format in a column
@@ -95,45 +723,49 @@ impl DataTypeDetector {
         }
 
         // Check boolean
-        if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        if is_boolean(value) {
             return DataType::Boolean;
         }
 
         // Check integer
-        if value.parse::<i64>().is_ok() {
+        if value.parse::<i64>().is_ok() || self.numbers.parse_integer(value).is_some() {
             return DataType::Integer;
         }
 
         // Check float
-        if value.parse::<f64>().is_ok() {
+        if self.numbers.parse_number(value).is_some() {
             return DataType::Float;
         }
 
+        // Check percentages and amounts, which are numbers with a marker
+        if parse_percentage(value).is_some() {
+            return DataType::Percentage;
+        }
+        if parse_currency(value).is_some() {
+            return DataType::Currency;
+        }
+
         // Check date formats
-        if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() ||
-           NaiveDate::parse_from_str(value, "%d/%m/%Y").is_ok() ||
-           NaiveDate::parse_from_str(value, "%m/%d/%Y").is_ok() {
+        if self.locale.parse_date(value).is_some() {
             return DataType::Date;
         }
 
         // Check datetime formats (with various time formats)
-        let datetime_formats = [
-            "%Y-%m-%d %H:%M:%S",      // 2025-10-01 09:00:00
-            "%Y-%m-%d %H:%M",         // 2025-10-01 09:00 (no seconds)
-            "%Y-%m-%dT%H:%M:%S",      // 2025-10-01T09:00:00
-            "%Y-%m-%dT%H:%M",         // 2025-10-01T09:00 (no seconds)
-            "%Y/%m/%d %H:%M:%S",      // 2025/10/01 09:00:00
-            "%Y/%m/%d %H:%M",         // 2025/10/01 09:00
-            "%d/%m/%Y %H:%M:%S",      // 01/10/2025 09:00:00
-            "%d/%m/%Y %H:%M",         // 01/10/2025 09:00
-            "%m/%d/%Y %H:%M:%S",      // 10/01/2025 09:00:00
-            "%m/%d/%Y %H:%M",         // 10/01/2025 09:00
-        ];
-        
-        for format in &datetime_formats {
-            if NaiveDateTime::parse_from_str(value, format).is_ok() {
-                return DataType::DateTime;
-            }
+        if self.detect_datetime_format(value).is_some() {
+            return DataType::DateTime;
+        }
+
+        if parse_time(value).is_some() {
+            return DataType::Time;
+        }
+        if parse_duration(value).is_some() {
+            return DataType::Duration;
+        }
+        if parse_uuid(value).is_some() {
+            return DataType::Uuid;
+        }
+        if value.parse::<IpAddr>().is_ok() {
+            return DataType::IpAddress;
         }
 
         // Check email
@@ -146,6 +778,11 @@ impl DataTypeDetector {
             return DataType::Url;
         }
 
+        // Checked after dates, which share its digits and dashes
+        if parse_phone(value).is_some() {
+            return DataType::Phone;
+        }
+
         // Check JSON
         if (value.starts_with('{') && value.ends_with('}')) ||
            (value.starts_with('[') && value.ends_with(']')) {
@@ -163,26 +800,32 @@ impl DataTypeDetector {
         }
 
         match data_type {
-            DataType::Integer => value.parse::<i64>().is_ok(),
-            DataType::Float => value.parse::<f64>().is_ok(),
-            DataType::Boolean => {
-                value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
-            }
-            DataType::Date => {
-                NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() ||
-                NaiveDate::parse_from_str(value, "%d/%m/%Y").is_ok() ||
-                NaiveDate::parse_from_str(value, "%m/%d/%Y").is_ok()
-            }
-            DataType::DateTime => {
-                NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").is_ok() ||
-                NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").is_ok()
-            }
+            DataType::Integer => value.parse::<i64>().is_ok() || self.numbers.parse_integer(value).is_some(),
+            DataType::Float => self.numbers.parse_number(value).is_some(),
+            DataType::Boolean => is_boolean(value),
+            DataType::Date => self.locale.parse_date(value).is_some(),
+            DataType::DateTime => self.detect_datetime_format(value).is_some(),
             DataType::Email => self.email_regex.is_match(value),
             DataType::Url => self.url_regex.is_match(value),
             DataType::Json => serde_json::from_str::<serde_json::Value>(value).is_ok(),
+            DataType::Currency
+            | DataType::Percentage
+            | DataType::Time
+            | DataType::Duration
+            | DataType::Uuid
+            | DataType::IpAddress
+            | DataType::Phone => ParsedValue::parse(value, data_type).is_some(),
             DataType::Text => true,
         }
     }
+
+    /// Normalized form of `value` read as `data_type`, see `ParsedValue::parse`
+    pub fn parse_value(&self, value: &str, data_type: &DataType) -> Option<ParsedValue> {
+        match data_type {
+            DataType::Integer | DataType::Float => self.numbers.parse_number(value).map(ParsedValue::Number),
+            _ => ParsedValue::parse(value, data_type),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -223,9 +866,162 @@ mod tests {
         assert_eq!(detector.detect_value_type("test@example.com"), DataType::Email);
     }
 
+    #[test]
+    fn test_override_parses_declared_formats() {
+        let detector = DataTypeDetector::new();
+
+        let amount = ColumnTypeOverride {
+            number_format: Some("#.##0,00".to_string()),
+            ..ColumnTypeOverride::new(DataType::Float)
+        };
+        assert_eq!(amount.parse_number("1.234,50"), Some(1234.5));
+        assert_eq!(amount.compare("9,00", "1.000,00"), Ordering::Less);
+
+        let shipped = ColumnTypeOverride {
+            date_format: Some("%d.%m.%Y".to_string()),
+            nullable: false,
+            ..ColumnTypeOverride::new(DataType::Date)
+        };
+        assert!(shipped.check("31.01.2024", &detector).is_none());
+        assert!(shipped.check("2024-01-31", &detector).is_some());
+        assert_eq!(shipped.check("", &detector).as_deref(), Some("Value is required"));
+
+        let zip = ColumnTypeOverride::new(DataType::Text);
+        assert_eq!(zip.to_json("00501"), serde_json::json!("00501"));
+
+        let status = ColumnTypeOverride {
+            enum_values: vec!["new".to_string(), "shipped".to_string()],
+            ..ColumnTypeOverride::new(DataType::Text)
+        };
+        assert!(status.check("lost", &detector).is_some());
+        assert_eq!(status.compare("shipped", "new"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_detect_richer_types() {
+        let detector = DataTypeDetector::new();
+        let cases = [
+            ("$1,200.50", DataType::Currency),
+            ("(€3.00)", DataType::Currency),
+            ("12.00 EUR", DataType::Currency),
+            ("45%", DataType::Percentage),
+            ("14:30", DataType::Time),
+            ("2:30 PM", DataType::Time),
+            ("P3DT4H", DataType::Duration),
+            ("1h 30m", DataType::Duration),
+            ("550e8400-e29b-41d4-a716-446655440000", DataType::Uuid),
+            ("192.168.0.1", DataType::IpAddress),
+            ("2001:db8::1", DataType::IpAddress),
+            ("+1 (555) 123-4567", DataType::Phone),
+            ("2024-01-15", DataType::Date),
+            ("1.234.567", DataType::Text),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(detector.detect_value_type(value), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_parse_normalizes_values() {
+        let parse = |value: &str, data_type: DataType| ParsedValue::parse(value, &data_type).unwrap();
+
+        assert_eq!(
+            parse("($1,200.50)", DataType::Currency),
+            ParsedValue::Currency { amount: -1200.5, currency: "$".to_string() }
+        );
+        assert_eq!(parse("45%", DataType::Percentage), ParsedValue::Percentage(0.45));
+        assert_eq!(parse("P3DT4H", DataType::Duration), ParsedValue::Duration(3.0 * 86400.0 + 4.0 * 3600.0));
+        assert_eq!(parse("2:30 PM", DataType::Time).as_number(), Some(14.5 * 3600.0));
+        assert_eq!(
+            parse("{550E8400-E29B-41D4-A716-446655440000}", DataType::Uuid),
+            ParsedValue::Uuid("550e8400-e29b-41d4-a716-446655440000".to_string())
+        );
+        assert_eq!(parse("+44 20 7946 0958", DataType::Phone), ParsedValue::Phone("+442079460958".to_string()));
+
+        let ip = ColumnTypeOverride::new(DataType::IpAddress);
+        assert_eq!(ip.compare("10.0.0.9", "10.0.0.10"), Ordering::Less);
+        assert!(!DataTypeDetector::new().validate_value("1.2", &DataType::Percentage));
+    }
+
     #[test]
     fn test_detect_url() {
         let detector = DataTypeDetector::new();
         assert_eq!(detector.detect_value_type("https://example.com"), DataType::Url);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_grouped_numbers_need_a_declared_locale() {
+        let detector = DataTypeDetector::new();
+        assert_eq!(detector.detect_value_type("1,234"), DataType::Text);
+        assert_eq!(detector.detect_value_type("1,234.5"), DataType::Text);
+        assert_eq!(detector.detect_value_type("1234.5"), DataType::Float);
+        assert!(!detector.validate_value("1,234", &DataType::Integer));
+
+        // The settings default is the same as no locale
+        let detector = DataTypeDetector::new().with_locale(LocaleFormat {
+            date_format: "%Y-%m-%d".to_string(),
+            ..LocaleFormat::default()
+        });
+        assert_eq!(detector.detect_value_type("1,234"), DataType::Text);
+
+        let detector = DataTypeDetector::new().with_locale(LocaleFormat::for_language_tag("fr-FR"));
+        assert_eq!(detector.detect_value_type("12 345 678"), DataType::Integer);
+        assert_eq!(detector.detect_value_type("1 234,5"), DataType::Float);
+        // Not grouped in threes, so not a number
+        assert_eq!(detector.detect_value_type("12 34"), DataType::Text);
+    }
+
+    #[test]
+    fn test_detect_with_locale() {
+        let detector = DataTypeDetector::new().with_locale(LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+        });
+        assert_eq!(detector.detect_value_type("1.234,56"), DataType::Float);
+        assert_eq!(detector.detect_value_type("1.234"), DataType::Integer);
+        assert_eq!(detector.detect_value_type("31.12.2024"), DataType::Date);
+        assert_eq!(detector.detect_value_type("令和6年12月31日"), DataType::Date);
+        assert!(detector.validate_value("１２３", &DataType::Integer));
+    }
+
+    #[test]
+    fn test_infer_column_type_reports_confidence_and_ambiguity() {
+        let detector = DataTypeDetector::new();
+
+        let values = ["01/02/2024", "03/04/2024", "", "soon", "05/06/2024"];
+        let inference = detector.infer_column_type(values.iter().enumerate());
+        assert_eq!(inference.data_type, DataType::Date);
+        assert_eq!(inference.confidence, 0.75);
+        assert_eq!(inference.nonconforming_rows, vec![3]);
+        assert_eq!(inference.candidates[0].count, 3);
+        assert_eq!(inference.candidates[1].data_type, DataType::Text);
+        assert_eq!(inference.ambiguities, vec![TypeAmbiguity::DayMonthOrder]);
+
+        // 13/02 can only be day-first, which settles the column
+        let values = ["01/02/2024", "13/02/2024"];
+        assert!(detector.infer_column_type(values.iter().enumerate()).ambiguities.is_empty());
+
+        let values = ["00501", "10001", "90210"];
+        let inference = detector.infer_column_type(values.iter().enumerate());
+        assert_eq!(inference.data_type, DataType::Integer);
+        assert_eq!(inference.ambiguities, vec![TypeAmbiguity::IntegerLikeId]);
+
+        let values = ["3", "14", "15"];
+        assert!(detector.infer_column_type(values.iter().enumerate()).ambiguities.is_empty());
+    }
+
+    #[test]
+    fn test_inferred_datetime_columns_validate() {
+        let detector = DataTypeDetector::new();
+        let values = ["2025-10-01 09:00", "2025-10-02 17:30", "2025/10/03 08:15:00"];
+
+        let inference = detector.infer_column_type(values.iter().enumerate());
+        assert_eq!(inference.data_type, DataType::DateTime);
+        assert_eq!(inference.confidence, 1.0);
+        assert!(inference.nonconforming_rows.is_empty());
+        assert!(values.iter().all(|v| detector.validate_value(v, &DataType::DateTime)));
+        assert!(!detector.validate_value("2025-10-01", &DataType::DateTime));
+        assert!(detector.validate_value("FALSE", &DataType::Boolean));
+    }
+}
@@ -0,0 +1,582 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use super::cleansing::RemovedRow;
+use super::locale::{to_half_width, LocaleFormat};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SimilarityMetric {
+    Exact,
+    Levenshtein,
+    JaroWinkler,
+    /// Compares the distinct words, ignoring order and repeats
+    TokenSet,
+}
+
+impl SimilarityMetric {
+    /// Similarity of two normalized values, from 0 to 1
+    pub fn similarity(&self, a: &str, b: &str) -> f64 {
+        match self {
+            Self::Exact => f64::from(u8::from(a == b)),
+            Self::Levenshtein => levenshtein_similarity(a, b),
+            Self::JaroWinkler => jaro_winkler(a, b),
+            Self::TokenSet => token_set_similarity(a, b),
+        }
+    }
+}
+
+/// Lower case, full-width folded, with punctuation dropped and
+/// whitespace collapsed, so `ACME, Inc.` matches `acme inc`
+pub fn normalize(value: &str) -> String {
+    let folded = to_half_width(value).to_lowercase();
+    let cleaned: String = folded
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// One minus the edit distance over the longer length
+pub fn levenshtein_similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0usize;
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && b[j] == *ca {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_order = a.iter().zip(&a_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let b_order = b.iter().zip(&b_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let transpositions = a_order.zip(b_order).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Token set ratio: the shared words compared with each side's full word
+/// set, taking the best match, so `acme corp` matches `corp acme inc` well
+pub fn token_set_similarity(a: &str, b: &str) -> f64 {
+    let a_tokens: BTreeSet<&str> = a.split_whitespace().collect();
+    let b_tokens: BTreeSet<&str> = b.split_whitespace().collect();
+    if a_tokens.is_empty() && b_tokens.is_empty() {
+        return 1.0;
+    }
+
+    let join = |tokens: Vec<&str>| tokens.join(" ");
+    let shared = join(a_tokens.intersection(&b_tokens).copied().collect());
+    let with_rest = |rest: Vec<&str>| match (shared.is_empty(), rest.is_empty()) {
+        (true, _) => join(rest),
+        (false, true) => shared.clone(),
+        (false, false) => format!("{} {}", shared, join(rest)),
+    };
+    let a_full = with_rest(a_tokens.difference(&b_tokens).copied().collect());
+    let b_full = with_rest(b_tokens.difference(&a_tokens).copied().collect());
+
+    let mut best = levenshtein_similarity(&a_full, &b_full);
+    if !shared.is_empty() {
+        best = best
+            .max(levenshtein_similarity(&shared, &a_full))
+            .max(levenshtein_similarity(&shared, &b_full));
+    }
+    best
+}
+
+/// A column compared when matching rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchColumn {
+    pub column_index: usize,
+    pub metric: SimilarityMetric,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// How rows are grouped so only rows in the same block are compared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Blocking {
+    /// Same first characters of the normalized value
+    Prefix { column_index: usize, length: usize },
+    /// At least one word in common
+    Tokens { column_index: usize },
+    /// Every pair of rows; only for small data
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzyMatchOptions {
+    pub columns: Vec<MatchColumn>,
+    /// Lowest weighted similarity for two rows to match
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    /// Defaults to a 3-character prefix of the first column
+    #[serde(default)]
+    pub blocking: Option<Blocking>,
+    /// Blocks larger than this are skipped, since they are usually
+    /// formed by words such as `inc` that say little
+    #[serde(default = "default_max_block_size")]
+    pub max_block_size: usize,
+}
+
+fn default_threshold() -> f64 {
+    0.85
+}
+
+fn default_max_block_size() -> usize {
+    1000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchPair {
+    pub row_a: usize,
+    pub row_b: usize,
+    pub score: f64,
+}
+
+/// Rows linked by matching pairs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub row_indices: Vec<usize>,
+    pub pairs: Vec<MatchPair>,
+    /// Mean score of the matching pairs
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzyDuplicateReport {
+    pub clusters: Vec<DuplicateCluster>,
+    pub compared_pairs: usize,
+    pub skipped_blocks: usize,
+}
+
+/// Which row of a group of duplicates is kept
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "keep", rename_all = "snake_case")]
+pub enum SurvivorPolicy {
+    First,
+    Last,
+    /// Most non-empty fields, the first on ties
+    MostComplete,
+    /// Latest date in the column; rows without a readable date lose
+    MaxDate { column_index: usize },
+    /// Earliest date in the column
+    MinDate { column_index: usize },
+}
+
+impl SurvivorPolicy {
+    /// From the `keep` and `date_column` cleansing parameters; unknown or
+    /// incomplete policies keep the first row
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Self {
+        let date_column = parameters.get("date_column").and_then(|v| v.parse().ok());
+        match (parameters.get("keep").map(String::as_str), date_column) {
+            (Some("last"), _) => Self::Last,
+            (Some("most_complete"), _) => Self::MostComplete,
+            (Some("max_date"), Some(column_index)) => Self::MaxDate { column_index },
+            (Some("min_date"), Some(column_index)) => Self::MinDate { column_index },
+            _ => Self::First,
+        }
+    }
+
+    /// Row to keep among `rows`, given in row order
+    pub fn pick(&self, rows: &[usize], data: &[Vec<String>], locale: &LocaleFormat) -> usize {
+        let date = |row: usize, column: usize| {
+            data[row].get(column).and_then(|v| locale.parse_date(v))
+        };
+
+        let picked = match self {
+            Self::First => rows.first(),
+            Self::Last => rows.last(),
+            // `max_by_key` keeps the last maximum, so search in reverse for the first
+            Self::MostComplete => rows
+                .iter()
+                .rev()
+                .max_by_key(|&&row| data[row].iter().filter(|v| !v.trim().is_empty()).count()),
+            Self::MaxDate { column_index } => rows
+                .iter()
+                .rev()
+                .max_by_key(|&&row| date(row, *column_index)),
+            Self::MinDate { column_index } => rows
+                .iter()
+                .filter(|&&row| date(row, *column_index).is_some())
+                .min_by_key(|&&row| date(row, *column_index))
+                .or(rows.first()),
+        };
+        picked.copied().unwrap_or_default()
+    }
+}
+
+struct UnionFind(Vec<usize>);
+
+impl UnionFind {
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.0[x] != x {
+            self.0[x] = self.0[self.0[x]];
+            x = self.0[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.0[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+pub struct FuzzyMatcher {
+    options: FuzzyMatchOptions,
+}
+
+impl FuzzyMatcher {
+    pub fn new(options: FuzzyMatchOptions) -> Self {
+        Self { options }
+    }
+
+    /// Weighted similarity of two rows over the match columns. Columns
+    /// empty in both rows are left out.
+    fn score(&self, a: &[String], b: &[String]) -> f64 {
+        let (mut total, mut weights) = (0.0, 0.0);
+        for column in &self.options.columns {
+            let (x, y) = (&a[column.column_index], &b[column.column_index]);
+            if x.is_empty() && y.is_empty() {
+                continue;
+            }
+            let similarity = if x.is_empty() || y.is_empty() { 0.0 } else { column.metric.similarity(x, y) };
+            total += similarity * column.weight;
+            weights += column.weight;
+        }
+        if weights > 0.0 { total / weights } else { 0.0 }
+    }
+
+    fn blocking(&self) -> Blocking {
+        self.options.blocking.clone().unwrap_or(Blocking::Prefix {
+            column_index: self.options.columns.first().map_or(0, |c| c.column_index),
+            length: 3,
+        })
+    }
+
+    fn blocks(&self, normalized: &[Vec<String>]) -> HashMap<String, Vec<usize>> {
+        let blocking = self.blocking();
+
+        let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+        for (row_idx, row) in normalized.iter().enumerate() {
+            let keys: Vec<String> = match &blocking {
+                Blocking::Prefix { column_index, length } => {
+                    let value = row.get(*column_index).map(String::as_str).unwrap_or("");
+                    vec![value.chars().filter(|c| *c != ' ').take(*length).collect()]
+                }
+                Blocking::Tokens { column_index } => row
+                    .get(*column_index)
+                    .map(|v| v.split_whitespace().map(String::from).collect::<BTreeSet<_>>().into_iter().collect())
+                    .unwrap_or_default(),
+                Blocking::None => vec![String::new()],
+            };
+            for key in keys {
+                blocks.entry(key).or_default().push(row_idx);
+            }
+        }
+        blocks
+    }
+
+    pub fn find_duplicates(&self, data: &[Vec<String>]) -> FuzzyDuplicateReport {
+        // The blocking column is normalized too, even when it is not matched on
+        let blocking_column = match self.blocking() {
+            Blocking::Prefix { column_index, .. } | Blocking::Tokens { column_index } => Some(column_index),
+            Blocking::None => None,
+        };
+        let width = self
+            .options
+            .columns
+            .iter()
+            .map(|c| c.column_index)
+            .chain(blocking_column)
+            .map(|i| i + 1)
+            .max()
+            .unwrap_or(0);
+        let normalized: Vec<Vec<String>> = data
+            .par_iter()
+            .map(|row| {
+                (0..width)
+                    .map(|i| row.get(i).map(|v| normalize(v)).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        // Token blocks overlap, so pairs are collected once across blocks
+        let blocks = self.blocks(&normalized);
+        let skipped_blocks = blocks.values().filter(|rows| rows.len() > self.options.max_block_size).count();
+        let candidates: BTreeSet<(usize, usize)> = blocks
+            .values()
+            .filter(|rows| rows.len() > 1 && rows.len() <= self.options.max_block_size)
+            .flat_map(|rows| {
+                rows.iter()
+                    .enumerate()
+                    .flat_map(move |(i, &a)| rows[i + 1..].iter().map(move |&b| (a.min(b), a.max(b))))
+            })
+            .collect();
+        let candidates: Vec<(usize, usize)> = candidates.into_iter().collect();
+
+        let pairs: Vec<MatchPair> = candidates
+            .par_iter()
+            .filter_map(|&(a, b)| {
+                let score = self.score(&normalized[a], &normalized[b]);
+                (score >= self.options.threshold).then_some(MatchPair { row_a: a, row_b: b, score })
+            })
+            .collect();
+
+        let mut sets = UnionFind((0..data.len()).collect());
+        for pair in &pairs {
+            sets.union(pair.row_a, pair.row_b);
+        }
+        let mut clusters: HashMap<usize, DuplicateCluster> = HashMap::new();
+        for pair in pairs {
+            let root = sets.find(pair.row_a);
+            let cluster = clusters.entry(root).or_insert_with(|| DuplicateCluster {
+                row_indices: Vec::new(),
+                pairs: Vec::new(),
+                score: 0.0,
+            });
+            cluster.row_indices.extend([pair.row_a, pair.row_b]);
+            cluster.pairs.push(pair);
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = clusters
+            .into_values()
+            .map(|mut cluster| {
+                cluster.row_indices.sort_unstable();
+                cluster.row_indices.dedup();
+                cluster.score = cluster.pairs.iter().map(|p| p.score).sum::<f64>() / cluster.pairs.len() as f64;
+                cluster
+            })
+            .collect();
+        clusters.sort_by_key(|c| c.row_indices[0]);
+
+        FuzzyDuplicateReport {
+            clusters,
+            compared_pairs: candidates.len(),
+            skipped_blocks,
+        }
+    }
+}
+
+/// Cells filled into a survivor from the other rows of its cluster.
+/// `row_index` is the survivor's index once the removed rows are gone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedCell {
+    pub row_index: usize,
+    pub column_index: usize,
+    pub old_value: String,
+    pub value: String,
+    pub source_row: usize,
+}
+
+/// Keep one row per cluster and drop the rest. With `fill_empty`, empty
+/// cells of the survivor take the first non-empty value of the others.
+/// Returns the removed rows and the filled cells.
+pub fn merge_clusters(
+    data: &mut Vec<Vec<String>>,
+    clusters: &[DuplicateCluster],
+    policy: &SurvivorPolicy,
+    fill_empty: bool,
+    locale: &LocaleFormat,
+) -> (Vec<RemovedRow>, Vec<MergedCell>) {
+    let mut removed = BTreeSet::new();
+    let mut merged = Vec::new();
+
+    for cluster in clusters {
+        let rows: Vec<usize> = cluster.row_indices.iter().copied().filter(|&r| r < data.len()).collect();
+        if rows.len() < 2 {
+            continue;
+        }
+        let survivor = policy.pick(&rows, data, locale);
+
+        for &row in rows.iter().filter(|&&r| r != survivor) {
+            if fill_empty {
+                for column in 0..data[row].len() {
+                    let empty = data[survivor].get(column).is_none_or(|v| v.trim().is_empty());
+                    if empty && !data[row][column].trim().is_empty() {
+                        let value = data[row][column].clone();
+                        let old_value = data[survivor].get(column).cloned().unwrap_or_default();
+                        if data[survivor].len() <= column {
+                            data[survivor].resize(column + 1, String::new());
+                        }
+                        data[survivor][column] = value.clone();
+                        merged.push(MergedCell {
+                            row_index: survivor,
+                            column_index: column,
+                            old_value,
+                            value,
+                            source_row: row,
+                        });
+                    }
+                }
+            }
+            removed.insert(row);
+        }
+    }
+
+    let removed_rows = removed
+        .iter()
+        .map(|&row_index| RemovedRow { row_index, values: data[row_index].clone() })
+        .collect();
+    let mut index = 0;
+    data.retain(|_| {
+        index += 1;
+        !removed.contains(&(index - 1))
+    });
+    for cell in &mut merged {
+        cell.row_index -= removed.range(..cell.row_index).count();
+    }
+    (removed_rows, merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_metrics() {
+        assert_eq!(normalize("  ACME, Inc. "), "acme inc");
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert!((jaro_winkler("martha", "marhta") - 0.9611).abs() < 1e-3);
+        assert_eq!(token_set_similarity("acme corp", "corp acme"), 1.0);
+        assert!(token_set_similarity("acme corp", "acme corp inc") > 0.9);
+        assert_eq!(SimilarityMetric::Exact.similarity("a", "b"), 0.0);
+    }
+
+    #[test]
+    fn test_find_and_merge_duplicates() {
+        let mut data: Vec<Vec<String>> = [
+            ["Acme Corporation", "Berlin", ""],
+            ["Globex", "Tokyo", "2024-01-01"],
+            ["ACME Corporation.", "Berlin", "2024-03-01"],
+            ["Acme Corporatoin", "", "2023-12-01"],
+            ["Initech", "Austin", ""],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let matcher = FuzzyMatcher::new(FuzzyMatchOptions {
+            columns: vec![
+                MatchColumn { column_index: 0, metric: SimilarityMetric::JaroWinkler, weight: 2.0 },
+                MatchColumn { column_index: 1, metric: SimilarityMetric::Exact, weight: 1.0 },
+            ],
+            threshold: 0.6,
+            blocking: None,
+            max_block_size: 1000,
+        });
+        let report = matcher.find_duplicates(&data);
+        assert_eq!(report.clusters.len(), 1);
+        assert_eq!(report.clusters[0].row_indices, vec![0, 2, 3]);
+
+        let policy = SurvivorPolicy::MaxDate { column_index: 2 };
+        let (removed, merged) = merge_clusters(&mut data, &report.clusters, &policy, true, &LocaleFormat::default());
+        assert_eq!(removed.iter().map(|r| r.row_index).collect::<Vec<_>>(), vec![0, 3]);
+        assert!(merged.is_empty());
+        assert_eq!(data.len(), 3);
+        assert_eq!(data[1][0], "ACME Corporation.");
+    }
+
+    #[test]
+    fn test_blocking_on_a_column_that_is_not_matched() {
+        let data: Vec<Vec<String>> = [
+            ["Acme Corp", "10115"],
+            ["Acme Corp.", "10115"],
+            ["Acme Corp", "80331"],
+            ["Globex", "80331"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let matcher = FuzzyMatcher::new(FuzzyMatchOptions {
+            columns: vec![MatchColumn { column_index: 0, metric: SimilarityMetric::JaroWinkler, weight: 1.0 }],
+            threshold: 0.9,
+            blocking: Some(Blocking::Prefix { column_index: 1, length: 5 }),
+            max_block_size: 2,
+        });
+        let report = matcher.find_duplicates(&data);
+        assert_eq!(report.skipped_blocks, 0);
+        assert_eq!(report.compared_pairs, 2);
+        assert_eq!(report.clusters.len(), 1);
+        assert_eq!(report.clusters[0].row_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_merged_cells_point_at_the_compacted_rows() {
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let mut data = vec![
+            row(&["Acme", "a@acme.com"]),
+            row(&["Acme", ""]),
+            row(&["Globex", "g@globex.com"]),
+            row(&["Globex", " "]),
+        ];
+        let clusters = vec![
+            DuplicateCluster { row_indices: vec![0, 1], pairs: Vec::new(), score: 1.0 },
+            DuplicateCluster { row_indices: vec![2, 3], pairs: Vec::new(), score: 1.0 },
+        ];
+        let (removed, merged) =
+            merge_clusters(&mut data, &clusters, &SurvivorPolicy::Last, true, &LocaleFormat::default());
+
+        assert_eq!(removed.iter().map(|r| r.row_index).collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].row_index, merged[0].old_value.as_str()), (0, ""));
+        assert_eq!(data[merged[0].row_index][1], "a@acme.com");
+        assert_eq!((merged[1].row_index, merged[1].old_value.as_str()), (1, " "));
+        assert_eq!(data[merged[1].row_index][1], "g@globex.com");
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use crate::settings::ImportExportSettings;
+
+/// Quoting, escaping and cleanup rules used when reading and writing a file.
+/// Characters are stored as strings to match `ImportExportSettings`; only the
+/// first byte of each is used, and an empty string disables the option.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CsvDialect {
+    #[serde(default = "default_quote_char")]
+    pub quote_char: String,
+    /// Escape character used when `double_quote` is off (e.g. `\"`)
+    #[serde(default)]
+    pub escape_char: String,
+    /// Quotes inside quoted fields are written as `""`
+    #[serde(default = "default_double_quote")]
+    pub double_quote: bool,
+    /// Lines starting with this prefix are skipped when reading
+    #[serde(default)]
+    pub comment_prefix: String,
+    #[serde(default)]
+    pub trim_whitespace: bool,
+    #[serde(default)]
+    pub skip_empty_rows: bool,
+}
+
+fn default_quote_char() -> String {
+    "\"".to_string()
+}
+
+fn default_double_quote() -> bool {
+    true
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            quote_char: default_quote_char(),
+            escape_char: String::new(),
+            double_quote: default_double_quote(),
+            comment_prefix: String::new(),
+            trim_whitespace: false,
+            skip_empty_rows: false,
+        }
+    }
+}
+
+impl CsvDialect {
+    pub fn from_settings(settings: &ImportExportSettings) -> Self {
+        Self {
+            quote_char: settings.quote_character.clone(),
+            escape_char: settings.escape_character.clone(),
+            double_quote: settings.double_quote,
+            comment_prefix: settings.comment_character.clone(),
+            trim_whitespace: settings.trim_whitespace,
+            skip_empty_rows: settings.skip_empty_rows,
+        }
+    }
+
+    pub fn quote(&self) -> u8 {
+        first_byte(&self.quote_char).unwrap_or(b'"')
+    }
+
+    pub fn escape(&self) -> Option<u8> {
+        first_byte(&self.escape_char)
+    }
+
+    pub fn comment(&self) -> Option<u8> {
+        first_byte(&self.comment_prefix)
+    }
+
+    pub fn reader_builder(&self, delimiter: u8, has_headers: bool) -> csv::ReaderBuilder {
+        let mut builder = csv::ReaderBuilder::new();
+        builder
+            .delimiter(delimiter)
+            .has_headers(has_headers)
+            .quote(self.quote())
+            .double_quote(self.double_quote)
+            .comment(self.comment())
+            .trim(if self.trim_whitespace { csv::Trim::All } else { csv::Trim::None });
+
+        // With doubled quotes the escape character would also match `\` in
+        // ordinary values, so it is only applied in backslash mode
+        if !self.double_quote {
+            builder.escape(self.escape());
+        }
+
+        builder
+    }
+
+    pub fn writer_builder(&self, delimiter: u8) -> csv::WriterBuilder {
+        let mut builder = csv::WriterBuilder::new();
+        builder
+            .delimiter(delimiter)
+            .quote(self.quote())
+            .double_quote(self.double_quote);
+
+        if let Some(escape) = self.escape() {
+            builder.escape(escape);
+        }
+
+        builder
+    }
+
+    /// Whether a parsed record should be dropped under `skip_empty_rows`
+    pub fn should_skip(&self, record: &csv::StringRecord) -> bool {
+        self.skip_empty_rows && record.iter().all(|field| field.trim().is_empty())
+    }
+}
+
+fn first_byte(value: &str) -> Option<u8> {
+    value.as_bytes().first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(dialect: &CsvDialect, text: &str) -> Vec<Vec<String>> {
+        dialect
+            .reader_builder(b',', false)
+            .from_reader(text.as_bytes())
+            .records()
+            .map(|r| r.unwrap())
+            .filter(|r| !dialect.should_skip(r))
+            .map(|r| r.iter().map(|s| s.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_backslash_escape_and_comments() {
+        let dialect = CsvDialect {
+            escape_char: "\\".to_string(),
+            double_quote: false,
+            comment_prefix: "#".to_string(),
+            ..CsvDialect::default()
+        };
+
+        let rows = read_all(&dialect, "# exported 2024-01-01\n\"say \\\"hi\\\"\",1\n");
+        assert_eq!(rows, vec![vec!["say \"hi\"".to_string(), "1".to_string()]]);
+    }
+
+    #[test]
+    fn test_single_quote_trim_and_skip_empty() {
+        let dialect = CsvDialect {
+            quote_char: "'".to_string(),
+            trim_whitespace: true,
+            skip_empty_rows: true,
+            ..CsvDialect::default()
+        };
+
+        let rows = read_all(&dialect, "'a,b' , c\n , \n");
+        assert_eq!(rows, vec![vec!["a,b".to_string(), "c".to_string()]]);
+    }
+
+    #[test]
+    fn test_writer_uses_escape_when_not_doubling() {
+        let dialect = CsvDialect {
+            escape_char: "\\".to_string(),
+            double_quote: false,
+            ..CsvDialect::default()
+        };
+
+        let mut writer = dialect.writer_builder(b',').from_writer(vec![]);
+        writer.write_record(["a\"b"]).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(output, "\"a\\\"b\"\n");
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashSet;
+use std::io::{self, Read};
+use encoding_rs::{Decoder, DecoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EncodingSource {
+    /// A byte order mark at the start of the file
+    Bom,
+    /// Statistical detection on a sample of the file
+    Detected,
+    /// Chosen by the user when opening the file
+    Override,
+}
+
+/// How the encoding of a file was determined and how cleanly it decoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodingDetection {
+    /// encoding_rs name, e.g. `windows-1252` or `UTF-16LE`
+    pub encoding: String,
+    pub confidence: f64,
+    pub source: EncodingSource,
+    /// Number of malformed byte sequences replaced with U+FFFD
+    pub decode_errors: usize,
+}
+
+/// Detect the encoding of a sample taken from the start of a file.
+/// A BOM wins outright; valid UTF-8 comes next, then chardet's guess
+/// resolved through the full encoding_rs label table.
+pub fn detect(sample: &[u8]) -> (&'static Encoding, EncodingDetection) {
+    let (encoding, confidence, source) = if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        (encoding, 1.0, EncodingSource::Bom)
+    } else if let Some(encoding) = sniff_utf16(sample) {
+        (encoding, 0.9, EncodingSource::Detected)
+    } else if is_utf8(sample) {
+        (UTF_8, 1.0, EncodingSource::Detected)
+    } else {
+        let (charset, confidence, _) = chardet::detect(sample);
+        let label = chardet::charset2encoding(&charset);
+        match Encoding::for_label(label.as_bytes()) {
+            // Valid UTF-8 was ruled out above, so a UTF-8 guess is wrong
+            Some(encoding) if encoding != UTF_8 => (encoding, confidence as f64, EncodingSource::Detected),
+            // Every byte decodes in windows-1252, so nothing is lost
+            _ => (WINDOWS_1252, 0.1, EncodingSource::Detected),
+        }
+    };
+
+    let detection = EncodingDetection {
+        encoding: encoding.name().to_string(),
+        confidence,
+        source,
+        decode_errors: 0,
+    };
+    (encoding, detection)
+}
+
+/// Decode with BOM handling like `Encoding::decode`, counting the
+/// malformed sequences that were replaced
+pub fn decode(encoding: &'static Encoding, bytes: &[u8]) -> (String, usize) {
+    let mut decoder = encoding.new_decoder();
+    let capacity = decoder
+        .max_utf8_buffer_length_without_replacement(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut output = String::with_capacity(capacity);
+    let mut input = bytes;
+    let mut errors = 0;
+
+    loop {
+        let (result, read) = decoder.decode_to_string_without_replacement(input, &mut output, true);
+        input = &input[read..];
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => output.reserve(input.len().max(16) * 3),
+            DecoderResult::Malformed(_, _) => {
+                errors += 1;
+                output.push('\u{FFFD}');
+            }
+        }
+    }
+
+    (output, errors)
+}
+
+/// Decodes a byte stream to UTF-8 as it is read, for parsing files that
+/// are not held in memory at once. Malformed sequences become U+FFFD and
+/// are counted as in `decode`.
+pub struct DecodingReader<R> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    input_pos: usize,
+    input_len: usize,
+    eof: bool,
+    finished: bool,
+    scratch: Vec<u8>,
+    pending: Vec<u8>,
+    pending_pos: usize,
+    errors: usize,
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            decoder: encoding.new_decoder(),
+            input: vec![0; 64 * 1024],
+            input_pos: 0,
+            input_len: 0,
+            eof: false,
+            finished: false,
+            scratch: vec![0; 64 * 1024],
+            pending: Vec::new(),
+            pending_pos: 0,
+            errors: 0,
+        }
+    }
+
+    pub fn decode_errors(&self) -> usize {
+        self.errors
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.pending_pos < self.pending.len() {
+                let n = buf.len().min(self.pending.len() - self.pending_pos);
+                buf[..n].copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + n]);
+                self.pending_pos += n;
+                return Ok(n);
+            }
+            if self.finished {
+                return Ok(0);
+            }
+
+            if self.input_pos == self.input_len && !self.eof {
+                self.input_len = self.inner.read(&mut self.input)?;
+                self.input_pos = 0;
+                self.eof = self.input_len == 0;
+            }
+
+            let (result, read, written) = self.decoder.decode_to_utf8_without_replacement(
+                &self.input[self.input_pos..self.input_len],
+                &mut self.scratch,
+                self.eof,
+            );
+            self.input_pos += read;
+            self.pending.clear();
+            self.pending.extend_from_slice(&self.scratch[..written]);
+            self.pending_pos = 0;
+
+            match result {
+                DecoderResult::InputEmpty => self.finished = self.eof,
+                DecoderResult::OutputFull => {}
+                DecoderResult::Malformed(_, _) => {
+                    self.errors += 1;
+                    self.pending.extend_from_slice("\u{FFFD}".as_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// Characters with no representation in the target encoding. Writing them
+/// would leave `&#NNNN;` references in the file, so it is refused.
+#[derive(Debug, Clone)]
+pub struct UnencodableError {
+    pub encoding: String,
+    /// Each character once, in order of first appearance
+    pub characters: Vec<char>,
+}
+
+impl std::fmt::Display for UnencodableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let characters: Vec<String> = self.characters.iter().map(|c| format!("'{}' (U+{:04X})", c, *c as u32)).collect();
+        write!(
+            f,
+            "{} cannot represent {}; save with an encoding such as UTF-8 instead",
+            self.encoding,
+            characters.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnencodableError {}
+
+/// Characters of `text` that `encoding` cannot represent, each listed once
+pub fn unmappable_chars(encoding: &'static Encoding, text: &str) -> Vec<char> {
+    if encoding == UTF_8 || encoding == UTF_16LE || encoding == UTF_16BE {
+        return Vec::new();
+    }
+
+    let mut seen = HashSet::new();
+    let mut buffer = [0u8; 4];
+    text.chars()
+        .filter(|c| !c.is_ascii() && seen.insert(*c))
+        .filter(|c| encoding.encode(c.encode_utf8(&mut buffer)).2)
+        .collect()
+}
+
+/// Encode text for writing. encoding_rs only decodes UTF-16, so it is
+/// encoded here with a BOM so the file is recognized when reopened.
+pub fn encode(encoding: &'static Encoding, text: &str) -> Result<Vec<u8>, UnencodableError> {
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little_endian = encoding == UTF_16LE;
+        let mut bytes = Vec::with_capacity(2 + text.len() * 2);
+        for unit in std::iter::once(0xFEFF).chain(text.encode_utf16()) {
+            let pair = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+            bytes.extend_from_slice(&pair);
+        }
+        Ok(bytes)
+    } else if encoding == UTF_8 {
+        Ok(text.as_bytes().to_vec())
+    } else {
+        let (encoded, _, had_errors) = encoding.encode(text);
+        if had_errors {
+            return Err(UnencodableError {
+                encoding: encoding.name().to_string(),
+                characters: unmappable_chars(encoding, text),
+            });
+        }
+        Ok(encoded.into_owned())
+    }
+}
+
+/// Resolve a user-supplied encoding name such as `latin1`, `sjis` or `utf-16le`
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+    let label = label.trim();
+    match label.to_lowercase().as_str() {
+        // WHATWG maps "ascii" to windows-1252; plain ASCII is valid UTF-8
+        "ascii" | "us-ascii" => Some(UTF_8),
+        // Spellings used by older settings and other tools
+        "sjis" | "shift-jis" | "cp932" => Some(encoding_rs::SHIFT_JIS),
+        "eucjp" | "euc_jp" => Some(encoding_rs::EUC_JP),
+        _ => Encoding::for_label(label.as_bytes()),
+    }
+}
+
+/// UTF-16 without a BOM shows up as a zero byte in every other position
+fn sniff_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs < 4 {
+        return None;
+    }
+
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Valid UTF-8, allowing a sequence cut off at the end of the sample
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && sample.len() - e.valid_up_to() < 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_round_trip_with_bom() {
+        let bytes = encode(UTF_16LE, "名前,値\n").unwrap();
+        let (encoding, detection) = detect(&bytes);
+        assert_eq!(encoding, UTF_16LE);
+        assert_eq!(detection.source, EncodingSource::Bom);
+
+        let (text, errors) = decode(encoding, &bytes);
+        assert_eq!(text, "名前,値\n");
+        assert_eq!(errors, 0);
+    }
+
+    #[test]
+    fn test_latin1_is_not_decoded_as_utf8() {
+        let bytes = b"name,city\nJos\xe9,M\xfcnchen\nFran\xe7ois,Z\xfcrich\n";
+        let (encoding, _) = detect(bytes);
+        assert_ne!(encoding, UTF_8);
+
+        let (text, errors) = decode(encoding, bytes);
+        assert!(text.contains("José"));
+        assert_eq!(errors, 0);
+    }
+
+    #[test]
+    fn test_decode_counts_malformed_sequences() {
+        let (text, errors) = decode(UTF_8, b"a,\xff\nb,\xfe\n");
+        assert_eq!(errors, 2);
+        assert_eq!(text, "a,\u{FFFD}\nb,\u{FFFD}\n");
+        assert_eq!(for_label("latin1"), Some(WINDOWS_1252));
+        assert_eq!(for_label("ascii"), Some(UTF_8));
+    }
+
+    #[test]
+    fn test_decoding_reader_matches_decode() {
+        let bytes = encode(encoding_rs::SHIFT_JIS, &"名前,値\n".repeat(20_000)).unwrap();
+        let mut reader = DecodingReader::new(&bytes[..], encoding_rs::SHIFT_JIS);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+
+        assert_eq!(text, decode(encoding_rs::SHIFT_JIS, &bytes).0);
+        assert_eq!(reader.decode_errors(), 0);
+    }
+
+    #[test]
+    fn test_encode_refuses_unmappable_characters() {
+        let error = encode(encoding_rs::SHIFT_JIS, "名前,café,😀,é\n").unwrap_err();
+        assert_eq!(error.characters, vec!['é', '😀']);
+        assert!(encode(WINDOWS_1252, "café\n").is_ok());
+    }
+}
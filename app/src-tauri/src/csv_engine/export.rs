@@ -3,6 +3,7 @@ use serde_json::json;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
+use crate::csv_engine::data_types::ColumnTypeOverride;
 use crate::csv_engine::reader::CsvData;
 use crate::utils::AppError;
 
@@ -51,6 +52,9 @@ impl Exporter {
             headers: data.headers.clone(),
             rows: data.rows.iter().take(max_rows).cloned().collect(),
             metadata: data.metadata.clone(),
+            sniff: None,
+            malformed_rows: Vec::new(),
+            encoding_detection: None,
         };
 
         match options.format {
@@ -195,12 +199,20 @@ impl Exporter {
         data: &CsvData,
         options: &ExportOptions,
     ) -> Result<String, AppError> {
-        let array: Vec<Vec<String>> = if options.include_headers {
-            std::iter::once(data.headers.clone())
-                .chain(data.rows.clone())
+        let declared = data.metadata.column_type_overrides(&data.headers);
+        let rows = data.rows.iter().map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, value)| Self::json_value(value, declared.get(i).and_then(|d| d.as_ref())))
+                .collect::<Vec<_>>()
+        });
+
+        let array: Vec<Vec<serde_json::Value>> = if options.include_headers {
+            std::iter::once(data.headers.iter().map(|h| json!(h)).collect())
+                .chain(rows)
                 .collect()
         } else {
-            data.rows.clone()
+            rows.collect()
         };
 
         let json_string = if options.pretty_print {
@@ -219,14 +231,15 @@ impl Exporter {
         data: &CsvData,
         options: &ExportOptions,
     ) -> Result<String, AppError> {
+        let declared = data.metadata.column_type_overrides(&data.headers);
         let objects: Vec<serde_json::Value> = data
             .rows
             .iter()
             .map(|row| {
                 let mut obj = serde_json::Map::new();
                 for (i, header) in data.headers.iter().enumerate() {
-                    let value = row.get(i).cloned().unwrap_or_default();
-                    obj.insert(header.clone(), json!(value));
+                    let value = row.get(i).map(|v| v.as_str()).unwrap_or_default();
+                    obj.insert(header.clone(), Self::json_value(value, declared[i].as_ref()));
                 }
                 json!(obj)
             })
@@ -244,6 +257,14 @@ impl Exporter {
         ))
     }
 
+    /// Cells are strings unless the column has a declared type
+    fn json_value(value: &str, declared: Option<&ColumnTypeOverride>) -> serde_json::Value {
+        match declared {
+            Some(declared) => declared.to_json(value),
+            None => json!(value),
+        }
+    }
+
     fn write_file(path: &Path, content: &str) -> Result<(), AppError> {
         let mut file = File::create(path).map_err(|e| {
             AppError::new(
@@ -279,6 +300,9 @@ mod tests {
                 delimiter: ",".to_string(),
                 has_headers: true,
             },
+            sniff: None,
+            malformed_rows: Vec::new(),
+            encoding_detection: None,
         }
     }
 
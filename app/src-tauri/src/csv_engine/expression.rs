@@ -0,0 +1,553 @@
+//! Row-level boolean expressions for custom validation rules, such as
+//! `end_date >= start_date` or `status == "shipped" -> tracking_no != ""`.
+//!
+//! Columns are referred to by name, or in backticks when the name is not a
+//! plain identifier (`` `Order Date` ``). Values compare as numbers when
+//! both sides are numbers, as dates when both are dates, and as text
+//! otherwise. Operators, loosest binding first:
+//!
+//! - `->` implication (right-associative)
+//! - `||` / `or`, `&&` / `and`, `!` / `not`
+//! - `==` `!=` `<` `<=` `>` `>=`
+//! - `+` `-`, then `*` `/` `%`, then unary `-`
+//!
+//! Functions: `len`, `lower`, `upper`, `trim`, `is_empty`, `contains`,
+//! `starts_with`, `ends_with` and `matches` (a regex literal, whole value).
+
+use std::cmp::Ordering;
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use super::locale::LocaleFormat;
+
+/// A parsed expression with its columns resolved against a header row
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Node,
+    /// Column indices read by the expression, in order of first use
+    columns: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Column(usize),
+    Literal(Value),
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+    Matches(Box<Node>, Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Implies,
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Len,
+    Lower,
+    Upper,
+    Trim,
+    IsEmpty,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Text(String),
+    Number(f64),
+    Symbol(&'static str),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+const SYMBOLS: [&str; 17] = [
+    "->", "||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "=", "|",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    _ => Token::Comma,
+                });
+            }
+            '"' | '\'' | '`' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) if c != '`' => match chars.next() {
+                            Some((_, escaped)) => value.push(escaped),
+                            None => bail!("Unterminated string at position {}", position),
+                        },
+                        Some((_, end)) if end == c => break,
+                        Some((_, other)) => value.push(other),
+                        None => bail!("Unterminated {} at position {}", if c == '`' { "column name" } else { "string" }, position),
+                    }
+                }
+                tokens.push(if c == '`' { Token::Identifier(value) } else { Token::Text(value) });
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&(_, d)) = chars.peek() {
+                    if !(d.is_ascii_digit() || d == '.') {
+                        break;
+                    }
+                    number.push(d);
+                    chars.next();
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid number '{}' at position {}", number, position))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&(_, d)) = chars.peek() {
+                    if !(d.is_alphanumeric() || d == '_') {
+                        break;
+                    }
+                    name.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Identifier(name));
+            }
+            _ => {
+                let rest = &text[position..];
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(**s))
+                    .ok_or_else(|| anyhow!("Unexpected '{}' at position {}", c, position))?;
+                for _ in 0..symbol.chars().count() {
+                    chars.next();
+                }
+                // `=` and `|` alone are common slips for `==` and `||`
+                tokens.push(Token::Symbol(match *symbol {
+                    "=" => "==",
+                    "|" => "||",
+                    other => other,
+                }));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    headers: &'a [String],
+    columns: Vec<usize>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of `symbols` (or a word operator)
+    fn accept(&mut self, symbols: &[&'static str]) -> Option<&'static str> {
+        let symbol = match self.peek()? {
+            Token::Symbol(s) => symbols.iter().find(|c| *c == s).copied(),
+            Token::Identifier(word) => symbols.iter().copied().find(|c| {
+                matches!((*c, word.to_lowercase().as_str()), ("||", "or") | ("&&", "and") | ("!", "not"))
+            }),
+            _ => None,
+        }?;
+        self.position += 1;
+        Some(symbol)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => bail!("Expected {:?} but found {:?}", expected, token),
+            None => bail!("Expected {:?} but the expression ended", expected),
+        }
+    }
+
+    fn implication(&mut self) -> Result<Node> {
+        let condition = self.or()?;
+        if self.accept(&["->"]).is_some() {
+            let consequence = self.implication()?;
+            return Ok(Node::Binary(BinaryOp::Implies, Box::new(condition), Box::new(consequence)));
+        }
+        Ok(condition)
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut node = self.and()?;
+        while self.accept(&["||"]).is_some() {
+            node = Node::Binary(BinaryOp::Or, Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut node = self.not()?;
+        while self.accept(&["&&"]).is_some() {
+            node = Node::Binary(BinaryOp::And, Box::new(node), Box::new(self.not()?));
+        }
+        Ok(node)
+    }
+
+    fn not(&mut self) -> Result<Node> {
+        if self.accept(&["!"]).is_some() {
+            return Ok(Node::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Node> {
+        let left = self.additive()?;
+        let op = match self.accept(&["==", "!=", "<=", ">=", "<", ">"]) {
+            Some("==") => BinaryOp::Eq,
+            Some("!=") => BinaryOp::Ne,
+            Some("<=") => BinaryOp::Le,
+            Some(">=") => BinaryOp::Ge,
+            Some("<") => BinaryOp::Lt,
+            Some(">") => BinaryOp::Gt,
+            _ => return Ok(left),
+        };
+        Ok(Node::Binary(op, Box::new(left), Box::new(self.additive()?)))
+    }
+
+    fn additive(&mut self) -> Result<Node> {
+        let mut node = self.multiplicative()?;
+        while let Some(symbol) = self.accept(&["+", "-"]) {
+            let op = if symbol == "+" { BinaryOp::Add } else { BinaryOp::Sub };
+            node = Node::Binary(op, Box::new(node), Box::new(self.multiplicative()?));
+        }
+        Ok(node)
+    }
+
+    fn multiplicative(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+        while let Some(symbol) = self.accept(&["*", "/", "%"]) {
+            let op = match symbol {
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        if self.accept(&["-"]).is_some() {
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Node::Literal(Value::Number(n))),
+            Some(Token::Text(s)) => Ok(Node::Literal(Value::Text(s))),
+            Some(Token::LeftParen) => {
+                let node = self.implication()?;
+                self.expect(Token::RightParen)?;
+                Ok(node)
+            }
+            Some(Token::Identifier(name)) if self.peek() == Some(&Token::LeftParen) => self.call(&name),
+            Some(Token::Identifier(name)) => self.column(name),
+            Some(token) => bail!("Unexpected {:?}", token),
+            None => bail!("Expression ended unexpectedly"),
+        }
+    }
+
+    /// A column, with `true` and `false` as literals unless a column has that name
+    fn column(&mut self, name: String) -> Result<Node> {
+        match self.headers.iter().position(|h| *h == name) {
+            Some(index) => {
+                if !self.columns.contains(&index) {
+                    self.columns.push(index);
+                }
+                Ok(Node::Column(index))
+            }
+            None if name.eq_ignore_ascii_case("true") => Ok(Node::Literal(Value::Bool(true))),
+            None if name.eq_ignore_ascii_case("false") => Ok(Node::Literal(Value::Bool(false))),
+            None => bail!("Unknown column '{}'", name),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Node> {
+        self.expect(Token::LeftParen)?;
+        let mut arguments = Vec::new();
+        if self.peek() != Some(&Token::RightParen) {
+            loop {
+                arguments.push(self.implication()?);
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.next();
+            }
+        }
+        self.expect(Token::RightParen)?;
+
+        let (function, arity) = match name.to_lowercase().as_str() {
+            "len" => (Function::Len, 1),
+            "lower" => (Function::Lower, 1),
+            "upper" => (Function::Upper, 1),
+            "trim" => (Function::Trim, 1),
+            "is_empty" => (Function::IsEmpty, 1),
+            "contains" => (Function::Contains, 2),
+            "starts_with" => (Function::StartsWith, 2),
+            "ends_with" => (Function::EndsWith, 2),
+            "matches" => {
+                let [value, Node::Literal(Value::Text(pattern))] = <[Node; 2]>::try_from(arguments)
+                    .map_err(|_| anyhow!("matches() takes a value and a pattern"))?
+                else {
+                    bail!("The pattern of matches() must be a string literal");
+                };
+                let regex = Regex::new(&format!("^(?:{})$", pattern))
+                    .map_err(|e| anyhow!("Invalid pattern in matches(): {}", e))?;
+                return Ok(Node::Matches(Box::new(value), regex));
+            }
+            _ => bail!("Unknown function '{}'", name),
+        };
+
+        if arguments.len() != arity {
+            bail!("{}() takes {} argument{}", name, arity, if arity == 1 { "" } else { "s" });
+        }
+        Ok(Node::Call(function, arguments))
+    }
+}
+
+impl Value {
+    fn as_text(&self) -> String {
+        match self {
+            Value::Text(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+
+    fn as_number(&self, locale: &LocaleFormat) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Text(s) => locale.parse_number(s),
+            Value::Bool(_) => None,
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Text(s) => !s.is_empty() && !s.eq_ignore_ascii_case("false"),
+        }
+    }
+
+    /// Numbers when both sides read as numbers, then dates, then text
+    fn compare(&self, other: &Value, locale: &LocaleFormat) -> Ordering {
+        if let (Some(a), Some(b)) = (self.as_number(locale), other.as_number(locale)) {
+            return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+        }
+        if let (Value::Text(a), Value::Text(b)) = (self, other) {
+            if let (Some(a), Some(b)) = (locale.parse_date(a), locale.parse_date(b)) {
+                return a.cmp(&b);
+            }
+        }
+        if let (Value::Bool(a), Value::Bool(b)) = (self, other) {
+            return a.cmp(b);
+        }
+        self.as_text().cmp(&other.as_text())
+    }
+}
+
+impl Expression {
+    /// Parse `text`, resolving column names against `headers`
+    pub fn compile(text: &str, headers: &[String]) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            headers,
+            columns: Vec::new(),
+        };
+        if parser.tokens.is_empty() {
+            bail!("Expression is empty");
+        }
+
+        let root = parser.implication()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected {:?} after the end of the expression", token);
+        }
+
+        Ok(Self { root, columns: parser.columns })
+    }
+
+    /// Indices of the columns the expression reads
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Whether `row` satisfies the expression. Arithmetic on values that
+    /// are not numbers is an error.
+    pub fn evaluate(&self, row: &[String], locale: &LocaleFormat) -> Result<bool> {
+        Ok(Self::eval(&self.root, row, locale)?.is_truthy())
+    }
+
+    fn eval(node: &Node, row: &[String], locale: &LocaleFormat) -> Result<Value> {
+        Ok(match node {
+            Node::Column(index) => Value::Text(row.get(*index).cloned().unwrap_or_default()),
+            Node::Literal(value) => value.clone(),
+            Node::Not(inner) => Value::Bool(!Self::eval(inner, row, locale)?.is_truthy()),
+            Node::Negate(inner) => {
+                let value = Self::eval(inner, row, locale)?;
+                let number = value
+                    .as_number(locale)
+                    .ok_or_else(|| anyhow!("'{}' is not a number", value.as_text()))?;
+                Value::Number(-number)
+            }
+            Node::Binary(op, left, right) => {
+                // Logical operators short-circuit
+                let left = Self::eval(left, row, locale)?;
+                match op {
+                    BinaryOp::Implies if !left.is_truthy() => return Ok(Value::Bool(true)),
+                    BinaryOp::Or if left.is_truthy() => return Ok(Value::Bool(true)),
+                    BinaryOp::And if !left.is_truthy() => return Ok(Value::Bool(false)),
+                    BinaryOp::Implies | BinaryOp::Or | BinaryOp::And => {
+                        return Ok(Value::Bool(Self::eval(right, row, locale)?.is_truthy()));
+                    }
+                    _ => {}
+                }
+
+                let right = Self::eval(right, row, locale)?;
+                match op {
+                    BinaryOp::Eq => Value::Bool(left.compare(&right, locale) == Ordering::Equal),
+                    BinaryOp::Ne => Value::Bool(left.compare(&right, locale) != Ordering::Equal),
+                    BinaryOp::Lt => Value::Bool(left.compare(&right, locale) == Ordering::Less),
+                    BinaryOp::Le => Value::Bool(left.compare(&right, locale) != Ordering::Greater),
+                    BinaryOp::Gt => Value::Bool(left.compare(&right, locale) == Ordering::Greater),
+                    BinaryOp::Ge => Value::Bool(left.compare(&right, locale) != Ordering::Less),
+                    _ => {
+                        let number = |value: &Value| {
+                            value
+                                .as_number(locale)
+                                .ok_or_else(|| anyhow!("'{}' is not a number", value.as_text()))
+                        };
+                        let (a, b) = (number(&left)?, number(&right)?);
+                        Value::Number(match op {
+                            BinaryOp::Add => a + b,
+                            BinaryOp::Sub => a - b,
+                            BinaryOp::Mul => a * b,
+                            BinaryOp::Div if b == 0.0 => bail!("Division by zero"),
+                            BinaryOp::Div => a / b,
+                            BinaryOp::Rem if b == 0.0 => bail!("Division by zero"),
+                            _ => a % b,
+                        })
+                    }
+                }
+            }
+            Node::Call(function, arguments) => {
+                let values: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| Ok(Self::eval(argument, row, locale)?.as_text()))
+                    .collect::<Result<_>>()?;
+                match function {
+                    Function::Len => Value::Number(values[0].chars().count() as f64),
+                    Function::Lower => Value::Text(values[0].to_lowercase()),
+                    Function::Upper => Value::Text(values[0].to_uppercase()),
+                    Function::Trim => Value::Text(values[0].trim().to_string()),
+                    Function::IsEmpty => Value::Bool(values[0].trim().is_empty()),
+                    Function::Contains => Value::Bool(values[0].contains(&values[1])),
+                    Function::StartsWith => Value::Bool(values[0].starts_with(&values[1])),
+                    Function::EndsWith => Value::Bool(values[0].ends_with(&values[1])),
+                }
+            }
+            Node::Matches(inner, regex) => Value::Bool(regex.is_match(&Self::eval(inner, row, locale)?.as_text())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(expression: &str, headers: &[&str], row: &[&str]) -> Result<bool> {
+        let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+        let row: Vec<String> = row.iter().map(|v| v.to_string()).collect();
+        Expression::compile(expression, &headers)?.evaluate(&row, &LocaleFormat::default())
+    }
+
+    #[test]
+    fn test_comparisons_use_numbers_and_dates() {
+        let headers = ["start_date", "end_date", "qty", "price"];
+        let row = ["2024-01-15", "2024-02-01", "9", "10.5"];
+
+        assert!(check("end_date >= start_date", &headers, &row).unwrap());
+        assert!(!check("end_date < start_date", &headers, &row).unwrap());
+        // 9 < 10.5 as numbers, though "9" > "10.5" as text
+        assert!(check("qty < price", &headers, &row).unwrap());
+        assert!(check("qty * price == 94.5 and -qty < 0", &headers, &row).unwrap());
+        assert!(check("(qty + 1) % 5 == 0 || false", &headers, &row).unwrap());
+    }
+
+    #[test]
+    fn test_implication_and_functions() {
+        let headers = ["status", "tracking_no", "Order Code"];
+        let rule = r#"status == "shipped" -> tracking_no != """#;
+
+        assert!(check(rule, &headers, &["pending", "", ""]).unwrap());
+        assert!(check(rule, &headers, &["shipped", "ZX81", ""]).unwrap());
+        assert!(!check(rule, &headers, &["shipped", "", ""]).unwrap());
+
+        assert!(check(r#"matches(`Order Code`, "[A-Z]{2}-\\d+") and len(tracking_no) == 4"#, &headers, &["", "ZX81", "AB-12"]).unwrap());
+        assert!(check("not is_empty(trim(status)) && lower(status) == 'open'", &headers, &["OPEN ", "", ""]).is_ok_and(|ok| !ok));
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_rejected() {
+        let headers = ["a"];
+        assert!(check("missing == 1", &headers, &["1"]).is_err());
+        assert!(check("a ==", &headers, &["1"]).is_err());
+        assert!(check("a == 1)", &headers, &["1"]).is_err());
+        assert!(check("matches(a, b)", &headers, &["1"]).is_err());
+        assert!(check("a + 1 > 0", &headers, &["x"]).is_err());
+    }
+}
@@ -0,0 +1,465 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use encoding_rs::{Encoding, UTF_8};
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+use crate::csv_engine::encoding::{self, EncodingDetection};
+use crate::csv_engine::reader::{CsvData, CsvReader};
+use crate::metadata::CsvMetadata;
+
+/// How surrounding padding is stripped from a field when reading
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TrimMode {
+    None,
+    Left,
+    Right,
+    #[default]
+    Both,
+}
+
+/// Which side a value is aligned to when it is padded on write
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FixedWidthAlign {
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FixedWidthColumn {
+    pub name: String,
+    /// Zero-based character offset of the field within a line
+    pub start: usize,
+    /// Field width in characters
+    pub width: usize,
+    #[serde(default)]
+    pub trim: TrimMode,
+    #[serde(default)]
+    pub align: FixedWidthAlign,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FixedWidthSpec {
+    pub columns: Vec<FixedWidthColumn>,
+    /// Whether the first line holds column titles rather than data
+    #[serde(default)]
+    pub has_headers: bool,
+    #[serde(default = "default_pad_char")]
+    pub pad_char: char,
+}
+
+fn default_pad_char() -> char {
+    ' '
+}
+
+impl FixedWidthSpec {
+    pub fn new(columns: Vec<FixedWidthColumn>) -> Self {
+        Self {
+            columns,
+            has_headers: false,
+            pad_char: default_pad_char(),
+        }
+    }
+
+    /// Total line length implied by the spec
+    pub fn line_width(&self) -> usize {
+        self.columns
+            .iter()
+            .map(|c| c.start + c.width)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.columns.is_empty() {
+            bail!("Fixed-width spec has no columns");
+        }
+
+        let mut sorted: Vec<&FixedWidthColumn> = self.columns.iter().collect();
+        sorted.sort_by_key(|c| c.start);
+
+        for pair in sorted.windows(2) {
+            if pair[0].start + pair[0].width > pair[1].start {
+                bail!(
+                    "Fixed-width columns '{}' and '{}' overlap",
+                    pair[0].name,
+                    pair[1].name
+                );
+            }
+        }
+
+        if let Some(column) = self.columns.iter().find(|c| c.width == 0) {
+            bail!("Fixed-width column '{}' has zero width", column.name);
+        }
+
+        Ok(())
+    }
+
+    /// Infer column boundaries from character positions that are blank in
+    /// every sample line. Each field runs up to the start of the next one so
+    /// that right-padded text keeps its full width.
+    pub fn infer(sample: &[&str], has_headers: bool) -> Result<Self> {
+        let lines: Vec<Vec<char>> = sample
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.chars().collect())
+            .collect();
+
+        let line_width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+        if line_width == 0 {
+            bail!("Cannot infer fixed-width columns from an empty sample");
+        }
+
+        let blank: Vec<bool> = (0..line_width)
+            .map(|pos| {
+                lines
+                    .iter()
+                    .all(|l| l.get(pos).is_none_or(|c| c.is_whitespace()))
+            })
+            .collect();
+
+        let starts: Vec<usize> = (0..line_width)
+            .filter(|&pos| !blank[pos] && (pos == 0 || blank[pos - 1]))
+            .collect();
+
+        let header_line = if has_headers { lines.first() } else { None };
+
+        let columns = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(line_width);
+                let name = header_line
+                    .map(|l| slice_chars(l, start, end - start).trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| format!("Column{}", i + 1));
+
+                FixedWidthColumn {
+                    name,
+                    start,
+                    width: end - start,
+                    trim: TrimMode::Both,
+                    align: FixedWidthAlign::Left,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            columns,
+            has_headers,
+            pad_char: default_pad_char(),
+        })
+    }
+}
+
+fn slice_chars(line: &[char], start: usize, width: usize) -> String {
+    if start >= line.len() {
+        return String::new();
+    }
+    let end = (start + width).min(line.len());
+    line[start..end].iter().collect()
+}
+
+fn apply_trim(value: &str, trim: TrimMode, pad_char: char) -> String {
+    let is_pad = |c: char| c == pad_char || c.is_whitespace();
+    match trim {
+        TrimMode::None => value.to_string(),
+        TrimMode::Left => value.trim_start_matches(is_pad).to_string(),
+        TrimMode::Right => value.trim_end_matches(is_pad).to_string(),
+        TrimMode::Both => value.trim_matches(is_pad).to_string(),
+    }
+}
+
+pub struct FixedWidthReader {
+    spec: Option<FixedWidthSpec>,
+    has_headers: bool,
+    encoding: &'static Encoding,
+    sample_lines: usize,
+}
+
+impl Default for FixedWidthReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixedWidthReader {
+    pub fn new() -> Self {
+        Self {
+            spec: None,
+            has_headers: true,
+            encoding: UTF_8,
+            sample_lines: 200,
+        }
+    }
+
+    /// Use an explicit spec instead of inferring one from the file
+    pub fn with_spec(mut self, spec: FixedWidthSpec) -> Self {
+        self.has_headers = spec.has_headers;
+        self.spec = Some(spec);
+        self
+    }
+
+    /// Only used when the spec is inferred
+    pub fn with_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    pub fn with_sample_lines(mut self, sample_lines: usize) -> Self {
+        self.sample_lines = sample_lines;
+        self
+    }
+
+    pub fn read_file(&mut self, path: &Path) -> Result<CsvData> {
+        let mut detector = CsvReader::new();
+        self.encoding = detector.detect_encoding(path)?;
+
+        let file = File::open(path).context("Failed to open fixed-width file")?;
+        let mut reader = BufReader::new(file);
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+
+        let (decoded, decode_errors) = encoding::decode(self.encoding, &buffer);
+
+        let mut metadata = CsvMetadata::new(path)?;
+        metadata.encoding = self.encoding.name().to_string();
+        let mut data = self.parse(&decoded, metadata)?;
+        data.encoding_detection = detector.encoding_detection().map(|detection| EncodingDetection {
+            decode_errors,
+            ..detection.clone()
+        });
+        Ok(data)
+    }
+
+    pub fn read_from_string(&mut self, text: &str) -> Result<CsvData> {
+        self.parse(text, CsvMetadata::from_pasted_data())
+    }
+
+    /// Infer a spec from the beginning of a file without reading all of it
+    pub fn infer_spec(&mut self, path: &Path) -> Result<FixedWidthSpec> {
+        self.encoding = CsvReader::new().detect_encoding(path)?;
+
+        let mut buffer = Vec::new();
+        File::open(path)
+            .context("Failed to open fixed-width file")?
+            .take(256 * 1024)
+            .read_to_end(&mut buffer)?;
+
+        let (decoded, _, _) = self.encoding.decode(&buffer);
+        let sample: Vec<&str> = decoded.lines().take(self.sample_lines).collect();
+        FixedWidthSpec::infer(&sample, self.has_headers)
+    }
+
+    fn parse(&mut self, text: &str, mut metadata: CsvMetadata) -> Result<CsvData> {
+        let spec = match &self.spec {
+            Some(spec) => spec.clone(),
+            None => {
+                let sample: Vec<&str> = text.lines().take(self.sample_lines).collect();
+                FixedWidthSpec::infer(&sample, self.has_headers)?
+            }
+        };
+        spec.validate()?;
+
+        let headers: Vec<String> = spec.columns.iter().map(|c| c.name.clone()).collect();
+
+        let mut lines = text.lines();
+        if spec.has_headers {
+            lines.next();
+        }
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chars: Vec<char> = line.chars().collect();
+            let row = spec
+                .columns
+                .iter()
+                .map(|c| apply_trim(&slice_chars(&chars, c.start, c.width), c.trim, spec.pad_char))
+                .collect();
+            rows.push(row);
+        }
+
+        metadata.has_headers = spec.has_headers;
+        metadata.update_counts(rows.len(), headers.len());
+        metadata.fixed_width_spec = Some(spec.clone());
+        self.spec = Some(spec);
+
+        Ok(CsvData {
+            headers,
+            rows,
+            metadata,
+            sniff: None,
+            malformed_rows: Vec::new(),
+            encoding_detection: None,
+        })
+    }
+}
+
+pub struct FixedWidthWriter {
+    spec: FixedWidthSpec,
+    encoding: &'static Encoding,
+}
+
+impl FixedWidthWriter {
+    pub fn new(spec: FixedWidthSpec) -> Self {
+        Self {
+            spec,
+            encoding: UTF_8,
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn write_file(&self, path: &Path, data: &CsvData) -> Result<()> {
+        let text = self.write_to_string(data)?;
+
+        let encoded_data = encoding::encode(self.encoding, &text)?;
+
+        let mut file = File::create(path)
+            .context("Failed to create output file")?;
+        file.write_all(&encoded_data)
+            .context("Failed to write to file")?;
+
+        Ok(())
+    }
+
+    pub fn write_to_string(&self, data: &CsvData) -> Result<String> {
+        self.spec.validate()?;
+
+        let mut content = String::new();
+
+        if self.spec.has_headers {
+            content.push_str(&self.format_line(&data.headers));
+            content.push('\n');
+        }
+
+        for row in &data.rows {
+            content.push_str(&self.format_line(row));
+            content.push('\n');
+        }
+
+        Ok(content)
+    }
+
+    /// Lay out a record according to the spec. Columns are matched to cells
+    /// by position; values longer than their width are truncated.
+    fn format_line(&self, values: &[String]) -> String {
+        let mut line: Vec<char> = vec![self.spec.pad_char; self.spec.line_width()];
+
+        for (col_idx, column) in self.spec.columns.iter().enumerate() {
+            let value = values.get(col_idx).map(|s| s.as_str()).unwrap_or("");
+            let chars: Vec<char> = value.chars().take(column.width).collect();
+            let offset = match column.align {
+                FixedWidthAlign::Left => 0,
+                FixedWidthAlign::Right => column.width - chars.len(),
+            };
+            for (i, c) in chars.into_iter().enumerate() {
+                line[column.start + offset + i] = c;
+            }
+        }
+
+        line.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "ID   NAME      AMOUNT\n\
+                          001  Alice     120.50\n\
+                          002  Bob         7.00\n";
+
+    #[test]
+    fn test_infer_spec() {
+        let lines: Vec<&str> = SAMPLE.lines().collect();
+        let spec = FixedWidthSpec::infer(&lines, true).unwrap();
+
+        let names: Vec<&str> = spec.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["ID", "NAME", "AMOUNT"]);
+        assert_eq!(spec.columns[0].start, 0);
+        assert_eq!(spec.columns[1].start, 5);
+    }
+
+    #[test]
+    fn test_read_with_spec() {
+        let spec = FixedWidthSpec::new(vec![
+            FixedWidthColumn { name: "id".to_string(), start: 0, width: 3, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+            FixedWidthColumn { name: "name".to_string(), start: 5, width: 10, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+        ]);
+
+        let mut reader = FixedWidthReader::new().with_spec(spec);
+        let data = reader.read_from_string("001  Alice     \n002  Bob\n").unwrap();
+
+        assert_eq!(data.headers, vec!["id", "name"]);
+        assert_eq!(data.rows[1], vec!["002", "Bob"]);
+        assert!(data.metadata.fixed_width_spec.is_some());
+    }
+
+    #[test]
+    fn test_write_pads_and_truncates() {
+        let spec = FixedWidthSpec::new(vec![
+            FixedWidthColumn { name: "code".to_string(), start: 0, width: 4, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+            FixedWidthColumn { name: "qty".to_string(), start: 4, width: 5, trim: TrimMode::Both, align: FixedWidthAlign::Right },
+        ]);
+
+        let data = CsvData {
+            headers: vec!["code".to_string(), "qty".to_string()],
+            rows: vec![vec!["ABCDEF".to_string(), "42".to_string()]],
+            metadata: CsvMetadata::from_pasted_data(),
+            sniff: None,
+            malformed_rows: Vec::new(),
+            encoding_detection: None,
+        };
+
+        let output = FixedWidthWriter::new(spec).write_to_string(&data).unwrap();
+        assert_eq!(output, "ABCD   42\n");
+    }
+
+    #[test]
+    fn test_written_lines_keep_their_width_and_read_back() {
+        let mut spec = FixedWidthSpec::new(vec![
+            FixedWidthColumn { name: "id".to_string(), start: 0, width: 3, trim: TrimMode::Both, align: FixedWidthAlign::Right },
+            FixedWidthColumn { name: "name".to_string(), start: 3, width: 8, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+        ]);
+        spec.pad_char = '_';
+        spec.has_headers = true;
+
+        let data = CsvData {
+            headers: vec!["id".to_string(), "name".to_string()],
+            rows: vec![
+                vec!["1".to_string(), "Alice".to_string()],
+                vec!["22".to_string(), "Bo".to_string()],
+            ],
+            metadata: CsvMetadata::from_pasted_data(),
+            sniff: None,
+            malformed_rows: Vec::new(),
+            encoding_detection: None,
+        };
+
+        let output = FixedWidthWriter::new(spec.clone()).write_to_string(&data).unwrap();
+        assert_eq!(output, "_idname____\n__1Alice___\n_22Bo______\n");
+        assert!(output.lines().all(|line| line.chars().count() == spec.line_width()));
+
+        let read = FixedWidthReader::new().with_spec(spec).read_from_string(&output).unwrap();
+        assert_eq!(read.rows, data.rows);
+    }
+
+    #[test]
+    fn test_overlapping_spec_is_rejected() {
+        let spec = FixedWidthSpec::new(vec![
+            FixedWidthColumn { name: "a".to_string(), start: 0, width: 5, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+            FixedWidthColumn { name: "b".to_string(), start: 3, width: 5, trim: TrimMode::Both, align: FixedWidthAlign::Left },
+        ]);
+        assert!(spec.validate().is_err());
+    }
+}
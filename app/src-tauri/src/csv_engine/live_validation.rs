@@ -0,0 +1,501 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use super::expression::Expression;
+use super::locale::LocaleFormat;
+use super::validation::{composite_key, ValidationError, ValidationRule, ValidationRuleType, Validator};
+
+/// Errors a cell edit introduced and resolved
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationDiff {
+    pub added: Vec<ValidationError>,
+    pub cleared: Vec<ValidationError>,
+}
+
+impl ValidationDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.cleared.is_empty()
+    }
+
+    /// Fold in the diff of a later edit. Errors one edit added and the
+    /// other cleared cancel out.
+    pub fn merge(&mut self, later: ValidationDiff) {
+        for error in later.added {
+            match self.cleared.iter().position(|e| *e == error) {
+                Some(i) => {
+                    self.cleared.remove(i);
+                }
+                None => self.added.push(error),
+            }
+        }
+        for error in later.cleared {
+            match self.added.iter().position(|e| *e == error) {
+                Some(i) => {
+                    self.added.remove(i);
+                }
+                None => self.cleared.push(error),
+            }
+        }
+    }
+}
+
+/// How a registered rule is re-checked after an edit
+enum RuleCheck {
+    /// Rules that only look at one row, checked with a single-rule `Validator`
+    Row(Validator),
+    /// `Unique` and `CompositeUnique`, with the rows holding each key
+    Unique {
+        key_columns: Vec<usize>,
+        index: HashMap<String, BTreeSet<usize>>,
+    },
+    /// `ForeignKey` into this data, with the rows holding each key and how
+    /// often each referenced key occurs
+    SelfReference {
+        key_columns: Vec<usize>,
+        reference_columns: Vec<usize>,
+        keys: HashMap<String, BTreeSet<usize>>,
+        referenced: HashMap<String, usize>,
+    },
+}
+
+struct LiveRule {
+    rule: ValidationRule,
+    /// Columns whose edits can change this rule's result
+    columns: Vec<usize>,
+    check: RuleCheck,
+}
+
+/// Validation state of an open document. Rules are registered once; each
+/// cell edit re-checks only the rows whose result it can change.
+///
+/// Rules that cannot be applied (an expression that does not compile, key
+/// columns that do not exist) are not registered; their rule errors are
+/// reported once. Files referenced by `ForeignKey` and `Enum` rules are
+/// read by the initial pass and not again. Inserting or deleting rows
+/// shifts row indices, so callers start a new `LiveValidator` then.
+pub struct LiveValidator {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    rules: Vec<LiveRule>,
+    /// Current errors by rule and row
+    errors: HashMap<(usize, usize), Vec<ValidationError>>,
+    /// Errors of rules that cannot be applied, which no edit changes
+    rule_errors: Vec<ValidationError>,
+}
+
+fn key_index(rows: &[Vec<String>], columns: &[usize]) -> HashMap<String, BTreeSet<usize>> {
+    let mut index: HashMap<String, BTreeSet<usize>> = HashMap::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        if let Some(key) = composite_key(row, columns) {
+            index.entry(key).or_default().insert(row_idx);
+        }
+    }
+    index
+}
+
+fn display_key(key: &str) -> String {
+    key.replace('\u{1f}', ", ")
+}
+
+impl LiveValidator {
+    pub fn new(
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+        rules: Vec<ValidationRule>,
+        base_dir: Option<PathBuf>,
+        locale: LocaleFormat,
+    ) -> Self {
+        let validator = |rule: &ValidationRule| {
+            let validator = Validator::new(vec![rule.clone()]).with_locale(locale.clone());
+            match &base_dir {
+                Some(dir) => validator.with_base_dir(dir),
+                None => validator,
+            }
+        };
+
+        let mut live_rules = Vec::new();
+        let mut rule_errors = Vec::new();
+        for rule in rules {
+            match Self::register(&rule, &headers, &rows, || validator(&rule)) {
+                Some((columns, check)) => live_rules.push(LiveRule { rule, columns, check }),
+                None => rule_errors.extend(
+                    validator(&rule).validate(&rows, &headers).into_iter().filter(|e| e.rule_error),
+                ),
+            }
+        }
+
+        let mut live = Self {
+            headers,
+            rows,
+            rules: live_rules,
+            errors: HashMap::new(),
+            rule_errors,
+        };
+        live.check_all();
+        live
+    }
+
+    /// Columns whose edits affect `rule` and how it is re-checked, `None`
+    /// when the rule cannot be applied to this data
+    fn register(
+        rule: &ValidationRule,
+        headers: &[String],
+        rows: &[Vec<String>],
+        validator: impl FnOnce() -> Validator,
+    ) -> Option<(Vec<usize>, RuleCheck)> {
+        let expression_columns = |key: &str| {
+            rule.parameters
+                .get(key)
+                .and_then(|source| Expression::compile(source, headers).ok())
+                .map(|expression| expression.columns().to_vec())
+        };
+
+        let registered = match rule.rule_type {
+            ValidationRuleType::Unique | ValidationRuleType::CompositeUnique => {
+                let key_columns = if rule.rule_type == ValidationRuleType::Unique {
+                    vec![rule.column_index]
+                } else {
+                    Validator::list_parameter(rule, "columns")
+                        .and_then(|names| Validator::column_indices(&names, headers))?
+                };
+                let index = key_index(rows, &key_columns);
+                (key_columns.clone(), RuleCheck::Unique { key_columns, index })
+            }
+            ValidationRuleType::ForeignKey
+                if rule.parameters.get("file").is_none_or(|f| f.is_empty()) =>
+            {
+                let names = Validator::list_parameter(rule, "columns")
+                    .unwrap_or_else(|| vec![rule.column_name.clone()]);
+                let reference_names = Validator::list_parameter(rule, "reference_columns")
+                    .unwrap_or_else(|| names.clone());
+                let key_columns = Validator::column_indices(&names, headers)?;
+                let reference_columns = Validator::column_indices(&reference_names, headers)?;
+
+                let keys = key_index(rows, &key_columns);
+                let referenced = key_index(rows, &reference_columns)
+                    .into_iter()
+                    .map(|(key, rows)| (key, rows.len()))
+                    .collect();
+                let columns = key_columns.iter().chain(&reference_columns).copied().collect();
+                (columns, RuleCheck::SelfReference { key_columns, reference_columns, keys, referenced })
+            }
+            _ => {
+                let columns = match rule.rule_type {
+                    ValidationRuleType::Custom => expression_columns("expression")?,
+                    ValidationRuleType::RequiredIf => {
+                        let mut columns = expression_columns("condition")?;
+                        columns.push(rule.column_index);
+                        columns
+                    }
+                    ValidationRuleType::ForeignKey => Validator::list_parameter(rule, "columns")
+                        .and_then(|names| Validator::column_indices(&names, headers))
+                        .unwrap_or_else(|| vec![rule.column_index]),
+                    _ => vec![rule.column_index],
+                };
+                (columns, RuleCheck::Row(validator()))
+            }
+        };
+        Some(registered)
+    }
+
+    /// Initial pass over every row. Row rules run once over the whole data
+    /// rather than row by row.
+    fn check_all(&mut self) {
+        for rule_idx in 0..self.rules.len() {
+            let live_rule = &self.rules[rule_idx];
+            let found = match &live_rule.check {
+                RuleCheck::Row(validator) => validator.validate(&self.rows, &self.headers),
+                RuleCheck::Unique { index, .. } => index
+                    .values()
+                    .flat_map(|rows| rows.iter().skip(1))
+                    .flat_map(|&row_idx| self.check_row(rule_idx, row_idx))
+                    .collect(),
+                RuleCheck::SelfReference { keys, .. } => keys
+                    .values()
+                    .flatten()
+                    .flat_map(|&row_idx| self.check_row(rule_idx, row_idx))
+                    .collect(),
+            };
+
+            for error in found {
+                if error.rule_error {
+                    self.rule_errors.push(error);
+                } else {
+                    self.errors.entry((rule_idx, error.row_index)).or_default().push(error);
+                }
+            }
+        }
+    }
+
+    /// Errors of one rule for one row, from the current indexes
+    fn check_row(&self, rule_idx: usize, row_idx: usize) -> Vec<ValidationError> {
+        let live_rule = &self.rules[rule_idx];
+        let Some(row) = self.rows.get(row_idx) else {
+            return Vec::new();
+        };
+
+        match &live_rule.check {
+            RuleCheck::Row(validator) => {
+                // Rule errors were reported by the initial pass
+                let mut errors = validator.validate(std::slice::from_ref(row), &self.headers);
+                errors.retain(|error| !error.rule_error);
+                for error in &mut errors {
+                    error.row_index = row_idx;
+                }
+                errors
+            }
+            RuleCheck::Unique { key_columns, index } => {
+                let Some(key) = composite_key(row, key_columns) else {
+                    return Vec::new();
+                };
+                match index.get(&key).and_then(|rows| rows.first()) {
+                    Some(&first_row) if first_row != row_idx => vec![Validator::duplicate_error(
+                        &live_rule.rule,
+                        &self.headers,
+                        row_idx,
+                        display_key(&key),
+                        first_row,
+                    )],
+                    _ => Vec::new(),
+                }
+            }
+            RuleCheck::SelfReference { key_columns, referenced, .. } => {
+                let Some(key) = composite_key(row, key_columns) else {
+                    return Vec::new();
+                };
+                if referenced.get(&key).copied().unwrap_or(0) > 0 {
+                    return Vec::new();
+                }
+                vec![Validator::missing_reference_error(&live_rule.rule, &self.headers, row_idx, display_key(&key))]
+            }
+        }
+    }
+
+    /// All current errors: rule errors first, then by row and rule order
+    pub fn errors(&self) -> Vec<ValidationError> {
+        let mut keys: Vec<&(usize, usize)> = self.errors.keys().collect();
+        keys.sort_by_key(|(rule_idx, row_idx)| (*row_idx, *rule_idx));
+        let row_errors = keys.into_iter().flat_map(|key| self.errors[key].iter().cloned());
+        self.rule_errors.iter().cloned().chain(row_errors).collect()
+    }
+
+    /// Apply an edit and re-check the rows it affects
+    pub fn update_cell(&mut self, row_idx: usize, column_idx: usize, value: String) -> Result<ValidationDiff> {
+        if column_idx >= self.headers.len() {
+            bail!("Column {} is out of range", column_idx);
+        }
+        let Some(row) = self.rows.get_mut(row_idx) else {
+            bail!("Row {} is out of range", row_idx);
+        };
+        if row.len() <= column_idx {
+            row.resize(column_idx + 1, String::new());
+        }
+        if row[column_idx] == value {
+            return Ok(ValidationDiff::default());
+        }
+
+        let old_row = row.clone();
+        row[column_idx] = value;
+        let new_row = row.clone();
+
+        let mut diff = ValidationDiff::default();
+        for rule_idx in 0..self.rules.len() {
+            if !self.rules[rule_idx].columns.contains(&column_idx) {
+                continue;
+            }
+
+            let mut affected = BTreeSet::from([row_idx]);
+            match &mut self.rules[rule_idx].check {
+                RuleCheck::Row(_) => {}
+                RuleCheck::Unique { key_columns, index } => {
+                    // Every row sharing the old or new key may change, since
+                    // which row came first can move
+                    let old_key = composite_key(&old_row, key_columns);
+                    let new_key = composite_key(&new_row, key_columns);
+                    if let Some(key) = &old_key {
+                        if let Some(rows) = index.get_mut(key) {
+                            rows.remove(&row_idx);
+                            affected.extend(rows.iter());
+                            if rows.is_empty() {
+                                index.remove(key);
+                            }
+                        }
+                    }
+                    if let Some(key) = new_key {
+                        let rows = index.entry(key).or_default();
+                        rows.insert(row_idx);
+                        affected.extend(rows.iter());
+                    }
+                }
+                RuleCheck::SelfReference { key_columns, reference_columns, keys, referenced } => {
+                    if let Some(key) = composite_key(&old_row, key_columns) {
+                        if let Some(rows) = keys.get_mut(&key) {
+                            rows.remove(&row_idx);
+                            if rows.is_empty() {
+                                keys.remove(&key);
+                            }
+                        }
+                    }
+                    if let Some(key) = composite_key(&new_row, key_columns) {
+                        keys.entry(key).or_default().insert(row_idx);
+                    }
+
+                    // Rows pointing at a key that appeared or disappeared
+                    let old_reference = composite_key(&old_row, reference_columns);
+                    let new_reference = composite_key(&new_row, reference_columns);
+                    if old_reference != new_reference {
+                        if let Some(key) = old_reference {
+                            let count = referenced.entry(key.clone()).or_default();
+                            *count = count.saturating_sub(1);
+                            if *count == 0 {
+                                referenced.remove(&key);
+                                affected.extend(keys.get(&key).into_iter().flatten());
+                            }
+                        }
+                        if let Some(key) = new_reference {
+                            let count = referenced.entry(key.clone()).or_default();
+                            *count += 1;
+                            if *count == 1 {
+                                affected.extend(keys.get(&key).into_iter().flatten());
+                            }
+                        }
+                    }
+                }
+            }
+
+            for affected_row in affected {
+                let found = self.check_row(rule_idx, affected_row);
+                let previous = match found.is_empty() {
+                    true => self.errors.remove(&(rule_idx, affected_row)),
+                    false => self.errors.insert((rule_idx, affected_row), found.clone()),
+                }
+                .unwrap_or_default();
+
+                diff.cleared.extend(previous.iter().filter(|e| !found.contains(e)).cloned());
+                diff.added.extend(found.into_iter().filter(|e| !previous.contains(e)));
+            }
+        }
+
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(rule_type: ValidationRuleType, column_index: usize, column_name: &str, parameters: &[(&str, &str)]) -> ValidationRule {
+        ValidationRule {
+            rule_type,
+            column_index,
+            column_name: column_name.to_string(),
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            error_message: None,
+        }
+    }
+
+    fn rows(data: &[&[&str]]) -> Vec<Vec<String>> {
+        data.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn test_live_unique_and_required() {
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let data = rows(&[&["1", "a"], &["2", "b"], &["1", ""]]);
+        let rules = vec![
+            rule(ValidationRuleType::Unique, 0, "id", &[]),
+            rule(ValidationRuleType::Required, 1, "name", &[]),
+        ];
+
+        let mut live = LiveValidator::new(headers.clone(), data.clone(), rules.clone(), None, LocaleFormat::default());
+        assert_eq!(live.errors(), Validator::new(rules).validate(&data, &headers));
+        assert_eq!(live.errors().len(), 2);
+
+        // Fixing the duplicate clears only its error
+        let diff = live.update_cell(2, 0, "3".to_string()).unwrap();
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.cleared.len(), 1);
+        assert_eq!(diff.cleared[0].rule_type, ValidationRuleType::Unique);
+
+        // A new duplicate of row 2 is reported against the later row
+        let diff = live.update_cell(0, 0, "3".to_string()).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].row_index, 2);
+
+        // Editing an unrelated column leaves the unique rule alone
+        let diff = live.update_cell(2, 1, "c".to_string()).unwrap();
+        assert_eq!(diff.cleared.len(), 1);
+        assert_eq!(diff.cleared[0].rule_type, ValidationRuleType::Required);
+        assert_eq!(live.errors().len(), 1);
+
+        assert!(live.update_cell(5, 0, "x".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_merged_diffs_cancel_out() {
+        let headers = vec!["id".to_string()];
+        let rules = vec![rule(ValidationRuleType::Required, 0, "id", &[])];
+        let mut live = LiveValidator::new(headers, rows(&[&["1"], &["2"]]), rules, None, LocaleFormat::default());
+
+        // Emptying a cell and filling it again nets to nothing
+        let mut diff = live.update_cell(0, 0, String::new()).unwrap();
+        diff.merge(live.update_cell(1, 0, String::new()).unwrap());
+        diff.merge(live.update_cell(0, 0, "3".to_string()).unwrap());
+        assert!(diff.cleared.is_empty());
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].row_index, 1);
+    }
+
+    #[test]
+    fn test_live_self_reference() {
+        let headers = vec!["id".to_string(), "parent".to_string()];
+        let data = rows(&[&["1", ""], &["2", "1"], &["3", "2"]]);
+        let rules = vec![rule(
+            ValidationRuleType::ForeignKey,
+            1,
+            "parent",
+            &[("reference_columns", r#"["id"]"#)],
+        )];
+
+        let mut live = LiveValidator::new(headers, data, rules, None, LocaleFormat::default());
+        assert!(live.errors().is_empty());
+
+        // Renaming a referenced id breaks the rows pointing at it
+        let diff = live.update_cell(1, 0, "20".to_string()).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].row_index, 2);
+
+        let diff = live.update_cell(2, 1, "20".to_string()).unwrap();
+        assert_eq!(diff.cleared.len(), 1);
+        assert!(live.errors().is_empty());
+    }
+
+    #[test]
+    fn test_live_reads_referenced_files_once() {
+        let dir = std::env::temp_dir().join(format!("live-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("customers.csv"), "id\nC1\nC2\n").unwrap();
+        std::fs::write(dir.join("statuses.txt"), "open\nclosed\n").unwrap();
+
+        let headers = vec!["customer".to_string(), "status".to_string()];
+        let data = rows(&[&["C1", "open"], &["C2", "closed"]]);
+        let rules = vec![
+            rule(ValidationRuleType::ForeignKey, 0, "customer", &[("file", "customers.csv"), ("reference_columns", r#"["id"]"#)]),
+            rule(ValidationRuleType::Enum, 1, "status", &[("values_file", "statuses.txt")]),
+        ];
+        let mut live = LiveValidator::new(headers, data, rules, Some(dir.clone()), LocaleFormat::default());
+        assert!(live.errors().is_empty());
+
+        // Edits are checked against what was loaded when validation started
+        std::fs::remove_dir_all(&dir).unwrap();
+        let diff = live.update_cell(0, 0, "C3".to_string()).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert!(!diff.added[0].rule_error);
+        let diff = live.update_cell(1, 1, "closed".to_string()).unwrap();
+        assert!(diff.is_empty());
+        let diff = live.update_cell(1, 1, "lost".to_string()).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].rule_type, ValidationRuleType::Enum);
+    }
+}
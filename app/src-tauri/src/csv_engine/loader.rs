@@ -0,0 +1,323 @@
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use crate::csv_engine::compression;
+use crate::csv_engine::encoding::DecodingReader;
+use crate::csv_engine::reader::{Cancelled, CsvData, CsvReader};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadProgress {
+    /// Bytes read from disk, compressed size for compressed files
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub rows_parsed: usize,
+    pub progress_percentage: f64,
+    pub estimated_remaining_seconds: Option<u64>,
+}
+
+impl LoadProgress {
+    fn new(bytes_read: u64, total_bytes: u64, rows_parsed: usize, elapsed: Duration) -> Self {
+        let fraction = if total_bytes > 0 {
+            (bytes_read as f64 / total_bytes as f64).min(1.0)
+        } else {
+            1.0
+        };
+
+        // Assume the remaining bytes parse at the rate seen so far
+        let estimated_remaining_seconds = if fraction > 0.0 && fraction < 1.0 {
+            Some((elapsed.as_secs_f64() * (1.0 - fraction) / fraction).ceil() as u64)
+        } else {
+            None
+        };
+
+        Self {
+            bytes_read,
+            total_bytes,
+            rows_parsed,
+            progress_percentage: fraction * 100.0,
+            estimated_remaining_seconds,
+        }
+    }
+}
+
+/// Rows are lent from the data being built, so a batch costs no copy
+pub enum LoadEvent<'a> {
+    /// The first screenful of rows, sent as soon as it is parsed
+    FirstRows { headers: &'a [String], rows: &'a [Vec<String>] },
+    /// A later batch of rows starting at `start_row`
+    Rows { start_row: usize, rows: &'a [Vec<String>] },
+    Progress(LoadProgress),
+}
+
+/// Reads a file incrementally, reporting rows and progress through a
+/// callback so the grid can be filled while the rest is still parsing.
+pub struct BackgroundLoader {
+    reader: CsvReader,
+    first_batch: usize,
+    batch_size: usize,
+    progress_interval: Duration,
+}
+
+impl BackgroundLoader {
+    pub fn new(reader: CsvReader) -> Self {
+        Self {
+            reader,
+            first_batch: 200,
+            batch_size: 10_000,
+            progress_interval: Duration::from_millis(200),
+        }
+    }
+
+    /// Number of rows in the first batch, enough to fill the visible grid
+    pub fn with_first_batch(mut self, rows: usize) -> Self {
+        self.first_batch = rows.max(1);
+        self
+    }
+
+    pub fn with_batch_size(mut self, rows: usize) -> Self {
+        self.batch_size = rows.max(1);
+        self
+    }
+
+    /// Load the file, returning `None` if `cancelled` was set on the way.
+    /// The returned data holds every row, including those already sent.
+    pub fn load(
+        mut self,
+        path: &Path,
+        cancelled: &Arc<AtomicBool>,
+        mut on_event: impl FnMut(LoadEvent),
+    ) -> Result<Option<CsvData>> {
+        self.reader.prepare(path)?;
+
+        // Lenient repairs and multi-character delimiters work on the whole
+        // text, so those files are parsed at once and then handed out
+        let delimiter = match self.reader.delimiter().as_bytes() {
+            [delimiter] if !self.reader.is_lenient() => *delimiter,
+            _ => return self.load_at_once(path, cancelled, on_event),
+        };
+
+        let total_bytes = std::fs::metadata(path)?.len();
+        let bytes_read = Rc::new(Cell::new(0));
+        let file = File::open(path).context("Failed to open CSV file")?;
+        let counting = CountingReader { inner: BufReader::new(file), count: bytes_read.clone() };
+        let source = compression::decompress(
+            counting,
+            self.reader.compression().unwrap_or_default(),
+            self.reader.archive_member(),
+        )?;
+
+        let dialect = self.reader.dialect().clone();
+        let mut csv_reader = dialect
+            .reader_builder(delimiter, false)
+            .from_reader(DecodingReader::new(source, self.reader.encoding()));
+
+        let started = Instant::now();
+        let mut last_progress = started;
+        let mut headers: Option<Vec<String>> = None;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut sent = 0;
+        let mut record = csv::StringRecord::new();
+
+        while csv_reader.read_record(&mut record).context("Failed to read CSV record")? {
+            if dialect.should_skip(&record) {
+                continue;
+            }
+
+            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            if self.reader.has_headers() && headers.is_none() {
+                headers = Some(row);
+                continue;
+            }
+            rows.push(row);
+
+            if rows.len().is_multiple_of(1024) && cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+
+            if sent == 0 && rows.len() >= self.first_batch {
+                on_event(LoadEvent::FirstRows {
+                    headers: &resolve_headers(headers.as_ref(), &rows),
+                    rows: &rows,
+                });
+                sent = rows.len();
+            } else if sent > 0 && rows.len() - sent >= self.batch_size {
+                on_event(LoadEvent::Rows { start_row: sent, rows: &rows[sent..] });
+                sent = rows.len();
+            }
+
+            if last_progress.elapsed() >= self.progress_interval {
+                last_progress = Instant::now();
+                on_event(LoadEvent::Progress(LoadProgress::new(
+                    bytes_read.get(),
+                    total_bytes,
+                    rows.len(),
+                    started.elapsed(),
+                )));
+            }
+        }
+
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let headers = resolve_headers(headers.as_ref(), &rows);
+        Self::send_remaining(&headers, &rows, sent, &mut on_event);
+        on_event(LoadEvent::Progress(LoadProgress::new(total_bytes, total_bytes, rows.len(), started.elapsed())));
+
+        let decode_errors = csv_reader.get_ref().decode_errors();
+        let metadata = self.reader.file_metadata(path, rows.len(), headers.len())?;
+        let encoding_detection = self.reader.encoding_detection().cloned().map(|mut detection| {
+            detection.decode_errors = decode_errors;
+            detection
+        });
+
+        Ok(Some(CsvData {
+            headers,
+            rows,
+            metadata,
+            sniff: self.reader.sniff().cloned(),
+            malformed_rows: Vec::new(),
+            encoding_detection,
+        }))
+    }
+
+    /// Parse the whole text, checking `cancelled` between batches of
+    /// records, then hand the rows out in the usual batches
+    fn load_at_once(
+        mut self,
+        path: &Path,
+        cancelled: &Arc<AtomicBool>,
+        mut on_event: impl FnMut(LoadEvent),
+    ) -> Result<Option<CsvData>> {
+        let started = Instant::now();
+        self.reader = self.reader.with_cancel_flag(cancelled.clone());
+        let data = match self.reader.read_prepared(path) {
+            Ok(data) => data,
+            Err(e) if e.is::<Cancelled>() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let total_bytes = data.metadata.file_size;
+        let first = data.rows.len().min(self.first_batch);
+        on_event(LoadEvent::FirstRows { headers: &data.headers, rows: &data.rows[..first] });
+        for start in (first..data.rows.len()).step_by(self.batch_size) {
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            let end = data.rows.len().min(start + self.batch_size);
+            on_event(LoadEvent::Rows { start_row: start, rows: &data.rows[start..end] });
+        }
+        on_event(LoadEvent::Progress(LoadProgress::new(total_bytes, total_bytes, data.rows.len(), started.elapsed())));
+
+        Ok(Some(data))
+    }
+
+    /// Send rows from `sent` on, as the first screenful if nothing was sent yet
+    fn send_remaining(headers: &[String], rows: &[Vec<String>], sent: usize, on_event: &mut impl FnMut(LoadEvent)) {
+        if sent == 0 {
+            on_event(LoadEvent::FirstRows { headers, rows });
+        } else if sent < rows.len() {
+            on_event(LoadEvent::Rows { start_row: sent, rows: &rows[sent..] });
+        }
+    }
+}
+
+/// Header row of the file, or generated names for files without one
+fn resolve_headers(headers: Option<&Vec<String>>, rows: &[Vec<String>]) -> Vec<String> {
+    match headers {
+        Some(headers) if !headers.is_empty() => headers.clone(),
+        _ => {
+            let column_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+            (1..=column_count).map(|i| format!("Column {}", i)).collect()
+        }
+    }
+}
+
+/// Counts bytes read from disk so progress can be reported against the
+/// file size even when the content is decompressed on the way
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_arrive_in_batches_and_can_be_cancelled() {
+        let path = std::env::temp_dir().join(format!("{}-load.csv", uuid::Uuid::new_v4()));
+        let text: String = std::iter::once("id,name\n".to_string())
+            .chain((0..2500).map(|i| format!("{},n{}\n", i, i)))
+            .collect();
+        std::fs::write(&path, text).unwrap();
+
+        let mut batches = Vec::new();
+        let data = BackgroundLoader::new(CsvReader::new())
+            .with_first_batch(100)
+            .with_batch_size(1000)
+            .load(&path, &Arc::new(AtomicBool::new(false)), |event| match event {
+                LoadEvent::FirstRows { headers, rows } => {
+                    assert_eq!(headers, ["id", "name"]);
+                    batches.push((0, rows.len()));
+                }
+                LoadEvent::Rows { start_row, rows } => batches.push((start_row, rows.len())),
+                LoadEvent::Progress(_) => {}
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(batches, vec![(0, 100), (100, 1000), (1100, 1000), (2100, 400)]);
+        assert_eq!(data.rows.len(), 2500);
+        assert_eq!(data.rows[2499], vec!["2499", "n2499"]);
+
+        let cancelled = BackgroundLoader::new(CsvReader::new())
+            .load(&path, &Arc::new(AtomicBool::new(true)), |_| {})
+            .unwrap();
+        assert!(cancelled.is_none());
+
+        // Lenient files are parsed at once but still batched and cancellable
+        let mut batches = Vec::new();
+        let data = BackgroundLoader::new(CsvReader::new().with_lenient(true))
+            .with_first_batch(100)
+            .with_batch_size(1000)
+            .load(&path, &Arc::new(AtomicBool::new(false)), |event| match event {
+                LoadEvent::FirstRows { rows, .. } => batches.push((0, rows.len())),
+                LoadEvent::Rows { start_row, rows } => batches.push((start_row, rows.len())),
+                LoadEvent::Progress(_) => {}
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(batches, vec![(0, 100), (100, 1000), (1100, 1000), (2100, 400)]);
+        assert_eq!(data.rows.len(), 2500);
+
+        let cancelled = BackgroundLoader::new(CsvReader::new().with_lenient(true))
+            .load(&path, &Arc::new(AtomicBool::new(true)), |_| panic!("no rows after cancelling"))
+            .unwrap();
+        assert!(cancelled.is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::settings::ImportExportSettings;
+
+/// How numbers and dates are written in a file. Separators are stored as
+/// strings to match `ImportExportSettings`; only the first character is
+/// used, and an empty thousands separator disables grouping.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocaleFormat {
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: String,
+    #[serde(default = "default_thousands_separator")]
+    pub thousands_separator: String,
+    /// chrono format tried before the built-in date formats
+    #[serde(default)]
+    pub date_format: String,
+}
+
+fn default_decimal_separator() -> String {
+    ".".to_string()
+}
+
+fn default_thousands_separator() -> String {
+    ",".to_string()
+}
+
+impl Default for LocaleFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: default_decimal_separator(),
+            thousands_separator: default_thousands_separator(),
+            date_format: String::new(),
+        }
+    }
+}
+
+/// Tried after the configured format. Month-first comes before day-first
+/// so ambiguous dates sort as they always have.
+const FALLBACK_DATE_FORMATS: [&str; 7] = [
+    "%Y-%m-%d",
+    "%m/%d/%Y",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%Y年%m月%d日",
+];
+
+impl LocaleFormat {
+    pub fn from_settings(settings: &ImportExportSettings) -> Self {
+        Self {
+            decimal_separator: settings.decimal_separator.clone(),
+            thousands_separator: settings.thousands_separator.clone(),
+            date_format: settings.date_format.clone(),
+        }
+    }
+
+    /// Separators customary for a BCP 47 tag such as `de-DE` or `ja`
+    pub fn for_language_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or("").to_lowercase();
+        let (decimal, thousands) = match language.as_str() {
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" => (",", "."),
+            "fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk" => (",", " "),
+            "" => (".", ""),
+            _ => (".", ","),
+        };
+
+        Self {
+            decimal_separator: decimal.to_string(),
+            thousands_separator: thousands.to_string(),
+            date_format: String::new(),
+        }
+    }
+
+    fn decimal(&self) -> char {
+        self.decimal_separator.chars().next().unwrap_or('.')
+    }
+
+    fn thousands(&self) -> Option<char> {
+        self.thousands_separator.chars().next().filter(|c| *c != self.decimal())
+    }
+
+    /// Integer and fraction digits of a number in this locale, with
+    /// thousands groups checked so `1,5` is not read as fifteen
+    fn split_number(&self, value: &str) -> Option<(String, Option<String>)> {
+        let text = to_half_width(value.trim());
+        let text = text.trim();
+        let (negative, body) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+
+        let (integer, fraction) = match body.split_once(self.decimal()) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (body, None),
+        };
+
+        let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        if fraction.is_some_and(|f| !is_digits(f)) || (integer.is_empty() && fraction.is_none()) {
+            return None;
+        }
+
+        let digits = match self.thousands() {
+            // A space separator also matches the no-break spaces many tools write
+            Some(separator) if integer.contains(separator) || (separator == ' ' && integer.contains('\u{a0}')) => {
+                let groups: Vec<&str> = integer.split([separator, '\u{a0}']).collect();
+                let valid = groups[0].len() <= 3
+                    && groups.iter().all(|g| is_digits(g))
+                    && groups[1..].iter().all(|g| g.len() == 3);
+                if !valid {
+                    return None;
+                }
+                groups.concat()
+            }
+            _ if integer.is_empty() || is_digits(integer) => integer.to_string(),
+            _ => return None,
+        };
+
+        let digits = if negative { format!("-{}", digits) } else { digits };
+        Some((digits, fraction.map(String::from)))
+    }
+
+    /// Parse a number written in this locale, full-width digits included.
+    /// With `.` as decimal separator, anything Rust parses (`1e5`) is also accepted.
+    pub fn parse_number(&self, value: &str) -> Option<f64> {
+        match self.split_number(value) {
+            Some((integer, Some(fraction))) => format!("{}.{}", integer, fraction).parse().ok(),
+            Some((integer, None)) => integer.parse().ok(),
+            None if self.decimal() == '.' => to_half_width(value.trim()).trim().parse().ok(),
+            None => None,
+        }
+    }
+
+    /// Parse a whole number written in this locale, without a fraction part
+    pub fn parse_integer(&self, value: &str) -> Option<i64> {
+        match self.split_number(value)? {
+            (integer, None) => integer.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Parse a date with the configured format, a Japanese era, or the
+    /// common numeric formats
+    pub fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        let text = to_half_width(value.trim());
+        let text = text.trim();
+
+        if !self.date_format.is_empty() {
+            if let Ok(date) = NaiveDate::parse_from_str(text, &self.date_format) {
+                return Some(date);
+            }
+        }
+
+        parse_japanese_era(text).or_else(|| {
+            FALLBACK_DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        })
+    }
+}
+
+/// Map full-width ASCII (`１２３`, `．`, `－`, `％`) and the ideographic
+/// space to their ASCII forms, as NFKC would
+pub fn to_half_width(value: &str) -> Cow<'_, str> {
+    let is_wide = |c: char| matches!(c, '\u{FF01}'..='\u{FF5E}' | '\u{3000}');
+    if !value.chars().any(is_wide) {
+        return Cow::Borrowed(value);
+    }
+
+    Cow::Owned(
+        value
+            .chars()
+            .map(|c| match c {
+                '\u{3000}' => ' ',
+                '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+                _ => c,
+            })
+            .collect(),
+    )
+}
+
+/// Dates such as `令和6年12月31日`, `平成元年5月1日` or `R6.12.31`.
+/// The year must fall inside its era.
+fn parse_japanese_era(text: &str) -> Option<NaiveDate> {
+    static ERA_DATE: OnceLock<Regex> = OnceLock::new();
+    let era_date = ERA_DATE.get_or_init(|| {
+        Regex::new(r"^(明治|大正|昭和|平成|令和|[MTSHRmtshr])\s*(元|\d{1,2})\s*(?:年|[./-])\s*(\d{1,2})\s*(?:月|[./-])\s*(\d{1,2})\s*日?$")
+            .unwrap()
+    });
+
+    // Year, month and day
+    type Ymd = (i32, u32, u32);
+    // Era name, initial, first day and last day
+    const ERAS: [(&str, char, Ymd, Ymd); 5] = [
+        ("明治", 'M', (1868, 1, 25), (1912, 7, 29)),
+        ("大正", 'T', (1912, 7, 30), (1926, 12, 24)),
+        ("昭和", 'S', (1926, 12, 25), (1989, 1, 7)),
+        ("平成", 'H', (1989, 1, 8), (2019, 4, 30)),
+        ("令和", 'R', (2019, 5, 1), (9999, 12, 31)),
+    ];
+
+    let captures = era_date.captures(text)?;
+    let era = &captures[1];
+    let (_, _, start, end) = ERAS.iter().find(|(name, initial, _, _)| {
+        era == *name || era.eq_ignore_ascii_case(&initial.to_string())
+    })?;
+
+    let year_in_era: i32 = match &captures[2] {
+        "元" => 1,
+        year => year.parse().ok()?,
+    };
+    let date = NaiveDate::from_ymd_opt(start.0 + year_in_era - 1, captures[3].parse().ok()?, captures[4].parse().ok()?)?;
+
+    let first = NaiveDate::from_ymd_opt(start.0, start.1, start.2)?;
+    let last = NaiveDate::from_ymd_opt(end.0, end.1, end.2)?;
+    (first..=last).contains(&date).then_some(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_european_numbers_and_dates() {
+        let locale = LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+        };
+
+        assert_eq!(locale.parse_number("1.234,56"), Some(1234.56));
+        assert_eq!(locale.parse_number("-0,5"), Some(-0.5));
+        assert_eq!(locale.parse_integer("1.234"), Some(1234));
+        assert_eq!(locale.parse_number("1.23,4"), None);
+        assert_eq!(locale.parse_date("31.12.2024"), NaiveDate::from_ymd_opt(2024, 12, 31));
+
+        let default = LocaleFormat::default();
+        assert_eq!(default.parse_number("1,234.5"), Some(1234.5));
+        assert_eq!(default.parse_number("1,5"), None);
+        assert_eq!(default.parse_number("1e3"), Some(1000.0));
+        assert_eq!(LocaleFormat::for_language_tag("fr-FR").parse_number("1 234,5"), Some(1234.5));
+    }
+
+    #[test]
+    fn test_japanese_era_dates_and_full_width_digits() {
+        let locale = LocaleFormat::default();
+
+        assert_eq!(locale.parse_number("１，２３４．５"), Some(1234.5));
+        assert_eq!(locale.parse_date("令和6年12月31日"), NaiveDate::from_ymd_opt(2024, 12, 31));
+        assert_eq!(locale.parse_date("平成元年１月８日"), NaiveDate::from_ymd_opt(1989, 1, 8));
+        assert_eq!(locale.parse_date("R6.1.5"), NaiveDate::from_ymd_opt(2024, 1, 5));
+        assert_eq!(locale.parse_date("2024年3月1日"), NaiveDate::from_ymd_opt(2024, 3, 1));
+        // Heisei ended on 30 April 2019
+        assert_eq!(locale.parse_date("平成31年5月1日"), None);
+    }
+}
@@ -6,5 +6,21 @@ pub mod validation;
 pub mod quality;
 pub mod cleansing;
 pub mod export;
+pub mod fixed_width;
+pub mod dialect;
+pub mod sniffer;
+pub mod encoding;
+pub mod compression;
+pub mod column_store;
+pub mod loader;
+pub mod locale;
+pub mod schema;
+pub mod expression;
+pub mod live_validation;
+pub mod report;
+pub mod quality_trend;
+pub mod profiler;
+pub mod dedup;
+pub mod outliers;
 
 // Re-exported types are used directly from their modules
\ No newline at end of file
@@ -0,0 +1,409 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use super::locale::LocaleFormat;
+
+/// How outliers are found. Every method but `Isolation` looks at one
+/// column at a time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum OutlierMethod {
+    /// Distance from the mean in standard deviations
+    ZScore { threshold: f64 },
+    /// Distance from the median in median absolute deviations, scaled to
+    /// match the z-score for normal data; robust to the outliers themselves
+    ModifiedZScore { threshold: f64 },
+    /// Beyond `k` interquartile ranges outside the quartiles
+    Iqr { k: f64 },
+    /// Below the `lower` or above the `upper` quantile, e.g. 0.01 and 0.99
+    Percentile { lower: f64, upper: f64 },
+    /// Rows isolated quickly by random splits across all the columns
+    /// (isolation forest); scores run from 0 to 1, with 0.5 typical
+    Isolation { threshold: f64 },
+}
+
+impl Default for OutlierMethod {
+    fn default() -> Self {
+        Self::ZScore { threshold: 3.0 }
+    }
+}
+
+/// A value found to be an outlier. `lower` and `upper` are the bounds
+/// values are capped to when winsorizing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Outlier {
+    pub row_index: usize,
+    pub column_index: usize,
+    pub value: String,
+    pub score: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Lower and upper bound; values outside are outliers
+pub type Bounds = (f64, f64);
+
+/// Outlying values of a column as row index and score, with the bounds used
+pub type ColumnOutliers = (Vec<(usize, f64)>, Option<Bounds>);
+
+impl OutlierMethod {
+    /// From the `method` cleansing parameter (`z_score`, `modified_z_score`,
+    /// `iqr`, `percentile` or `isolation`) with its `threshold`, `k`,
+    /// `lower` and `upper` parameters; anything missing takes the default
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Self {
+        let number = |key: &str, default: f64| {
+            parameters.get(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        match parameters.get("method").map(String::as_str) {
+            Some("modified_z_score") => Self::ModifiedZScore { threshold: number("threshold", 3.5) },
+            Some("iqr") => Self::Iqr { k: number("k", 1.5) },
+            Some("percentile") => Self::Percentile {
+                lower: number("lower", 0.01),
+                upper: number("upper", 0.99),
+            },
+            Some("isolation") => Self::Isolation { threshold: number("threshold", 0.6) },
+            _ => Self::ZScore { threshold: number("threshold", 3.0) },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ZScore { .. } => "Z-Score",
+            Self::ModifiedZScore { .. } => "Modified Z-Score",
+            Self::Iqr { .. } => "IQR",
+            Self::Percentile { .. } => "Percentile",
+            Self::Isolation { .. } => "Isolation",
+        }
+    }
+
+    /// Outliers among the numeric values of one column, as row index and
+    /// score, with the bounds values outside are outliers. Needs at least
+    /// three values; `Isolation` treats the column as its only feature.
+    pub fn detect_column(&self, values: &[(usize, f64)]) -> ColumnOutliers {
+        if values.len() < 3 {
+            return (Vec::new(), None);
+        }
+        let mut sorted: Vec<f64> = values.iter().map(|(_, v)| *v).collect();
+        sorted.sort_by(f64::total_cmp);
+
+        // Bounds, and the score of a value given them
+        let (lower, upper, score): (f64, f64, Box<dyn Fn(f64) -> f64>) = match *self {
+            Self::ZScore { threshold } => {
+                let n = sorted.len() as f64;
+                let mean = sorted.iter().sum::<f64>() / n;
+                let std_dev = (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+                if std_dev == 0.0 {
+                    return (Vec::new(), None);
+                }
+                let (lower, upper) = (mean - threshold * std_dev, mean + threshold * std_dev);
+                (lower, upper, Box::new(move |v| ((v - mean) / std_dev).abs()))
+            }
+            Self::ModifiedZScore { threshold } => {
+                let median = quantile(&sorted, 0.5);
+                let mut deviations: Vec<f64> = sorted.iter().map(|v| (v - median).abs()).collect();
+                deviations.sort_by(f64::total_cmp);
+                let mad = quantile(&deviations, 0.5);
+                if mad == 0.0 {
+                    return (Vec::new(), None);
+                }
+                let spread = threshold * mad / 0.6745;
+                (median - spread, median + spread, Box::new(move |v| 0.6745 * (v - median).abs() / mad))
+            }
+            Self::Iqr { k } => {
+                let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+                let iqr = q3 - q1;
+                if iqr == 0.0 {
+                    return (Vec::new(), None);
+                }
+                let (lower, upper) = (q1 - k * iqr, q3 + k * iqr);
+                (lower, upper, Box::new(move |v| fence_distance(v, lower, upper) / iqr))
+            }
+            Self::Percentile { lower, upper } => {
+                let (lower, upper) = (quantile(&sorted, lower), quantile(&sorted, upper));
+                let range = (upper - lower).max(f64::EPSILON);
+                (lower, upper, Box::new(move |v| fence_distance(v, lower, upper) / range))
+            }
+            Self::Isolation { threshold } => {
+                let features: Vec<Vec<f64>> = values.iter().map(|(_, v)| vec![*v]).collect();
+                let scores = isolation_scores(&features);
+                let outliers = values
+                    .iter()
+                    .zip(scores)
+                    .filter(|(_, score)| *score > threshold)
+                    .map(|((row, _), score)| (*row, score))
+                    .collect();
+                let bounds = (quantile(&sorted, 0.01), quantile(&sorted, 0.99));
+                return (outliers, Some(bounds));
+            }
+        };
+
+        let outliers = values
+            .iter()
+            .filter(|(_, v)| *v < lower || *v > upper)
+            .map(|(row, v)| (*row, score(*v)))
+            .collect();
+        (outliers, Some((lower, upper)))
+    }
+}
+
+fn fence_distance(value: f64, lower: f64, upper: f64) -> f64 {
+    if value < lower {
+        lower - value
+    } else if value > upper {
+        value - upper
+    } else {
+        0.0
+    }
+}
+
+/// Linear interpolation between the closest ranks of sorted values
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/// Numeric values of a column, by row
+fn column_values(data: &[Vec<String>], column: usize, locale: &LocaleFormat) -> Vec<(usize, f64)> {
+    data.iter()
+        .enumerate()
+        .filter_map(|(row_idx, row)| {
+            let value = locale.parse_number(row.get(column)?)?;
+            value.is_finite().then_some((row_idx, value))
+        })
+        .collect()
+}
+
+/// Outliers of `columns` by `method`. Single-column methods check each
+/// column alone. `Isolation` scores whole rows over the columns that are
+/// mostly numeric, reporting each of their cells in an outlying row, and
+/// skips rows missing any of them.
+pub fn find_outliers(
+    data: &[Vec<String>],
+    columns: &[usize],
+    method: &OutlierMethod,
+    locale: &LocaleFormat,
+) -> Vec<Outlier> {
+    let cell = |row: usize, column: usize| data[row].get(column).cloned().unwrap_or_default();
+
+    let OutlierMethod::Isolation { threshold } = method else {
+        return columns
+            .par_iter()
+            .flat_map_iter(|&column| {
+                let (outliers, bounds) = method.detect_column(&column_values(data, column, locale));
+                let (lower, upper) = bounds.unwrap_or((f64::NEG_INFINITY, f64::INFINITY));
+                outliers.into_iter().map(move |(row_index, score)| Outlier {
+                    row_index,
+                    column_index: column,
+                    value: cell(row_index, column),
+                    score,
+                    lower,
+                    upper,
+                })
+            })
+            .collect();
+    };
+
+    // Column index, its values by row, and the bounds reported with each outlier
+    let numeric: Vec<(usize, HashMap<usize, f64>, Bounds)> = columns
+        .iter()
+        .filter_map(|&column| {
+            let values = column_values(data, column, locale);
+            let filled = data.iter().filter(|row| row.get(column).is_some_and(|v| !v.trim().is_empty())).count();
+            if values.len() < 3 || values.len() * 2 < filled {
+                return None;
+            }
+            let mut sorted: Vec<f64> = values.iter().map(|(_, v)| *v).collect();
+            sorted.sort_by(f64::total_cmp);
+            let bounds = (quantile(&sorted, 0.01), quantile(&sorted, 0.99));
+            Some((column, values.into_iter().collect(), bounds))
+        })
+        .collect();
+    if numeric.is_empty() {
+        return Vec::new();
+    }
+
+    let rows: Vec<(usize, Vec<f64>)> = (0..data.len())
+        .filter_map(|row| {
+            let features: Option<Vec<f64>> = numeric.iter().map(|(_, values, _)| values.get(&row).copied()).collect();
+            features.map(|f| (row, f))
+        })
+        .collect();
+    let features: Vec<Vec<f64>> = rows.iter().map(|(_, f)| f.clone()).collect();
+    let scores = isolation_scores(&features);
+
+    rows.iter()
+        .zip(scores)
+        .filter(|(_, score)| *score > *threshold)
+        .flat_map(|((row_index, _), score)| {
+            numeric.iter().map(move |(column, _, (lower, upper))| Outlier {
+                row_index: *row_index,
+                column_index: *column,
+                value: cell(*row_index, *column),
+                score,
+                lower: *lower,
+                upper: *upper,
+            })
+        })
+        .collect()
+}
+
+const ISOLATION_TREES: usize = 100;
+const ISOLATION_SAMPLE: usize = 256;
+
+/// Small deterministic generator, so the same data always gets the same scores
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+enum IsolationNode {
+    Leaf { size: usize },
+    Split { feature: usize, value: f64, left: Box<IsolationNode>, right: Box<IsolationNode> },
+}
+
+/// Average path length of an unsuccessful search in a binary tree of `n` points
+fn average_path(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        n => {
+            let n = n as f64;
+            2.0 * ((n - 1.0).ln() + 0.577_215_664_9) - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+fn build_tree(points: &[&Vec<f64>], depth: usize, max_depth: usize, rng: &mut XorShift) -> IsolationNode {
+    if points.len() <= 1 || depth >= max_depth {
+        return IsolationNode::Leaf { size: points.len() };
+    }
+
+    // Features that still vary among the points, each with its range
+    let dimensions = points[0].len();
+    let ranges: Vec<(usize, f64, f64)> = (0..dimensions)
+        .filter_map(|feature| {
+            let (min, max) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+                (min.min(p[feature]), max.max(p[feature]))
+            });
+            (max > min).then_some((feature, min, max))
+        })
+        .collect();
+    if ranges.is_empty() {
+        return IsolationNode::Leaf { size: points.len() };
+    }
+
+    let (feature, min, max) = ranges[rng.below(ranges.len())];
+    let value = min + rng.unit() * (max - min);
+    let (left, right): (Vec<&Vec<f64>>, Vec<&Vec<f64>>) = points.iter().partition(|p| p[feature] < value);
+
+    IsolationNode::Split {
+        feature,
+        value,
+        left: Box::new(build_tree(&left, depth + 1, max_depth, rng)),
+        right: Box::new(build_tree(&right, depth + 1, max_depth, rng)),
+    }
+}
+
+fn path_length(point: &[f64], node: &IsolationNode, depth: usize) -> f64 {
+    match node {
+        IsolationNode::Leaf { size } => depth as f64 + average_path(*size),
+        IsolationNode::Split { feature, value, left, right } => {
+            let next = if point[*feature] < *value { left } else { right };
+            path_length(point, next, depth + 1)
+        }
+    }
+}
+
+/// Isolation forest anomaly score of each point, from 0 to 1
+fn isolation_scores(points: &[Vec<f64>]) -> Vec<f64> {
+    if points.len() < 3 {
+        return vec![0.0; points.len()];
+    }
+    let sample = points.len().min(ISOLATION_SAMPLE);
+    let max_depth = (sample as f64).log2().ceil() as usize;
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+    let trees: Vec<IsolationNode> = (0..ISOLATION_TREES)
+        .map(|_| {
+            let subsample: Vec<&Vec<f64>> = (0..sample).map(|_| &points[rng.below(points.len())]).collect();
+            build_tree(&subsample, 0, max_depth, &mut rng)
+        })
+        .collect();
+
+    let normalizer = average_path(sample);
+    points
+        .par_iter()
+        .map(|point| {
+            let mean_path = trees.iter().map(|tree| path_length(point, tree, 0)).sum::<f64>() / trees.len() as f64;
+            2f64.powf(-mean_path / normalizer)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn indexed(values: &[f64]) -> Vec<(usize, f64)> {
+        values.iter().copied().enumerate().collect()
+    }
+
+    #[test]
+    fn test_single_column_methods() {
+        let mut values: Vec<f64> = (0..50).map(|i| 10.0 + (i % 5) as f64).collect();
+        values.push(100.0);
+        let values = indexed(&values);
+
+        for method in [
+            OutlierMethod::ZScore { threshold: 3.0 },
+            OutlierMethod::ModifiedZScore { threshold: 3.5 },
+            OutlierMethod::Iqr { k: 1.5 },
+        ] {
+            let (outliers, bounds) = method.detect_column(&values);
+            assert_eq!(outliers.iter().map(|(row, _)| *row).collect::<Vec<_>>(), vec![50], "{}", method.name());
+            assert!(bounds.unwrap().1 < 100.0);
+        }
+
+        let (outliers, bounds) = OutlierMethod::Percentile { lower: 0.0, upper: 0.98 }.detect_column(&values);
+        assert_eq!(outliers.len(), 1);
+        assert!(bounds.unwrap().1 <= 14.0 + 1e-9);
+        assert_eq!(quantile(&[1.0, 2.0, 3.0, 100.0], 0.75), 27.25);
+    }
+
+    #[test]
+    fn test_isolation_across_columns() {
+        // Each value is ordinary on its own; the combination is not
+        let mut data: Vec<Vec<String>> = (0..200)
+            .map(|i| {
+                let x = (i % 20) as f64;
+                vec![x.to_string(), (x * 2.0 + (i % 3) as f64).to_string()]
+            })
+            .collect();
+        data.push(vec!["1".to_string(), "38".to_string()]);
+
+        let method = OutlierMethod::Isolation { threshold: 0.68 };
+        let outliers = find_outliers(&data, &[0, 1], &method, &LocaleFormat::default());
+        let rows: BTreeSet<usize> = outliers.iter().map(|o| o.row_index).collect();
+        assert_eq!(rows.into_iter().collect::<Vec<_>>(), vec![200]);
+        assert_eq!(outliers.len(), 2);
+    }
+}
@@ -0,0 +1,510 @@
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::csv_engine::locale::LocaleFormat;
+
+/// Characters of a value kept in its shape pattern
+const MAX_PATTERN_LENGTH: usize = 40;
+/// Quantiles reported for numeric columns
+const QUANTILES: [f64; 7] = [0.01, 0.05, 0.25, 0.5, 0.75, 0.95, 0.99];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileOptions {
+    /// Most frequent values and patterns reported per column
+    pub top_k: usize,
+    /// Histogram bins for numbers and string lengths
+    pub bins: usize,
+    /// Distinct values and patterns counted per column before counts
+    /// become estimates
+    pub max_tracked_values: usize,
+    /// Centroids kept per numeric summary; distributions with fewer
+    /// distinct values are exact
+    pub max_centroids: usize,
+}
+
+impl Default for ProfileOptions {
+    fn default() -> Self {
+        Self {
+            top_k: 10,
+            bins: 20,
+            max_tracked_values: 1000,
+            max_centroids: 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quantile {
+    pub quantile: f64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumericProfile {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub quantiles: Vec<Quantile>,
+    pub histogram: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateProfile {
+    pub count: u64,
+    pub min: NaiveDate,
+    pub max: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LengthProfile {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub histogram: Vec<HistogramBin>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnProfile {
+    pub column_index: usize,
+    pub column_name: String,
+    pub count: u64,
+    pub empty_count: u64,
+    pub numeric: Option<NumericProfile>,
+    pub dates: Option<DateProfile>,
+    /// Lengths of non-empty values, in characters
+    pub lengths: Option<LengthProfile>,
+    pub top_values: Vec<ValueCount>,
+    /// Shapes such as `AAA-9999`: letters become `A`/`a`, digits `9`
+    pub patterns: Vec<ValueCount>,
+    /// Whether the column had more distinct values or patterns than are
+    /// tracked, making the top counts lower bounds
+    pub approximate: bool,
+}
+
+/// Shape of a value: upper and lower case letters become `A` and `a`,
+/// digits `9`, anything else is kept
+pub fn shape_pattern(value: &str) -> String {
+    let mut pattern: String = value
+        .chars()
+        .take(MAX_PATTERN_LENGTH)
+        .map(|c| match c {
+            c if c.is_ascii_digit() => '9',
+            c if c.is_uppercase() => 'A',
+            c if c.is_lowercase() => 'a',
+            c => c,
+        })
+        .collect();
+    if value.chars().nth(MAX_PATTERN_LENGTH).is_some() {
+        pattern.push('…');
+    }
+    pattern
+}
+
+/// Frequent items in bounded memory (Misra-Gries). Counts are exact until
+/// more distinct items than `capacity` are seen, and lower bounds after.
+struct FrequentItems {
+    capacity: usize,
+    counts: HashMap<String, u64>,
+    approximate: bool,
+}
+
+impl FrequentItems {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counts: HashMap::new(),
+            approximate: false,
+        }
+    }
+
+    fn add(&mut self, item: &str) {
+        if let Some(count) = self.counts.get_mut(item) {
+            *count += 1;
+        } else if self.counts.len() < self.capacity {
+            self.counts.insert(item.to_string(), 1);
+        } else {
+            // The new item and one occurrence of every tracked item cancel out
+            self.approximate = true;
+            self.counts.retain(|_, count| {
+                *count -= 1;
+                *count > 0
+            });
+        }
+    }
+
+    fn top(&self, k: usize) -> Vec<ValueCount> {
+        let mut top: Vec<ValueCount> = self
+            .counts
+            .iter()
+            .map(|(value, &count)| ValueCount { value: value.clone(), count })
+            .collect();
+        top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        top.truncate(k);
+        top
+    }
+}
+
+/// Distribution summary in bounded memory: values are kept as weighted
+/// centroids, and the two closest are merged when there are too many
+/// (Ben-Haim and Tom-Tov). Exact while there are few distinct values.
+struct StreamingHistogram {
+    max_centroids: usize,
+    centroids: Vec<(f64, u64)>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl StreamingHistogram {
+    fn new(max_centroids: usize) -> Self {
+        Self {
+            max_centroids: max_centroids.max(2),
+            centroids: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        match self.centroids.binary_search_by(|(v, _)| v.total_cmp(&value)) {
+            Ok(index) => self.centroids[index].1 += 1,
+            Err(index) => {
+                self.centroids.insert(index, (value, 1));
+                if self.centroids.len() > self.max_centroids {
+                    self.merge_closest();
+                }
+            }
+        }
+    }
+
+    fn merge_closest(&mut self) {
+        let Some(index) = (0..self.centroids.len() - 1).min_by(|&a, &b| {
+            let gap = |i: usize| self.centroids[i + 1].0 - self.centroids[i].0;
+            gap(a).total_cmp(&gap(b))
+        }) else {
+            return;
+        };
+        let (left, left_count) = self.centroids[index];
+        let (right, right_count) = self.centroids.remove(index + 1);
+        let count = left_count + right_count;
+        self.centroids[index] = ((left * left_count as f64 + right * right_count as f64) / count as f64, count);
+    }
+
+    /// Value below which a share `q` of the values fall, interpolated
+    /// between centroids
+    fn quantile(&self, q: f64) -> f64 {
+        let target = q * self.count as f64;
+        let mut cumulative = 0.0;
+        let mut previous = (self.min, 0.0);
+        for &(value, count) in &self.centroids {
+            // Treat each centroid's values as centred on it
+            let middle = cumulative + count as f64 / 2.0;
+            if target <= middle {
+                let span = middle - previous.1;
+                let t = if span > 0.0 { (target - previous.1) / span } else { 1.0 };
+                return (previous.0 + t * (value - previous.0)).clamp(self.min, self.max);
+            }
+            cumulative += count as f64;
+            previous = (value, middle);
+        }
+        self.max
+    }
+
+    /// Equal-width bins over the value range. A centroid falls wholly in
+    /// the bin holding its mean.
+    fn bins(&self, bins: usize, integer_width: bool) -> Vec<HistogramBin> {
+        if self.count == 0 {
+            return Vec::new();
+        }
+        let range = self.max - self.min;
+        let mut bins = bins.max(1);
+        if integer_width {
+            bins = bins.min(range as usize + 1);
+        }
+        let width = if range > 0.0 { range / bins as f64 } else { 1.0 };
+
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|i| HistogramBin {
+                lower: self.min + width * i as f64,
+                upper: if i + 1 == bins { self.max.max(self.min + width) } else { self.min + width * (i + 1) as f64 },
+                count: 0,
+            })
+            .collect();
+        for &(value, count) in &self.centroids {
+            let index = (((value - self.min) / width) as usize).min(bins - 1);
+            histogram[index].count += count;
+        }
+        histogram
+    }
+}
+
+/// Running profile of one column
+pub struct ColumnProfiler {
+    options: ProfileOptions,
+    count: u64,
+    empty_count: u64,
+    numbers: StreamingHistogram,
+    sum: f64,
+    sum_of_squares: f64,
+    dates: Option<(NaiveDate, NaiveDate)>,
+    date_count: u64,
+    lengths: StreamingHistogram,
+    values: FrequentItems,
+    patterns: FrequentItems,
+}
+
+impl ColumnProfiler {
+    pub fn new(options: &ProfileOptions) -> Self {
+        Self {
+            options: options.clone(),
+            count: 0,
+            empty_count: 0,
+            numbers: StreamingHistogram::new(options.max_centroids),
+            sum: 0.0,
+            sum_of_squares: 0.0,
+            dates: None,
+            date_count: 0,
+            lengths: StreamingHistogram::new(options.max_centroids),
+            values: FrequentItems::new(options.max_tracked_values),
+            patterns: FrequentItems::new(options.max_tracked_values),
+        }
+    }
+
+    pub fn observe(&mut self, value: &str, locale: &LocaleFormat) {
+        self.count += 1;
+        let value = value.trim();
+        if value.is_empty() {
+            self.empty_count += 1;
+            return;
+        }
+
+        if let Some(number) = locale.parse_number(value).filter(|n| n.is_finite()) {
+            self.numbers.add(number);
+            self.sum += number;
+            self.sum_of_squares += number * number;
+        } else if let Some(date) = locale.parse_date(value) {
+            self.date_count += 1;
+            self.dates = Some(match self.dates {
+                Some((min, max)) => (min.min(date), max.max(date)),
+                None => (date, date),
+            });
+        }
+
+        self.lengths.add(value.chars().count() as f64);
+        self.values.add(value);
+        self.patterns.add(&shape_pattern(value));
+    }
+
+    pub fn finish(self, column_index: usize, column_name: &str) -> ColumnProfile {
+        let numeric = (self.numbers.count > 0).then(|| {
+            let n = self.numbers.count as f64;
+            let mean = self.sum / n;
+            NumericProfile {
+                count: self.numbers.count,
+                min: self.numbers.min,
+                max: self.numbers.max,
+                mean,
+                std_dev: (self.sum_of_squares / n - mean * mean).max(0.0).sqrt(),
+                quantiles: QUANTILES
+                    .iter()
+                    .map(|&quantile| Quantile { quantile, value: self.numbers.quantile(quantile) })
+                    .collect(),
+                histogram: self.numbers.bins(self.options.bins, false),
+            }
+        });
+
+        let lengths = (self.lengths.count > 0).then(|| LengthProfile {
+            min: self.lengths.min as usize,
+            max: self.lengths.max as usize,
+            mean: self.lengths.centroids.iter().map(|(v, c)| v * *c as f64).sum::<f64>() / self.lengths.count as f64,
+            histogram: self.lengths.bins(self.options.bins, true),
+        });
+
+        ColumnProfile {
+            column_index,
+            column_name: column_name.to_string(),
+            count: self.count,
+            empty_count: self.empty_count,
+            numeric,
+            dates: self.dates.map(|(min, max)| DateProfile { count: self.date_count, min, max }),
+            lengths,
+            top_values: self.values.top(self.options.top_k),
+            patterns: self.patterns.top(self.options.top_k),
+            approximate: self.values.approximate || self.patterns.approximate,
+        }
+    }
+}
+
+/// Profiles every column in one pass over rows fed in chunks, so files
+/// can be profiled without being loaded
+pub struct DataProfiler {
+    headers: Vec<String>,
+    locale: LocaleFormat,
+    columns: Vec<ColumnProfiler>,
+    /// Set for headerless data, whose columns are added as rows need them
+    numbered: Option<ProfileOptions>,
+    rows_seen: usize,
+}
+
+impl DataProfiler {
+    pub fn new(headers: Vec<String>, options: &ProfileOptions) -> Self {
+        let columns = headers.iter().map(|_| ColumnProfiler::new(options)).collect();
+        Self {
+            headers,
+            locale: LocaleFormat::default(),
+            columns,
+            numbered: None,
+            rows_seen: 0,
+        }
+    }
+
+    /// Profiler for data without a header row. Columns are named
+    /// "Column 1", "Column 2", ... and there are as many as the widest row
+    /// has cells.
+    pub fn numbered(options: &ProfileOptions) -> Self {
+        Self {
+            numbered: Some(options.clone()),
+            ..Self::new(Vec::new(), options)
+        }
+    }
+
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Feed rows; columns are profiled in parallel. Missing cells count as empty.
+    pub fn observe_rows(&mut self, rows: &[Vec<String>]) {
+        if let Some(options) = &self.numbered {
+            let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+            while self.columns.len() < width {
+                // Earlier rows were too short to reach this column
+                let mut column = ColumnProfiler::new(options);
+                for _ in 0..self.rows_seen {
+                    column.observe("", &self.locale);
+                }
+                self.columns.push(column);
+                self.headers.push(format!("Column {}", self.columns.len()));
+            }
+        }
+        self.rows_seen += rows.len();
+
+        let locale = &self.locale;
+        self.columns.par_iter_mut().enumerate().for_each(|(col_idx, column)| {
+            for row in rows {
+                column.observe(row.get(col_idx).map(|v| v.as_str()).unwrap_or(""), locale);
+            }
+        });
+    }
+
+    pub fn finish(self) -> Vec<ColumnProfile> {
+        let headers = self.headers;
+        self.columns
+            .into_iter()
+            .enumerate()
+            .map(|(col_idx, column)| column.finish(col_idx, &headers[col_idx]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_columns() {
+        let headers = vec!["code".to_string(), "amount".to_string(), "day".to_string()];
+        let rows: Vec<Vec<String>> = (1..=100)
+            .map(|i| {
+                vec![
+                    format!("AB-{:04}", i % 3),
+                    i.to_string(),
+                    format!("2024-01-{:02}", i % 28 + 1),
+                ]
+            })
+            .collect();
+
+        let mut profiler = DataProfiler::new(headers, &ProfileOptions::default());
+        profiler.observe_rows(&rows[..50]);
+        profiler.observe_rows(&rows[50..]);
+        let profiles = profiler.finish();
+
+        let code = &profiles[0];
+        assert_eq!(code.patterns.len(), 1);
+        assert_eq!(code.patterns[0].value, "AA-9999");
+        assert_eq!(code.patterns[0].count, 100);
+        assert_eq!(code.top_values[0].value, "AB-0001");
+        assert_eq!(code.top_values[0].count, 34);
+        assert!(code.numeric.is_none());
+        assert!(!code.approximate);
+
+        let amount = profiles[1].numeric.as_ref().unwrap();
+        assert_eq!((amount.min, amount.max, amount.mean), (1.0, 100.0, 50.5));
+        let median = amount.quantiles.iter().find(|q| q.quantile == 0.5).unwrap().value;
+        assert!((median - 50.5).abs() <= 1.0);
+        assert_eq!(amount.histogram.len(), 20);
+        assert_eq!(amount.histogram.iter().map(|b| b.count).sum::<u64>(), 100);
+
+        let day = profiles[2].dates.as_ref().unwrap();
+        assert_eq!(day.min, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(day.max, NaiveDate::from_ymd_opt(2024, 1, 28).unwrap());
+        assert_eq!(profiles[2].lengths.as_ref().unwrap().max, 10);
+    }
+
+    #[test]
+    fn test_bounded_summaries() {
+        let mut histogram = StreamingHistogram::new(16);
+        for i in 0..10_000 {
+            histogram.add(i as f64);
+        }
+        assert_eq!(histogram.centroids.len(), 16);
+        assert!((histogram.quantile(0.5) - 5000.0).abs() < 500.0);
+
+        let mut items = FrequentItems::new(4);
+        for i in 0..1000 {
+            items.add(if i % 2 == 0 { "common" } else { "rare" });
+            items.add(&i.to_string());
+        }
+        assert!(items.approximate);
+        assert_eq!(items.top(1)[0].value, "common");
+    }
+
+    #[test]
+    fn test_numbered_columns_cover_the_widest_row() {
+        let rows = |data: &[&[&str]]| -> Vec<Vec<String>> {
+            data.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect()
+        };
+
+        let mut profiler = DataProfiler::numbered(&ProfileOptions::default());
+        profiler.observe_rows(&rows(&[&["1", "a"], &["2"]]));
+        profiler.observe_rows(&rows(&[&["3", "b", "x"]]));
+        let profiles = profiler.finish();
+
+        let names: Vec<&str> = profiles.iter().map(|p| p.column_name.as_str()).collect();
+        assert_eq!(names, vec!["Column 1", "Column 2", "Column 3"]);
+        assert!(profiles.iter().all(|p| p.count == 3));
+        assert_eq!(profiles[2].empty_count, 2);
+    }
+}
@@ -1,5 +1,15 @@
+use anyhow::{bail, Result};
+use chrono::{Local, NaiveDate};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+use crate::csv_engine::data_types::{ColumnTypeOverride, DataType};
+use crate::csv_engine::locale::LocaleFormat;
+use crate::csv_engine::outliers::OutlierMethod;
+use crate::csv_engine::report::{escape_html, ReportFormat};
+use crate::csv_engine::validation::{ValidationError, ValidationRule, ValidationRuleType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityReport {
@@ -9,6 +19,9 @@ pub struct QualityReport {
     pub column_reports: Vec<ColumnQualityReport>,
     pub duplicates: DuplicateReport,
     pub outliers: OutlierReport,
+    /// Set by `QualityAnalyzer::score`
+    #[serde(default)]
+    pub score: Option<QualityScore>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +35,9 @@ pub struct ColumnQualityReport {
     pub uniqueness: f64,
     pub data_type_consistency: f64,
     pub dominant_type: String,
+    /// Set by `QualityAnalyzer::score`
+    #[serde(default)]
+    pub score: Option<QualityScore>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,20 +64,166 @@ pub struct OutlierDetail {
     pub column_index: usize,
     pub column_name: String,
     pub value: String,
+    /// Score from the method that found the value; a z-score for `Z-Score`
     pub z_score: f64,
     pub method: String,
 }
 
+/// Share of good values per quality dimension, from 0 to 1. Dimensions
+/// that do not apply are `None` and left out of the score.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityDimensions {
+    pub completeness: f64,
+    /// Cells passing the validation rules; columns without rules have none
+    pub validity: Option<f64>,
+    /// Distinct values for columns with a unique rule, distinct rows for the file
+    pub uniqueness: Option<f64>,
+    /// Values of the column's dominant or declared type
+    pub consistency: f64,
+    /// Dates within `max_age_days`, for date columns
+    pub timeliness: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityWeights {
+    pub completeness: f64,
+    pub validity: f64,
+    pub uniqueness: f64,
+    pub consistency: f64,
+    pub timeliness: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            completeness: 0.3,
+            validity: 0.3,
+            uniqueness: 0.15,
+            consistency: 0.15,
+            timeliness: 0.1,
+        }
+    }
+}
+
+/// How quality is scored and what passes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityConfig {
+    #[serde(default)]
+    pub weights: QualityWeights,
+    /// Lowest file score that passes
+    #[serde(default = "default_min_score")]
+    pub min_score: f64,
+    /// A file with any column scoring below this fails
+    #[serde(default = "default_min_column_score")]
+    pub min_column_score: f64,
+    /// Dates older than this count against timeliness; `None` skips it
+    #[serde(default = "default_max_age_days")]
+    pub max_age_days: Option<i64>,
+    /// Day dates are judged against, default today
+    #[serde(default)]
+    pub reference_date: Option<NaiveDate>,
+    /// Methods outliers are found with; a value is reported by the first
+    /// method that finds it
+    #[serde(default = "default_outlier_methods")]
+    pub outlier_methods: Vec<OutlierMethod>,
+}
+
+fn default_min_score() -> f64 {
+    0.8
+}
+
+fn default_min_column_score() -> f64 {
+    0.6
+}
+
+fn default_max_age_days() -> Option<i64> {
+    Some(365)
+}
+
+pub fn default_outlier_methods() -> Vec<OutlierMethod> {
+    vec![OutlierMethod::ZScore { threshold: 3.0 }, OutlierMethod::Iqr { k: 1.5 }]
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            weights: QualityWeights::default(),
+            min_score: default_min_score(),
+            min_column_score: default_min_column_score(),
+            max_age_days: default_max_age_days(),
+            reference_date: None,
+            outlier_methods: default_outlier_methods(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityScore {
+    pub dimensions: QualityDimensions,
+    /// Weighted mean of the dimensions that apply
+    pub score: f64,
+    pub passed: bool,
+}
+
+impl QualityWeights {
+    fn score(&self, dimensions: &QualityDimensions) -> f64 {
+        let weighted = [
+            (self.completeness, Some(dimensions.completeness)),
+            (self.validity, dimensions.validity),
+            (self.uniqueness, dimensions.uniqueness),
+            (self.consistency, Some(dimensions.consistency)),
+            (self.timeliness, dimensions.timeliness),
+        ];
+        let (total, weight) = weighted
+            .iter()
+            .filter_map(|(weight, value)| value.map(|v| (v * weight, *weight)))
+            .fold((0.0, 0.0), |(total, weights), (v, w)| (total + v, weights + w));
+
+        if weight > 0.0 { total / weight } else { 1.0 }
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
 pub struct QualityAnalyzer;
 
 impl QualityAnalyzer {
     pub fn analyze(data: &[Vec<String>], headers: &[String]) -> QualityReport {
+        Self::analyze_with_types(data, headers, &[])
+    }
+
+    /// Like `analyze`, judging type consistency and outliers by the declared
+    /// type of each column where there is one
+    pub fn analyze_with_types(
+        data: &[Vec<String>],
+        headers: &[String],
+        declared: &[Option<ColumnTypeOverride>],
+    ) -> QualityReport {
+        Self::analyze_with_outlier_methods(data, headers, declared, &default_outlier_methods())
+    }
+
+    /// Like `analyze_with_types`, finding outliers with each of `methods`
+    pub fn analyze_with_outlier_methods(
+        data: &[Vec<String>],
+        headers: &[String],
+        declared: &[Option<ColumnTypeOverride>],
+        methods: &[OutlierMethod],
+    ) -> QualityReport {
         let total_rows = data.len();
         let total_columns = headers.len();
 
-        let column_reports = Self::analyze_columns(data, headers);
-        let duplicates = Self::detect_duplicates(data);
-        let outliers = Self::detect_outliers(data, headers);
+        // The three passes are independent, and each also splits its work
+        // across columns or rows on the rayon pool
+        let (column_reports, (duplicates, outliers)) = rayon::join(
+            || Self::analyze_columns(data, headers, declared),
+            || rayon::join(
+                || Self::detect_duplicates(data),
+                || Self::detect_outliers(data, headers, declared, methods),
+            ),
+        );
 
         let total_cells = total_rows * total_columns;
         let empty_cells: usize = column_reports.iter().map(|r| r.empty_count).sum();
@@ -78,16 +240,116 @@ impl QualityAnalyzer {
             column_reports,
             duplicates,
             outliers,
+            score: None,
         }
     }
 
-    fn analyze_columns(data: &[Vec<String>], headers: &[String]) -> Vec<ColumnQualityReport> {
-        let mut reports = Vec::new();
+    /// Score each column and the file by the weighted dimensions of
+    /// `config`. `validation` holds the rules the data was checked with and
+    /// the errors found; without it validity is left out. Rule errors say
+    /// nothing about any row, so they are not counted. Dates without a
+    /// declared format are read in `locale`.
+    pub fn score(
+        report: &mut QualityReport,
+        data: &[Vec<String>],
+        declared: &[Option<ColumnTypeOverride>],
+        validation: Option<(&[ValidationRule], &[ValidationError])>,
+        config: &QualityConfig,
+        locale: &LocaleFormat,
+    ) {
+        let today = config.reference_date.unwrap_or_else(|| Local::now().date_naive());
+        let total_rows = report.total_rows;
+        let share = |bad: usize| if total_rows > 0 { 1.0 - bad as f64 / total_rows as f64 } else { 1.0 };
+
+        // Rows failing any rule, by column
+        let mut failing: HashMap<usize, HashSet<usize>> = HashMap::new();
+        let mut ruled = HashSet::new();
+        let mut unique = HashSet::new();
+        if let Some((rules, errors)) = validation {
+            for rule in rules {
+                ruled.insert(rule.column_index);
+                if rule.rule_type == ValidationRuleType::Unique {
+                    unique.insert(rule.column_index);
+                }
+            }
+            for error in errors.iter().filter(|e| !e.rule_error) {
+                failing.entry(error.column_index).or_default().insert(error.row_index);
+            }
+        }
+
+        for column in &mut report.column_reports {
+            let col_idx = column.column_index;
+            let declared = declared.get(col_idx).and_then(|d| d.as_ref());
 
-        for (col_idx, header) in headers.iter().enumerate() {
+            let is_date = match declared {
+                Some(declared) => matches!(declared.data_type, DataType::Date | DataType::DateTime),
+                None => matches!(column.dominant_type.as_str(), "Date" | "DateTime"),
+            };
+            let timeliness = match config.max_age_days {
+                Some(max_age) if is_date => {
+                    let parser = declared.cloned().unwrap_or_else(|| ColumnTypeOverride::new(DataType::Date));
+                    let parse = |value: &str| match parser.date_format {
+                        Some(_) => parser.parse_date(value).map(|d| d.date()),
+                        None => locale.parse_date(value).or_else(|| parser.parse_date(value).map(|d| d.date())),
+                    };
+                    let (dated, fresh) = data
+                        .iter()
+                        .filter_map(|row| row.get(col_idx).and_then(|v| parse(v)))
+                        .fold((0usize, 0usize), |(dated, fresh), date| {
+                            let age = (today - date).num_days();
+                            (dated + 1, fresh + usize::from(age <= max_age))
+                        });
+                    (dated > 0).then(|| fresh as f64 / dated as f64)
+                }
+                _ => None,
+            };
+
+            let dimensions = QualityDimensions {
+                completeness: column.completeness,
+                validity: ruled
+                    .contains(&col_idx)
+                    .then(|| share(failing.get(&col_idx).map_or(0, |rows| rows.len()))),
+                uniqueness: unique.contains(&col_idx).then(|| {
+                    let non_empty = column.total_values - column.empty_count;
+                    if non_empty > 0 { column.unique_count as f64 / non_empty as f64 } else { 1.0 }
+                }),
+                consistency: column.data_type_consistency,
+                timeliness,
+            };
+            let score = config.weights.score(&dimensions);
+            column.score = Some(QualityScore {
+                dimensions,
+                score,
+                passed: score >= config.min_column_score,
+            });
+        }
+
+        let column_scores = || report.column_reports.iter().filter_map(|c| c.score.as_ref());
+        let dimensions = QualityDimensions {
+            completeness: report.completeness,
+            validity: validation.map(|(_, errors)| {
+                share(errors.iter().filter(|e| !e.rule_error).map(|e| e.row_index).collect::<HashSet<_>>().len())
+            }),
+            uniqueness: Some(share(report.duplicates.total_duplicates)),
+            consistency: mean(column_scores().map(|s| s.dimensions.consistency)).unwrap_or(1.0),
+            timeliness: mean(column_scores().filter_map(|s| s.dimensions.timeliness)),
+        };
+        let score = config.weights.score(&dimensions);
+        let passed = score >= config.min_score && column_scores().all(|s| s.passed);
+        report.score = Some(QualityScore { dimensions, score, passed });
+    }
+
+    fn analyze_columns(
+        data: &[Vec<String>],
+        headers: &[String],
+        declared: &[Option<ColumnTypeOverride>],
+    ) -> Vec<ColumnQualityReport> {
+        let type_detector = crate::csv_engine::data_types::DataTypeDetector::new();
+
+        headers.par_iter().enumerate().map(|(col_idx, header)| {
+            let declared = declared.get(col_idx).and_then(|d| d.as_ref());
             let mut empty_count = 0;
             let mut unique_values = HashSet::new();
-            let type_detector = crate::csv_engine::data_types::DataTypeDetector::new();
             let mut type_counts: HashMap<String, usize> = HashMap::new();
 
             for row in data {
@@ -96,7 +358,12 @@ impl QualityAnalyzer {
                         empty_count += 1;
                     } else {
                         unique_values.insert(value.clone());
-                        let detected_type = format!("{:?}", type_detector.detect_value_type(value));
+                        let detected_type = match declared {
+                            Some(declared) if declared.check(value, &type_detector).is_none() => {
+                                format!("{:?}", declared.data_type)
+                            }
+                            _ => format!("{:?}", type_detector.detect_value_type(value)),
+                        };
                         *type_counts.entry(detected_type).or_insert(0) += 1;
                     }
                 }
@@ -115,11 +382,20 @@ impl QualityAnalyzer {
                 0.0
             };
 
-            let (dominant_type, dominant_count) = type_counts
-                .iter()
-                .max_by_key(|(_, count)| *count)
-                .map(|(t, c)| (t.clone(), *c))
-                .unwrap_or(("Text".to_string(), 0));
+            // A declared type is dominant by definition; consistency is then
+            // the share of values that conform to it
+            let (dominant_type, dominant_count) = match declared {
+                Some(declared) => {
+                    let name = format!("{:?}", declared.data_type);
+                    let count = type_counts.get(&name).copied().unwrap_or(0);
+                    (name, count)
+                }
+                None => type_counts
+                    .iter()
+                    .max_by_key(|(_, count)| *count)
+                    .map(|(t, c)| (t.clone(), *c))
+                    .unwrap_or(("Text".to_string(), 0)),
+            };
 
             let non_empty_count = total_values - empty_count;
             let data_type_consistency = if non_empty_count > 0 {
@@ -128,7 +404,7 @@ impl QualityAnalyzer {
                 1.0
             };
 
-            reports.push(ColumnQualityReport {
+            ColumnQualityReport {
                 column_index: col_idx,
                 column_name: header.clone(),
                 total_values,
@@ -138,18 +414,16 @@ impl QualityAnalyzer {
                 uniqueness,
                 data_type_consistency,
                 dominant_type,
-            });
-        }
-
-        reports
+                score: None,
+            }
+        }).collect()
     }
 
     fn detect_duplicates(data: &[Vec<String>]) -> DuplicateReport {
-        let mut row_map: HashMap<String, Vec<usize>> = HashMap::new();
-
+        // Keyed on the cells themselves, so `a|b,c` and `a,b|c` stay apart
+        let mut row_map: HashMap<&[String], Vec<usize>> = HashMap::new();
         for (row_idx, row) in data.iter().enumerate() {
-            let row_key = row.join("|");
-            row_map.entry(row_key).or_insert_with(Vec::new).push(row_idx);
+            row_map.entry(row.as_slice()).or_default().push(row_idx);
         }
 
         let mut duplicate_rows: Vec<DuplicateRow> = row_map
@@ -171,104 +445,168 @@ impl QualityAnalyzer {
         }
     }
 
-    fn detect_outliers(data: &[Vec<String>], headers: &[String]) -> OutlierReport {
-        let mut outlier_details = Vec::new();
+    fn detect_outliers(
+        data: &[Vec<String>],
+        headers: &[String],
+        declared: &[Option<ColumnTypeOverride>],
+        methods: &[OutlierMethod],
+    ) -> OutlierReport {
+        let mut outlier_details: Vec<OutlierDetail> = headers
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(col_idx, header)| {
+                let declared = declared.get(col_idx).and_then(|d| d.as_ref());
+                Self::column_outliers(data, col_idx, header, declared, methods)
+            })
+            .collect();
 
-        for (col_idx, header) in headers.iter().enumerate() {
-            let mut numeric_values = Vec::new();
-            let mut row_value_pairs = Vec::new();
+        outlier_details.sort_by(|a, b| b.z_score.partial_cmp(&a.z_score).unwrap());
 
-            for (row_idx, row) in data.iter().enumerate() {
-                if let Some(value) = row.get(col_idx) {
-                    if let Ok(num) = value.parse::<f64>() {
-                        numeric_values.push(num);
-                        row_value_pairs.push((row_idx, value.clone(), num));
-                    }
-                }
-            }
+        OutlierReport {
+            total_outliers: outlier_details.len(),
+            outlier_details,
+        }
+    }
 
-            if numeric_values.len() < 3 {
-                continue; // Need at least 3 values for outlier detection
+    fn column_outliers(
+        data: &[Vec<String>],
+        col_idx: usize,
+        header: &str,
+        declared: Option<&ColumnTypeOverride>,
+        methods: &[OutlierMethod],
+    ) -> Vec<OutlierDetail> {
+        // Columns declared as anything but numbers, such as zip codes, have no outliers
+        let parse = |value: &str| match declared {
+            Some(declared) if matches!(declared.data_type, DataType::Integer | DataType::Float) => {
+                declared.parse_number(value)
             }
+            Some(_) => None,
+            None => value.parse::<f64>().ok(),
+        };
 
-            // Calculate mean and standard deviation
-            let mean = numeric_values.iter().sum::<f64>() / numeric_values.len() as f64;
-            let variance = numeric_values
-                .iter()
-                .map(|v| (v - mean).powi(2))
-                .sum::<f64>()
-                / numeric_values.len() as f64;
-            let std_dev = variance.sqrt();
-
-            if std_dev == 0.0 {
-                continue; // All values are the same
-            }
+        let values: Vec<(usize, f64)> = data
+            .iter()
+            .enumerate()
+            .filter_map(|(row_idx, row)| Some((row_idx, parse(row.get(col_idx)?)?)))
+            .collect();
 
-            // Detect outliers using Z-score (threshold: 3.0)
-            for (row_idx, value, num) in row_value_pairs {
-                let z_score = ((num - mean) / std_dev).abs();
-                if z_score > 3.0 {
+        // A value found by several methods is reported by the first
+        let mut seen = HashSet::new();
+        let mut outlier_details = Vec::new();
+        for method in methods {
+            let (outliers, _) = method.detect_column(&values);
+            for (row_idx, score) in outliers {
+                if seen.insert(row_idx) {
                     outlier_details.push(OutlierDetail {
                         row_index: row_idx,
                         column_index: col_idx,
-                        column_name: header.clone(),
-                        value,
-                        z_score,
-                        method: "Z-Score".to_string(),
+                        column_name: header.to_string(),
+                        value: data[row_idx][col_idx].clone(),
+                        z_score: score,
+                        method: method.name().to_string(),
                     });
                 }
             }
-
-            // IQR method as an alternative
-            let mut sorted_values = numeric_values.clone();
-            sorted_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-
-            let q1_idx = sorted_values.len() / 4;
-            let q3_idx = (sorted_values.len() * 3) / 4;
-
-            if q1_idx < sorted_values.len() && q3_idx < sorted_values.len() {
-                let q1 = sorted_values[q1_idx];
-                let q3 = sorted_values[q3_idx];
-                let iqr = q3 - q1;
-
-                if iqr > 0.0 {
-                    let lower_bound = q1 - 1.5 * iqr;
-                    let upper_bound = q3 + 1.5 * iqr;
-
-                    for (row_idx, row) in data.iter().enumerate() {
-                        if let Some(value) = row.get(col_idx) {
-                            if let Ok(num) = value.parse::<f64>() {
-                                if num < lower_bound || num > upper_bound {
-                                    // Only add if not already detected by Z-score
-                                    if !outlier_details.iter().any(|o|
-                                        o.row_index == row_idx && o.column_index == col_idx
-                                    ) {
-                                        outlier_details.push(OutlierDetail {
-                                            row_index: row_idx,
-                                            column_index: col_idx,
-                                            column_name: header.clone(),
-                                            value: value.clone(),
-                                            z_score: 0.0,
-                                            method: "IQR".to_string(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
         }
 
-        outlier_details.sort_by(|a, b| b.z_score.partial_cmp(&a.z_score).unwrap());
+        outlier_details
+    }
+}
 
-        OutlierReport {
-            total_outliers: outlier_details.len(),
-            outlier_details,
+impl QualityReport {
+    /// Write the report as JSON, or as a self-contained HTML page
+    pub fn write(&self, path: &Path, source: &str, format: ReportFormat) -> Result<()> {
+        let content = match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Html => self.to_html(source),
+            ReportFormat::Csv => bail!("Quality reports can be exported as JSON or HTML"),
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn to_html(&self, source: &str) -> String {
+        let percent = |value: f64| format!("{:.1}%", value * 100.0);
+        let optional = |value: Option<f64>| value.map(percent).unwrap_or_else(|| "&ndash;".to_string());
+        let verdict = |score: &QualityScore| match score.passed {
+            true => "<span class=\"pass\">pass</span>",
+            false => "<span class=\"fail\">fail</span>",
+        };
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Quality report - {}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>Quality report</h1>\n<p>{} &middot; generated {} &middot; {} rows, {} columns</p>\n",
+            escape_html(source),
+            QUALITY_STYLE,
+            escape_html(source),
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            self.total_rows,
+            self.total_columns
+        );
+
+        if let Some(score) = &self.score {
+            let d = &score.dimensions;
+            let _ = write!(
+                html,
+                "<p class=\"score\">Score {} {}</p>\n<table>\n<tr><th>Completeness</th><th>Validity</th><th>Uniqueness</th><th>Consistency</th><th>Timeliness</th></tr>\n<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>\n",
+                percent(score.score),
+                verdict(score),
+                percent(d.completeness),
+                optional(d.validity),
+                optional(d.uniqueness),
+                percent(d.consistency),
+                optional(d.timeliness)
+            );
+        }
+
+        html.push_str("<h2>Columns</h2>\n<table>\n<tr><th>Column</th><th>Type</th><th>Empty</th><th>Distinct</th><th>Completeness</th><th>Validity</th><th>Uniqueness</th><th>Consistency</th><th>Timeliness</th><th>Score</th></tr>\n");
+        for column in &self.column_reports {
+            let dimensions = column.score.as_ref().map(|s| s.dimensions.clone()).unwrap_or(QualityDimensions {
+                completeness: column.completeness,
+                validity: None,
+                uniqueness: None,
+                consistency: column.data_type_consistency,
+                timeliness: None,
+            });
+            let score = match &column.score {
+                Some(score) => format!("{} {}", percent(score.score), verdict(score)),
+                None => "&ndash;".to_string(),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&column.column_name),
+                escape_html(&column.dominant_type),
+                column.empty_count,
+                column.unique_count,
+                percent(dimensions.completeness),
+                optional(dimensions.validity),
+                optional(dimensions.uniqueness),
+                percent(dimensions.consistency),
+                optional(dimensions.timeliness),
+                score
+            );
         }
+        html.push_str("</table>\n");
+
+        let _ = write!(
+            html,
+            "<h2>Duplicates and outliers</h2>\n<p>{} duplicate rows in {} groups; {} outliers</p>\n</body>\n</html>\n",
+            self.duplicates.total_duplicates,
+            self.duplicates.duplicate_rows.len(),
+            self.outliers.total_outliers
+        );
+        html
     }
 }
 
+const QUALITY_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#f3f3f3}.score{font-size:1.4em;font-weight:bold}\
+.pass{color:#1a7f37;font-weight:bold}.fail{color:#c62828;font-weight:bold}";
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,7 +620,7 @@ mod tests {
         ];
         let headers = vec!["name".to_string(), "age".to_string()];
 
-        let reports = QualityAnalyzer::analyze_columns(&data, &headers);
+        let reports = QualityAnalyzer::analyze_columns(&data, &headers, &[]);
 
         assert_eq!(reports.len(), 2);
         assert_eq!(reports[0].empty_count, 1);
@@ -303,6 +641,22 @@ mod tests {
         assert_eq!(report.duplicate_rows.len(), 1);
     }
 
+    #[test]
+    fn test_declared_text_column_has_no_outliers() {
+        let mut data: Vec<Vec<String>> = (0..20).map(|_| vec!["00501".to_string()]).collect();
+        data.push(vec!["99950".to_string()]);
+        data.push(vec!["n/a".to_string()]);
+        let headers = vec!["zip".to_string()];
+        let declared = vec![Some(ColumnTypeOverride::new(DataType::Text))];
+
+        assert!(QualityAnalyzer::analyze(&data, &headers).outliers.total_outliers > 0);
+
+        let report = QualityAnalyzer::analyze_with_types(&data, &headers, &declared);
+        assert_eq!(report.outliers.total_outliers, 0);
+        assert_eq!(report.column_reports[0].dominant_type, "Text");
+        assert_eq!(report.column_reports[0].data_type_consistency, 1.0);
+    }
+
     #[test]
     fn test_detect_outliers() {
         let data = vec![
@@ -313,7 +667,83 @@ mod tests {
         ];
         let headers = vec!["value".to_string()];
 
-        let report = QualityAnalyzer::detect_outliers(&data, &headers);
+        let report = QualityAnalyzer::detect_outliers(&data, &headers, &[], &default_outlier_methods());
         assert!(report.total_outliers > 0);
     }
+
+    #[test]
+    fn test_score_with_rules_and_dates() {
+        let data = vec![
+            vec!["1".to_string(), "2024-06-01".to_string()],
+            vec!["2".to_string(), "2020-01-01".to_string()],
+            vec!["2".to_string(), "".to_string()],
+            vec!["4".to_string(), "2024-05-01".to_string()],
+        ];
+        let headers = vec!["id".to_string(), "updated".to_string()];
+        let rules = vec![ValidationRule {
+            rule_type: ValidationRuleType::Unique,
+            column_index: 0,
+            column_name: "id".to_string(),
+            parameters: HashMap::new(),
+            error_message: None,
+        }];
+        let errors = crate::csv_engine::validation::Validator::new(rules.clone()).validate(&data, &headers);
+        let config = QualityConfig {
+            reference_date: NaiveDate::from_ymd_opt(2024, 7, 1),
+            ..QualityConfig::default()
+        };
+
+        let mut report = QualityAnalyzer::analyze(&data, &headers);
+        QualityAnalyzer::score(&mut report, &data, &[], Some((&rules, &errors)), &config, &LocaleFormat::default());
+
+        let id = report.column_reports[0].score.as_ref().unwrap();
+        assert_eq!(id.dimensions.validity, Some(0.75));
+        assert_eq!(id.dimensions.uniqueness, Some(0.75));
+        assert_eq!(id.dimensions.timeliness, None);
+
+        let updated = report.column_reports[1].score.as_ref().unwrap();
+        assert_eq!(updated.dimensions.validity, None);
+        assert!((updated.dimensions.timeliness.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+
+        let file = report.score.as_ref().unwrap();
+        assert_eq!(file.dimensions.validity, Some(0.75));
+        assert!(file.score < 1.0);
+        assert_eq!(file.passed, file.score >= 0.8);
+        assert!(report.to_html("data.csv").contains("Score"));
+    }
+
+    #[test]
+    fn test_score_skips_rule_errors_and_reads_dates_in_the_locale() {
+        let data = vec![
+            vec!["a".to_string(), "01.06.2024".to_string()],
+            vec!["b".to_string(), "01.01.2020".to_string()],
+        ];
+        let headers = vec!["code".to_string(), "updated".to_string()];
+        let rules = vec![ValidationRule {
+            rule_type: ValidationRuleType::Custom,
+            column_index: 0,
+            column_name: "code".to_string(),
+            parameters: [("expression".to_string(), "value >".to_string())].into_iter().collect(),
+            error_message: None,
+        }];
+        let errors = crate::csv_engine::validation::Validator::new(rules.clone()).validate(&data, &headers);
+        assert!(errors.iter().all(|e| e.rule_error));
+        let config = QualityConfig {
+            reference_date: NaiveDate::from_ymd_opt(2024, 7, 1),
+            ..QualityConfig::default()
+        };
+        let german = LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+        };
+
+        let mut report = QualityAnalyzer::analyze(&data, &headers);
+        QualityAnalyzer::score(&mut report, &data, &[], Some((&rules, &errors)), &config, &german);
+
+        assert_eq!(report.column_reports[0].score.as_ref().unwrap().dimensions.validity, Some(1.0));
+        assert_eq!(report.score.as_ref().unwrap().dimensions.validity, Some(1.0));
+        let updated = report.column_reports[1].score.as_ref().unwrap();
+        assert_eq!(updated.dimensions.timeliness, Some(0.5));
+    }
 }
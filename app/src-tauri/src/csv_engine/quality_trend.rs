@@ -0,0 +1,320 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::csv_engine::quality::QualityReport;
+
+/// Snapshots kept per file; the oldest are dropped first
+const MAX_SNAPSHOTS: usize = 200;
+
+/// Compact record of one `QualityReport`, kept in the file's metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualitySnapshot {
+    pub taken_at: String,
+    /// Version of the data the report was made from
+    pub file_modified: String,
+    pub file_size: u64,
+    pub content_hash: String,
+    pub total_rows: usize,
+    #[serde(default)]
+    pub score: Option<f64>,
+    pub duplicates: usize,
+    pub columns: Vec<ColumnSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSnapshot {
+    pub column_name: String,
+    pub completeness: f64,
+    pub distinct_count: usize,
+    pub type_consistency: f64,
+    pub outlier_count: usize,
+}
+
+/// Version of the data a snapshot was taken from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileVersion {
+    pub modified: String,
+    pub size: u64,
+    pub content_hash: String,
+}
+
+impl FileVersion {
+    /// Modification time of the file at `path`, and the size and a hash of
+    /// the headers and rows as analyzed, which may hold unsaved edits
+    pub fn of_data(path: &Path, headers: &[String], rows: &[Vec<String>]) -> Self {
+        let modified: DateTime<Local> = std::fs::metadata(path)
+            .and_then(|info| info.modified())
+            .map(Into::into)
+            .unwrap_or_else(|_| Local::now());
+
+        // 64-bit FNV-1a, stable across builds unlike `DefaultHasher`.
+        // Fields and records are separated so moving text across a cell
+        // boundary changes the hash.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut size = 0u64;
+        for record in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+            for field in record {
+                for byte in field.bytes().chain([0x1f]) {
+                    hash ^= u64::from(byte);
+                    hash = hash.wrapping_mul(0x0100_0000_01b3);
+                }
+                size += field.len() as u64;
+            }
+            hash ^= 0x1e;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        Self {
+            modified: modified.to_rfc3339(),
+            size,
+            content_hash: format!("{:016x}", hash),
+        }
+    }
+}
+
+impl QualitySnapshot {
+    pub fn from_report(report: &QualityReport, version: FileVersion) -> Self {
+        let columns = report
+            .column_reports
+            .iter()
+            .map(|column| ColumnSnapshot {
+                column_name: column.column_name.clone(),
+                completeness: column.completeness,
+                distinct_count: column.unique_count,
+                type_consistency: column.data_type_consistency,
+                outlier_count: report
+                    .outliers
+                    .outlier_details
+                    .iter()
+                    .filter(|o| o.column_index == column.column_index)
+                    .count(),
+            })
+            .collect();
+
+        Self {
+            taken_at: Local::now().to_rfc3339(),
+            file_modified: version.modified,
+            file_size: version.size,
+            content_hash: version.content_hash,
+            total_rows: report.total_rows,
+            score: report.score.as_ref().map(|s| s.score),
+            duplicates: report.duplicates.total_duplicates,
+            columns,
+        }
+    }
+}
+
+/// When a change between consecutive snapshots is flagged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrendThresholds {
+    /// Absolute change in completeness or type consistency, e.g. 0.05 for 5 points
+    pub ratio_change: f64,
+    /// Relative change in distinct or outlier count, e.g. 0.2 for 20%
+    pub count_change: f64,
+    /// Counts below this on both sides are never flagged
+    pub min_count: usize,
+}
+
+impl Default for TrendThresholds {
+    fn default() -> Self {
+        Self {
+            ratio_change: 0.05,
+            count_change: 0.2,
+            min_count: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub taken_at: String,
+    pub file_modified: String,
+    pub completeness: f64,
+    pub distinct_count: usize,
+    pub type_consistency: f64,
+    pub outlier_count: usize,
+}
+
+/// A metric that moved more than the thresholds allow since the
+/// previous snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendChange {
+    /// Index into the column's points
+    pub point_index: usize,
+    pub metric: String,
+    pub previous: f64,
+    pub current: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnTrend {
+    pub column_name: String,
+    pub points: Vec<TrendPoint>,
+    pub changes: Vec<TrendChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityTrend {
+    pub snapshot_count: usize,
+    pub columns: Vec<ColumnTrend>,
+}
+
+/// Record `snapshot` in `history`. It replaces the latest snapshot when
+/// that was taken from the same data, so re-analyzing unchanged data does
+/// not add a point, while returning to an earlier version does.
+pub fn record_snapshot(history: &mut Vec<QualitySnapshot>, snapshot: QualitySnapshot) {
+    if history
+        .last()
+        .is_some_and(|s| s.content_hash == snapshot.content_hash && s.file_size == snapshot.file_size)
+    {
+        history.pop();
+    }
+    history.push(snapshot);
+    if history.len() > MAX_SNAPSHOTS {
+        history.drain(..history.len() - MAX_SNAPSHOTS);
+    }
+}
+
+/// Time series per column, oldest first, in the column order of the
+/// latest snapshot followed by columns that have since disappeared
+pub fn quality_trend(history: &[QualitySnapshot], thresholds: &TrendThresholds) -> QualityTrend {
+    let mut names: Vec<&str> = Vec::new();
+    for snapshot in history.iter().rev() {
+        for column in &snapshot.columns {
+            if !names.contains(&column.column_name.as_str()) {
+                names.push(&column.column_name);
+            }
+        }
+    }
+
+    let columns = names
+        .into_iter()
+        .map(|name| {
+            let points: Vec<TrendPoint> = history
+                .iter()
+                .filter_map(|snapshot| {
+                    let column = snapshot.columns.iter().find(|c| c.column_name == name)?;
+                    Some(TrendPoint {
+                        taken_at: snapshot.taken_at.clone(),
+                        file_modified: snapshot.file_modified.clone(),
+                        completeness: column.completeness,
+                        distinct_count: column.distinct_count,
+                        type_consistency: column.type_consistency,
+                        outlier_count: column.outlier_count,
+                    })
+                })
+                .collect();
+            let changes = significant_changes(&points, thresholds);
+
+            ColumnTrend {
+                column_name: name.to_string(),
+                points,
+                changes,
+            }
+        })
+        .collect();
+
+    QualityTrend {
+        snapshot_count: history.len(),
+        columns,
+    }
+}
+
+fn significant_changes(points: &[TrendPoint], thresholds: &TrendThresholds) -> Vec<TrendChange> {
+    let ratio_changed = |a: f64, b: f64| (b - a).abs() >= thresholds.ratio_change;
+    let count_changed = |a: usize, b: usize| {
+        a.max(b) >= thresholds.min_count
+            && (b as f64 - a as f64).abs() / (a.max(1) as f64) >= thresholds.count_change
+    };
+
+    let mut changes = Vec::new();
+    for (index, pair) in points.windows(2).enumerate() {
+        let (previous, current) = (&pair[0], &pair[1]);
+        let metrics = [
+            ("completeness", ratio_changed(previous.completeness, current.completeness), previous.completeness, current.completeness),
+            ("type_consistency", ratio_changed(previous.type_consistency, current.type_consistency), previous.type_consistency, current.type_consistency),
+            ("distinct_count", count_changed(previous.distinct_count, current.distinct_count), previous.distinct_count as f64, current.distinct_count as f64),
+            ("outlier_count", count_changed(previous.outlier_count, current.outlier_count), previous.outlier_count as f64, current.outlier_count as f64),
+        ];
+
+        for (metric, changed, previous, current) in metrics {
+            if changed {
+                changes.push(TrendChange {
+                    point_index: index + 1,
+                    metric: metric.to_string(),
+                    previous,
+                    current,
+                });
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(hash: &str, completeness: f64, distinct_count: usize) -> QualitySnapshot {
+        QualitySnapshot {
+            taken_at: String::new(),
+            file_modified: String::new(),
+            file_size: 100,
+            content_hash: hash.to_string(),
+            total_rows: 100,
+            score: None,
+            duplicates: 0,
+            columns: vec![ColumnSnapshot {
+                column_name: "id".to_string(),
+                completeness,
+                distinct_count,
+                type_consistency: 1.0,
+                outlier_count: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_quality_trend_flags_changes() {
+        let mut history = Vec::new();
+        record_snapshot(&mut history, snapshot("a", 1.0, 100));
+        record_snapshot(&mut history, snapshot("b", 0.99, 105));
+        // Re-analyzing the same version replaces its snapshot
+        record_snapshot(&mut history, snapshot("b", 0.98, 104));
+        record_snapshot(&mut history, snapshot("c", 0.80, 60));
+        assert_eq!(history.len(), 3);
+
+        let trend = quality_trend(&history, &TrendThresholds::default());
+        let column = &trend.columns[0];
+        assert_eq!(column.points.len(), 3);
+        assert_eq!(column.points[1].completeness, 0.98);
+
+        let flagged: Vec<(usize, &str)> = column.changes.iter().map(|c| (c.point_index, c.metric.as_str())).collect();
+        assert_eq!(flagged, vec![(2, "completeness"), (2, "distinct_count")]);
+    }
+
+    #[test]
+    fn test_returning_to_a_version_adds_a_point() {
+        let mut history = Vec::new();
+        record_snapshot(&mut history, snapshot("a", 1.0, 100));
+        record_snapshot(&mut history, snapshot("b", 0.9, 90));
+        record_snapshot(&mut history, snapshot("a", 1.0, 100));
+        let hashes: Vec<&str> = history.iter().map(|s| s.content_hash.as_str()).collect();
+        assert_eq!(hashes, vec!["a", "b", "a"]);
+    }
+
+    #[test]
+    fn test_version_follows_the_analyzed_data() {
+        let path = Path::new("missing.csv");
+        let headers = vec!["id".to_string(), "name".to_string()];
+        let rows = vec![vec!["1".to_string(), "ab".to_string()]];
+        let version = FileVersion::of_data(path, &headers, &rows);
+        let again = FileVersion::of_data(path, &headers, &rows);
+        assert_eq!((version.size, &version.content_hash), (again.size, &again.content_hash));
+
+        // The same text split across cells differently is another version
+        let moved = vec![vec!["1a".to_string(), "b".to_string()]];
+        assert_ne!(version.content_hash, FileVersion::of_data(path, &headers, &moved).content_hash);
+    }
+}
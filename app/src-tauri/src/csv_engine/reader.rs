@@ -1,230 +1,835 @@
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::Read;
 use std::path::Path;
-use csv;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use encoding_rs::{Encoding, UTF_8};
-use chardet;
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use crate::metadata::CsvMetadata;
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::encoding::{self, EncodingDetection, EncodingSource};
+use crate::csv_engine::compression::{self, Compression};
+use crate::csv_engine::sniffer::{self, SniffResult, Sniffer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CsvData {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<String>>,
     pub metadata: CsvMetadata,
+    /// How the delimiter and header row were detected, for display only
+    #[serde(default)]
+    pub sniff: Option<SniffResult>,
+    /// Rows repaired while reading in lenient mode
+    #[serde(default)]
+    pub malformed_rows: Vec<MalformedRow>,
+    /// How the encoding was chosen and how many bytes failed to decode
+    #[serde(default)]
+    pub encoding_detection: Option<EncodingDetection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MalformedIssue {
+    TooFewFields,
+    TooManyFields,
+    UnterminatedQuote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MalformedRow {
+    /// Index into `CsvData.rows`, or `None` when the header line is affected
+    pub row_index: Option<usize>,
+    /// 1-based line on which the record starts
+    pub line_number: u64,
+    /// Offset of the record within the decoded text
+    pub byte_offset: u64,
+    pub raw_text: String,
+    pub issue: MalformedIssue,
+    pub expected_fields: usize,
+    pub actual_fields: usize,
+}
+
+/// Texts at least this long are split at record boundaries and the
+/// pieces parsed on the rayon pool
+const PARALLEL_PARSE_THRESHOLD: usize = 4 * 1024 * 1024;
+
+/// Smallest piece worth handing to another thread
+const MIN_PARSE_CHUNK: usize = 1024 * 1024;
+
+/// Records parsed between checks of the cancel flag
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
+/// Returned when parsing stops because the cancel flag was set
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parsing was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Headers, rows and the repairs made while parsing them
+type ParsedText = (Vec<String>, Vec<Vec<String>>, Vec<MalformedRow>);
+
+struct PositionedRecord {
+    fields: Vec<String>,
+    byte_offset: usize,
+    line_number: u64,
+    raw_text: String,
+    unterminated_quote: bool,
 }
 
 pub struct CsvReader {
-    delimiter: u8,
+    delimiter: String,
     has_headers: bool,
+    detect_headers: bool,
+    extra_delimiters: Vec<String>,
     encoding: &'static Encoding,
+    encoding_override: bool,
+    encoding_detection: Option<EncodingDetection>,
+    dialect: CsvDialect,
+    lenient: bool,
+    sniff: Option<SniffResult>,
+    compression: Option<Compression>,
+    archive_member: Option<String>,
+    parallel_threshold: usize,
+    cancelled: Option<Arc<AtomicBool>>,
 }
 
 impl CsvReader {
     pub fn new() -> Self {
         Self {
-            delimiter: b',',
+            delimiter: ",".to_string(),
             has_headers: true,
+            detect_headers: true,
+            extra_delimiters: Vec::new(),
             encoding: UTF_8,
+            encoding_override: false,
+            encoding_detection: None,
+            dialect: CsvDialect::default(),
+            lenient: false,
+            sniff: None,
+            compression: None,
+            archive_member: None,
+            parallel_threshold: PARALLEL_PARSE_THRESHOLD,
+            cancelled: None,
         }
     }
 
     pub fn with_delimiter(mut self, delimiter: u8) -> Self {
-        self.delimiter = delimiter;
+        self.delimiter = (delimiter as char).to_string();
         self
     }
 
+    /// Disables header detection in favor of the given setting
     pub fn with_headers(mut self, has_headers: bool) -> Self {
         self.has_headers = has_headers;
+        self.detect_headers = false;
         self
     }
 
+    /// Additional delimiters to consider during detection, e.g. `^` or `||`
+    pub fn with_delimiter_candidates(mut self, delimiters: Vec<String>) -> Self {
+        self.extra_delimiters = delimiters;
+        self
+    }
+
+    /// Decode with this encoding instead of detecting it
     pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
         self.encoding = encoding;
+        self.encoding_override = true;
         self
     }
 
-    pub fn detect_encoding(&mut self, path: &Path) -> Result<&'static Encoding> {
-        let mut file = File::open(path)?;
-        let mut buffer = vec![0; 8192];
-        let bytes_read = file.read(&mut buffer)?;
-        buffer.truncate(bytes_read);
-
-        let result = chardet::detect(&buffer);
-        let encoding_name = result.0.as_str();
-
-        self.encoding = match encoding_name.to_lowercase().as_str() {
-            "utf-8" | "ascii" => UTF_8,
-            "shift_jis" | "shift-jis" | "sjis" => encoding_rs::SHIFT_JIS,
-            "euc-jp" | "eucjp" => encoding_rs::EUC_JP,
-            "gb18030" | "gb2312" | "gbk" => encoding_rs::GB18030,
-            _ => UTF_8,
-        };
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
 
-        Ok(self.encoding)
+    /// Stop parsing with a `Cancelled` error once `cancelled` is set
+    pub fn with_cancel_flag(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// Skip detection of the file's compression
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
     }
 
-    pub fn detect_delimiter(&mut self, path: &Path) -> Result<u8> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        let mut buffer = vec![0; 1024];
-        let bytes_read = reader.read(&mut buffer)?;
-        buffer.truncate(bytes_read);
+    /// Member to open when the file is a zip archive
+    pub fn with_archive_member(mut self, member: String) -> Self {
+        self.archive_member = Some(member);
+        self
+    }
 
-        let common_delimiters = vec![b',', b'\t', b';', b'|'];
-        let mut delimiter_counts = vec![0; common_delimiters.len()];
+    /// Decoded size from which the text is parsed in parallel chunks;
+    /// `usize::MAX` always parses on the calling thread
+    pub fn with_parallel_threshold(mut self, bytes: usize) -> Self {
+        self.parallel_threshold = bytes;
+        self
+    }
 
-        for (i, &delim) in common_delimiters.iter().enumerate() {
-            delimiter_counts[i] = buffer.iter().filter(|&&b| b == delim).count();
-        }
+    /// Load ragged or broken rows instead of failing; see `CsvData.malformed_rows`
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn detect_encoding(&mut self, path: &Path) -> Result<&'static Encoding> {
+        let mut buffer = Vec::new();
+        self.open_source(path)?.take(8192).read_to_end(&mut buffer)?;
 
-        let max_index = delimiter_counts
-            .iter()
-            .position(|&count| count == *delimiter_counts.iter().max().unwrap())
-            .unwrap_or(0);
+        let (detected, detection) = encoding::detect(&buffer);
+        self.encoding = detected;
+        self.encoding_detection = Some(detection);
 
-        self.delimiter = common_delimiters[max_index];
-        Ok(self.delimiter)
+        Ok(self.encoding)
     }
 
-    pub fn read_file(&mut self, path: &Path) -> Result<CsvData> {
-        // Always detect encoding and delimiter from the file
-        self.detect_encoding(path)?;
-        self.detect_delimiter(path)?;
+    /// Result of the last `detect_encoding` call
+    pub fn encoding_detection(&self) -> Option<&EncodingDetection> {
+        self.encoding_detection.as_ref()
+    }
 
-        let file = File::open(path).context("Failed to open CSV file")?;
-        let mut reader = BufReader::new(file);
+    /// Sniff delimiter and header row from the first 64KB of the file
+    pub fn detect_format(&mut self, path: &Path) -> Result<SniffResult> {
+        const SAMPLE_SIZE: usize = 64 * 1024;
         let mut buffer = Vec::new();
-        reader.read_to_end(&mut buffer)?;
+        self.open_source(path)?.take(SAMPLE_SIZE as u64 + 1).read_to_end(&mut buffer)?;
+
+        // The length of a decompressed stream is unknown up front, so one
+        // extra byte tells whether the sample is the whole file
+        let complete = buffer.len() <= SAMPLE_SIZE;
+        buffer.truncate(SAMPLE_SIZE);
 
         let (decoded, _, _) = self.encoding.decode(&buffer);
-        let text = decoded.into_owned();
+        Ok(self.sniff_text(&decoded, complete))
+    }
 
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .delimiter(self.delimiter)
-            .has_headers(self.has_headers)
-            .from_reader(text.as_bytes());
+    fn sniff_text(&mut self, text: &str, complete: bool) -> SniffResult {
+        let result = Sniffer::new()
+            .with_extra_candidates(&self.extra_delimiters)
+            .with_quote(self.dialect.quote())
+            .with_comment(self.dialect.comment())
+            .sniff(text, complete);
 
-        let headers: Vec<String> = if self.has_headers {
-            csv_reader
-                .headers()
-                .context("Failed to read CSV headers")?
-                .iter()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
-        };
+        self.delimiter = result.delimiter.clone();
+        if self.detect_headers {
+            self.has_headers = result.has_headers;
+        }
+        self.sniff = Some(result.clone());
+        result
+    }
 
-        let mut rows: Vec<Vec<String>> = Vec::new();
-        for result in csv_reader.records() {
-            let record = result.context("Failed to read CSV record")?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            rows.push(row);
+    pub fn read_file(&mut self, path: &Path) -> Result<CsvData> {
+        self.prepare(path)?;
+        self.read_prepared(path)
+    }
+
+    /// Read the whole file using the settings found by `prepare`
+    pub fn read_prepared(&mut self, path: &Path) -> Result<CsvData> {
+        let mut buffer = Vec::new();
+        self.open_source(path)?
+            .read_to_end(&mut buffer)
+            .context("Failed to read CSV file")?;
+
+        let (text, decode_errors) = encoding::decode(self.encoding, &buffer);
+        if let Some(detection) = self.encoding_detection.as_mut() {
+            detection.decode_errors = decode_errors;
         }
 
-        let mut metadata = CsvMetadata::new(path)?;
-        metadata.delimiter = String::from_utf8_lossy(&[self.delimiter]).to_string();
-        metadata.encoding = self.encoding.name().to_string();
-        metadata.has_headers = self.has_headers;
-        metadata.update_counts(rows.len(), if !headers.is_empty() { headers.len() } else if !rows.is_empty() { rows[0].len() } else { 0 });
+        let (headers, rows, malformed_rows) = self.parse_text(&text)?;
+        let metadata = self.file_metadata(path, rows.len(), headers.len())?;
 
         Ok(CsvData {
             headers,
             rows,
             metadata,
+            sniff: self.sniff.clone(),
+            malformed_rows,
+            encoding_detection: self.encoding_detection.clone(),
         })
     }
 
-    pub fn read_from_string(&mut self, text: &str) -> Result<CsvData> {
-        // Detect delimiter from the text
-        self.detect_delimiter_from_string(text)?;
+    /// Detect encoding unless one was forced, and always the delimiter,
+    /// header row and compression
+    pub fn prepare(&mut self, path: &Path) -> Result<()> {
+        if self.encoding_override {
+            self.encoding_detection = Some(EncodingDetection {
+                encoding: self.encoding.name().to_string(),
+                confidence: 1.0,
+                source: EncodingSource::Override,
+                decode_errors: 0,
+            });
+        } else {
+            self.detect_encoding(path)?;
+        }
+        self.detect_format(path)?;
+        Ok(())
+    }
 
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .delimiter(self.delimiter)
-            .has_headers(self.has_headers)
-            .from_reader(text.as_bytes());
+    /// Metadata describing how the file was read
+    pub fn file_metadata(&self, path: &Path, row_count: usize, column_count: usize) -> Result<CsvMetadata> {
+        let mut metadata = CsvMetadata::new(path)?;
+        metadata.delimiter = self.delimiter.clone();
+        metadata.encoding = self.encoding.name().to_string();
+        metadata.has_headers = self.has_headers;
+        metadata.dialect = Some(self.dialect.clone());
+        metadata.compression = self.compression.unwrap_or_default();
+        metadata.archive_member = self.archive_member.clone();
+        metadata.update_counts(row_count, column_count);
+        Ok(metadata)
+    }
 
-        let headers: Vec<String> = if self.has_headers {
-            csv_reader
-                .headers()
-                .context("Failed to read CSV headers")?
-                .iter()
-                .map(|s| s.to_string())
-                .collect()
-        } else {
-            Vec::new()
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    pub fn has_headers(&self) -> bool {
+        self.has_headers
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    pub fn dialect(&self) -> &CsvDialect {
+        &self.dialect
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    /// Fail with `Cancelled` every `CANCEL_CHECK_INTERVAL` records once the
+    /// cancel flag is set
+    fn check_cancelled(&self, records: usize) -> Result<()> {
+        match &self.cancelled {
+            Some(flag) if records.is_multiple_of(CANCEL_CHECK_INTERVAL) && flag.load(Ordering::Relaxed) => {
+                Err(Cancelled.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn sniff(&self) -> Option<&SniffResult> {
+        self.sniff.as_ref()
+    }
+
+    /// Compression found by `prepare`, or `None` before the file was opened
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+
+    pub fn archive_member(&self) -> Option<&str> {
+        self.archive_member.as_deref()
+    }
+
+    /// Open the file through its decompressor, detecting the compression
+    /// (and the zip member to use) on first access
+    fn open_source(&mut self, path: &Path) -> Result<Box<dyn Read>> {
+        let compression = match self.compression {
+            Some(compression) => compression,
+            None => {
+                let detected = Compression::detect(path)?;
+                self.compression = Some(detected);
+                detected
+            }
         };
 
-        let mut rows: Vec<Vec<String>> = Vec::new();
-        for result in csv_reader.records() {
-            let record = result.context("Failed to read CSV record")?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            rows.push(row);
+        if compression == Compression::Zip && self.archive_member.is_none() {
+            self.archive_member = Some(compression::default_zip_member(path)?);
         }
 
+        compression::open(path, compression, self.archive_member.as_deref())
+    }
+
+    pub fn read_from_string(&mut self, text: &str) -> Result<CsvData> {
+        // Detect delimiter from the text
+        self.sniff_text(text, true);
+
+        let (headers, rows, malformed_rows) = self.parse_text(text)?;
+
         let mut metadata = CsvMetadata::from_pasted_data();
-        metadata.delimiter = String::from_utf8_lossy(&[self.delimiter]).to_string();
+        metadata.delimiter = self.delimiter.clone();
         metadata.encoding = "UTF-8".to_string();
         metadata.has_headers = self.has_headers;
-        metadata.update_counts(rows.len(), if !headers.is_empty() { headers.len() } else if !rows.is_empty() { rows[0].len() } else { 0 });
+        metadata.dialect = Some(self.dialect.clone());
+        metadata.update_counts(rows.len(), headers.len());
 
         Ok(CsvData {
             headers,
             rows,
             metadata,
+            sniff: self.sniff.clone(),
+            malformed_rows,
+            encoding_detection: self.encoding_detection.clone(),
         })
     }
 
-    fn detect_delimiter_from_string(&mut self, text: &str) -> Result<u8> {
-        let sample = text.lines().take(5).collect::<Vec<_>>().join("\n");
-        let buffer = sample.as_bytes();
+    /// Parse records and split off the header row. Files without a header
+    /// get generated column names so the grid always has titles to show.
+    fn parse_text(&self, text: &str) -> Result<ParsedText> {
+        let (records, mut malformed) = if self.lenient {
+            self.parse_lenient(text)?
+        } else {
+            (self.parse_records(text, usize::MAX)?, Vec::new())
+        };
+        let mut records = records.into_iter();
 
-        let common_delimiters = vec![b',', b'\t', b';', b'|'];
-        let mut delimiter_counts = vec![0; common_delimiters.len()];
+        let headers: Vec<String> = if self.has_headers {
+            records.next().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let rows: Vec<Vec<String>> = records.collect();
 
-        for (i, &delim) in common_delimiters.iter().enumerate() {
-            delimiter_counts[i] = buffer.iter().filter(|&&b| b == delim).count();
+        if self.has_headers {
+            for row in &mut malformed {
+                row.row_index = row.row_index.and_then(|i| i.checked_sub(1));
+            }
         }
 
-        let max_index = delimiter_counts
-            .iter()
-            .position(|&count| count == *delimiter_counts.iter().max().unwrap())
-            .unwrap_or(0);
+        let headers = if headers.is_empty() {
+            let column_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+            (1..=column_count).map(|i| format!("Column {}", i)).collect()
+        } else {
+            headers
+        };
 
-        self.delimiter = common_delimiters[max_index];
-        Ok(self.delimiter)
+        Ok((headers, rows, malformed))
     }
 
-    pub fn read_chunk(&mut self, path: &Path, start_row: usize, end_row: usize) -> Result<Vec<Vec<String>>> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        let mut buffer = Vec::new();
-        reader.read_to_end(&mut buffer)?;
+    /// Lenient parsing: ragged rows are padded or truncated to the expected
+    /// width and an unclosed quote only swallows its own line. Every repair
+    /// is reported with the original text so it can be reviewed. Row indices
+    /// in the report count the header line as record 0.
+    fn parse_lenient(&self, text: &str) -> Result<(Vec<Vec<String>>, Vec<MalformedRow>)> {
+        let records = match self.delimiter.as_bytes() {
+            [delimiter] => self.read_positioned(text, *delimiter)?,
+            _ => self.split_positioned(text),
+        };
 
-        let (decoded, _, _) = self.encoding.decode(&buffer);
-        let text = decoded.into_owned();
+        // The header defines the width; without one the most common width wins
+        let expected = if self.has_headers {
+            records.first().map(|r| r.fields.len()).unwrap_or(0)
+        } else {
+            let mut counts: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
+            for record in &records {
+                *counts.entry(record.fields.len()).or_insert(0) += 1;
+            }
+            counts
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)))
+                .map(|(len, _)| len)
+                .unwrap_or(0)
+        };
+
+        let mut rows = Vec::with_capacity(records.len());
+        let mut malformed = Vec::new();
+
+        for (index, record) in records.into_iter().enumerate() {
+            self.check_cancelled(index)?;
+            let actual = record.fields.len();
+            let issue = if record.unterminated_quote {
+                Some(MalformedIssue::UnterminatedQuote)
+            } else if actual < expected {
+                Some(MalformedIssue::TooFewFields)
+            } else if actual > expected {
+                Some(MalformedIssue::TooManyFields)
+            } else {
+                None
+            };
+
+            if let Some(issue) = issue {
+                let is_header = self.has_headers && index == 0;
+                malformed.push(MalformedRow {
+                    row_index: if is_header { None } else { Some(index) },
+                    line_number: record.line_number,
+                    byte_offset: record.byte_offset as u64,
+                    raw_text: record.raw_text,
+                    issue,
+                    expected_fields: expected,
+                    actual_fields: actual,
+                });
+            }
+
+            let mut fields = record.fields;
+            fields.resize(expected, String::new());
+            rows.push(fields);
+        }
+
+        Ok((rows, malformed))
+    }
+
+    /// Read records with their positions using the csv crate. When the last
+    /// record runs to the end of the text with an unbalanced quote, only its
+    /// first line is kept (read without quoting) and parsing resumes after it.
+    fn read_positioned(&self, text: &str, delimiter: u8) -> Result<Vec<PositionedRecord>> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+        let mut line_base = 0;
+
+        'restart: while offset < text.len() {
+            let slice = &text[offset..];
+            let mut csv_reader = self.dialect
+                .reader_builder(delimiter, false)
+                .flexible(true)
+                .from_reader(slice.as_bytes());
+            let mut record = csv::StringRecord::new();
+
+            while csv_reader.read_record(&mut record).context("Failed to read CSV record")? {
+                self.check_cancelled(records.len())?;
+                let (start, line) = record
+                    .position()
+                    .map(|p| (p.byte() as usize, p.line()))
+                    .unwrap_or((0, 1));
+                let end = (csv_reader.position().byte() as usize).min(slice.len());
+                let raw = slice[start..end].trim_end_matches(['\r', '\n']);
+
+                if end >= slice.len() && self.has_unclosed_quote(raw) {
+                    let first_line = raw.split('\n').next().unwrap_or("").trim_end_matches('\r');
+                    let fields = self.dialect
+                        .reader_builder(delimiter, false)
+                        .quoting(false)
+                        .flexible(true)
+                        .from_reader(first_line.as_bytes())
+                        .records()
+                        .next()
+                        .transpose()
+                        .context("Failed to read CSV record")?
+                        .map(|r| r.iter().map(|s| s.to_string()).collect())
+                        .unwrap_or_default();
+
+                    records.push(PositionedRecord {
+                        fields,
+                        byte_offset: offset + start,
+                        line_number: line_base + line,
+                        raw_text: first_line.to_string(),
+                        unterminated_quote: true,
+                    });
+
+                    offset += start + raw.find('\n').map_or(raw.len(), |i| i + 1);
+                    line_base += line;
+                    continue 'restart;
+                }
+
+                if self.dialect.should_skip(&record) {
+                    continue;
+                }
+
+                records.push(PositionedRecord {
+                    fields: record.iter().map(|s| s.to_string()).collect(),
+                    byte_offset: offset + start,
+                    line_number: line_base + line,
+                    raw_text: raw.to_string(),
+                    unterminated_quote: false,
+                });
+            }
 
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .delimiter(self.delimiter)
-            .has_headers(self.has_headers)
+            break;
+        }
+
+        Ok(records)
+    }
+
+    /// Multi-character delimiter counterpart of `read_positioned`. Unclosed
+    /// quotes are reported but not recovered from.
+    fn split_positioned(&self, text: &str) -> Vec<PositionedRecord> {
+        let (split, unterminated) = sniffer::split_records_with_offsets(
+            text,
+            &self.delimiter,
+            self.dialect.quote(),
+            self.dialect.comment(),
+            usize::MAX,
+        );
+
+        let offsets: Vec<usize> = split.iter().map(|(offset, _)| *offset).collect();
+        let mut line_number = 1;
+        let mut counted_to = 0;
+        let last = split.len().saturating_sub(1);
+        let mut records = Vec::with_capacity(split.len());
+
+        for (index, (start, fields)) in split.into_iter().enumerate() {
+            line_number += text[counted_to..start].matches('\n').count() as u64;
+            counted_to = start;
+
+            let end = offsets.get(index + 1).copied().unwrap_or(text.len());
+            let fields: Vec<String> = if self.dialect.trim_whitespace {
+                fields.into_iter().map(|v| v.trim().to_string()).collect()
+            } else {
+                fields
+            };
+            if self.dialect.skip_empty_rows && fields.iter().all(|v| v.trim().is_empty()) {
+                continue;
+            }
+
+            records.push(PositionedRecord {
+                fields,
+                byte_offset: start,
+                line_number,
+                raw_text: text[start..end].trim_end_matches(['\r', '\n']).to_string(),
+                unterminated_quote: unterminated && index == last,
+            });
+        }
+
+        records
+    }
+
+    /// Whether a record's raw text leaves a quote open, ignoring doubled or
+    /// escaped quotes
+    fn has_unclosed_quote(&self, raw: &str) -> bool {
+        let quote = self.dialect.quote();
+        let escape = if self.dialect.double_quote { None } else { self.dialect.escape() };
+        let bytes = raw.as_bytes();
+        let mut count = 0;
+
+        for (i, &b) in bytes.iter().enumerate() {
+            if b == quote && (i == 0 || Some(bytes[i - 1]) != escape) {
+                count += 1;
+            }
+        }
+
+        count % 2 == 1
+    }
+
+    /// Parse up to `limit` records (header row included). Single-byte
+    /// delimiters go through the csv crate; longer ones use the sniffer's
+    /// quote-aware splitter.
+    fn parse_records(&self, text: &str, limit: usize) -> Result<Vec<Vec<String>>> {
+        if let [delimiter] = self.delimiter.as_bytes() {
+            if limit == usize::MAX && text.len() >= self.parallel_threshold {
+                return self.parse_parallel(text, *delimiter);
+            }
+            return self.parse_chunk(text, *delimiter, limit).map(|(rows, _)| rows);
+        }
+
+        let mut rows = Vec::new();
+        for (index, row) in sniffer::split_records(text, &self.delimiter, self.dialect.quote(), self.dialect.comment(), limit)
+            .into_iter()
+            .enumerate()
+        {
+            self.check_cancelled(index)?;
+            let row: Vec<String> = if self.dialect.trim_whitespace {
+                row.into_iter().map(|v| v.trim().to_string()).collect()
+            } else {
+                row
+            };
+            if !(self.dialect.skip_empty_rows && row.iter().all(|v| v.trim().is_empty())) {
+                rows.push(row);
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Parse one piece of text with the csv crate, also returning the field
+    /// count of its first record so pieces can be checked against each other
+    fn parse_chunk(&self, text: &str, delimiter: u8, limit: usize) -> Result<(Vec<Vec<String>>, Option<usize>)> {
+        let mut csv_reader = self.dialect
+            .reader_builder(delimiter, false)
             .from_reader(text.as_bytes());
 
         let mut rows: Vec<Vec<String>> = Vec::new();
-        for (index, result) in csv_reader.records().enumerate() {
-            if index < start_row {
+        let mut first_width = None;
+        for result in csv_reader.records() {
+            if rows.len() >= limit {
+                break;
+            }
+            let record = result.context("Failed to read CSV record")?;
+            self.check_cancelled(rows.len())?;
+            first_width.get_or_insert(record.len());
+            if self.dialect.should_skip(&record) {
                 continue;
             }
-            if index >= end_row {
-                break;
+            rows.push(record.iter().map(|s| s.to_string()).collect());
+        }
+        Ok((rows, first_width))
+    }
+
+    /// Split the text at record boundaries and parse the pieces on the
+    /// rayon pool. Each piece is read by its own csv reader, so the equal
+    /// field count the csv crate enforces within a piece is checked across
+    /// pieces here.
+    fn parse_parallel(&self, text: &str, delimiter: u8) -> Result<Vec<Vec<String>>> {
+        let pieces = rayon::current_num_threads() * 4;
+        let chunk_size = (text.len() / pieces).max(MIN_PARSE_CHUNK);
+        let bounds = record_boundaries(text.as_bytes(), delimiter, &self.dialect, chunk_size);
+
+        let chunks: Vec<(Vec<Vec<String>>, Option<usize>)> = bounds
+            .par_windows(2)
+            .map(|w| self.parse_chunk(&text[w[0]..w[1]], delimiter, usize::MAX))
+            .collect::<Result<_>>()?;
+
+        let mut widths = chunks.iter().filter_map(|(_, width)| *width);
+        if let Some(expected) = widths.next() {
+            if let Some(width) = widths.find(|width| *width != expected) {
+                bail!(
+                    "Failed to read CSV record: found record with {} fields, but the previous record has {} fields",
+                    width,
+                    expected
+                );
             }
+        }
 
-            let record = result?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            rows.push(row);
+        let mut rows = Vec::with_capacity(chunks.iter().map(|(rows, _)| rows.len()).sum());
+        for (chunk, _) in chunks {
+            rows.extend(chunk);
         }
+        Ok(rows)
+    }
+
+    pub fn read_chunk(&mut self, path: &Path, start_row: usize, end_row: usize) -> Result<Vec<Vec<String>>> {
+        let mut buffer = Vec::new();
+        self.open_source(path)?.read_to_end(&mut buffer)?;
+
+        let (decoded, _, _) = self.encoding.decode(&buffer);
+        let text = decoded.into_owned();
+
+        let skip = if self.has_headers { 1 } else { 0 };
+        let rows = self.parse_records(&text, end_row.saturating_add(skip))?
+            .into_iter()
+            .skip(skip + start_row)
+            .collect();
 
         Ok(rows)
     }
-}
\ No newline at end of file
+}
+
+/// Offsets that split `bytes` into pieces of roughly `chunk_size` without
+/// cutting a record. Mirrors the csv crate's states: a quote only opens a
+/// quoted field at the start of a field, doubled quotes or the escape
+/// character keep it open, and comment lines are skipped whole. Starts
+/// with 0 and ends with `bytes.len()`.
+fn record_boundaries(bytes: &[u8], delimiter: u8, dialect: &CsvDialect, chunk_size: usize) -> Vec<usize> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        RecordStart,
+        FieldStart,
+        Unquoted,
+        Quoted,
+        QuoteInQuoted,
+        Comment,
+    }
+
+    let quote = dialect.quote();
+    let escape = if dialect.double_quote { None } else { dialect.escape() };
+    let comment = dialect.comment();
+
+    let mut bounds = vec![0];
+    let mut next_split = chunk_size;
+    let mut state = State::RecordStart;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let byte = bytes[i];
+        state = match state {
+            State::Quoted if Some(byte) == escape => {
+                i += 1;
+                State::Quoted
+            }
+            State::Quoted if byte == quote => {
+                if dialect.double_quote { State::QuoteInQuoted } else { State::Unquoted }
+            }
+            State::Quoted => State::Quoted,
+            State::Comment if byte == b'\n' => State::RecordStart,
+            State::Comment => State::Comment,
+            State::QuoteInQuoted if byte == quote => State::Quoted,
+            State::RecordStart if Some(byte) == comment => State::Comment,
+            State::RecordStart | State::FieldStart if byte == quote => State::Quoted,
+            _ if byte == b'\n' => State::RecordStart,
+            _ if byte == delimiter => State::FieldStart,
+            _ => State::Unquoted,
+        };
+        i += 1;
+
+        if state == State::RecordStart && i >= next_split && i < bytes.len() {
+            bounds.push(i);
+            next_split = i + chunk_size;
+        }
+    }
+
+    bounds.push(bytes.len());
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lenient_pads_and_truncates_ragged_rows() {
+        let mut reader = CsvReader::new().with_lenient(true);
+        let data = reader.read_from_string("a,b,c\n1,2\n3,4,5,6\n7,8,9\n").unwrap();
+
+        assert_eq!(data.rows.len(), 3);
+        assert_eq!(data.rows[0], vec!["1", "2", ""]);
+        assert_eq!(data.rows[1], vec!["3", "4", "5"]);
+
+        let issues: Vec<&MalformedIssue> = data.malformed_rows.iter().map(|m| &m.issue).collect();
+        assert_eq!(issues, vec![&MalformedIssue::TooFewFields, &MalformedIssue::TooManyFields]);
+        assert_eq!(data.malformed_rows[1].row_index, Some(1));
+        assert_eq!(data.malformed_rows[1].line_number, 3);
+        assert_eq!(data.malformed_rows[1].raw_text, "3,4,5,6");
+    }
+
+    #[test]
+    fn test_lenient_recovers_from_unterminated_quote() {
+        let text = "name,note\nAlice,\"never closed\nBob,fine\nCarol,ok\n";
+        let mut reader = CsvReader::new().with_lenient(true);
+        let data = reader.read_from_string(text).unwrap();
+
+        assert_eq!(data.rows.len(), 3);
+        assert_eq!(data.rows[2], vec!["Carol", "ok"]);
+
+        let issue = &data.malformed_rows[0];
+        assert_eq!(issue.issue, MalformedIssue::UnterminatedQuote);
+        assert_eq!(issue.line_number, 2);
+        assert_eq!(issue.byte_offset, 10);
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_ragged_rows() {
+        let mut reader = CsvReader::new();
+        assert!(reader.read_from_string("a,b\n1,2,3\n").is_err());
+    }
+
+    #[test]
+    fn test_parallel_parse_matches_sequential() {
+        let mut text = String::from("id,note\n");
+        for i in 0..40_000 {
+            text.push_str(&format!("{},\"line {}\nwith, comma and \"\"quotes\"\"\"\n", i, i));
+            text.push_str(&format!("{},5\" screen\n", i));
+        }
+
+        let sequential = CsvReader::new().with_parallel_threshold(usize::MAX).read_from_string(&text).unwrap();
+        let parallel = CsvReader::new().with_parallel_threshold(0).read_from_string(&text).unwrap();
+        assert_eq!(parallel.rows.len(), 80_000);
+        assert_eq!(parallel.rows, sequential.rows);
+
+        let bounds = record_boundaries(text.as_bytes(), b',', &CsvDialect::default(), 100);
+        assert!(bounds.len() > 2);
+
+        text.push_str("1,2,3\n");
+        assert!(CsvReader::new().with_parallel_threshold(0).read_from_string(&text).is_err());
+    }
+
+    #[test]
+    fn test_read_gzip_file() {
+        let path = std::env::temp_dir().join(format!("{}-data.csv.gz", uuid::Uuid::new_v4()));
+        compression::write(&path, Compression::Gzip, None, b"id;name\n1;a\n2;b\n").unwrap();
+
+        let data = CsvReader::new().read_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(data.headers, vec!["id", "name"]);
+        assert_eq!(data.rows.len(), 2);
+        assert_eq!(data.metadata.compression, Compression::Gzip);
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+use super::validation::ValidationError;
+
+/// Sample values kept per group
+const MAX_SAMPLES: usize = 5;
+/// Row links listed per group before the rest are only counted
+const MAX_ROW_LINKS: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+/// One failed check, from a validation rule or a data type check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportEntry {
+    pub row_index: usize,
+    pub column_index: usize,
+    pub column_name: String,
+    pub value: String,
+    /// Rule type, e.g. `unique`, or `type` for data type checks
+    pub rule: String,
+    pub message: String,
+    /// The rule could not be applied, so the entry belongs to no row
+    #[serde(default)]
+    pub rule_error: bool,
+}
+
+impl From<&ValidationError> for ReportEntry {
+    fn from(error: &ValidationError) -> Self {
+        let rule = serde_json::to_value(&error.rule_type)
+            .ok()
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+
+        Self {
+            row_index: error.row_index,
+            column_index: error.column_index,
+            column_name: error.column_name.clone(),
+            value: error.value.clone(),
+            rule,
+            message: error.message.clone(),
+            rule_error: error.rule_error,
+        }
+    }
+}
+
+/// Failures of one rule in one column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportGroup {
+    pub rule: String,
+    pub column_index: usize,
+    pub column_name: String,
+    pub count: usize,
+    /// Distinct offending values, at most `MAX_SAMPLES`
+    pub samples: Vec<String>,
+    pub rows: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub source: String,
+    pub generated_at: String,
+    pub total_rows: usize,
+    pub failing_rows: usize,
+    pub error_count: usize,
+    pub groups: Vec<ReportGroup>,
+    pub errors: Vec<ReportEntry>,
+}
+
+impl ValidationReport {
+    pub fn new(source: &str, total_rows: usize, mut errors: Vec<ReportEntry>) -> Self {
+        errors.sort_by_key(|e| (e.row_index, e.column_index));
+
+        let mut groups: BTreeMap<(String, usize), ReportGroup> = BTreeMap::new();
+        for error in &errors {
+            let group = groups
+                .entry((error.rule.clone(), error.column_index))
+                .or_insert_with(|| ReportGroup {
+                    rule: error.rule.clone(),
+                    column_index: error.column_index,
+                    column_name: error.column_name.clone(),
+                    count: 0,
+                    samples: Vec::new(),
+                    rows: Vec::new(),
+                });
+            group.count += 1;
+            if group.samples.len() < MAX_SAMPLES && !group.samples.contains(&error.value) {
+                group.samples.push(error.value.clone());
+            }
+            if !error.rule_error && group.rows.last() != Some(&error.row_index) {
+                group.rows.push(error.row_index);
+            }
+        }
+
+        let mut groups: Vec<ReportGroup> = groups.into_values().collect();
+        groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+
+        Self {
+            source: source.to_string(),
+            generated_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            total_rows,
+            failing_rows: Self::row_indices(&errors).len(),
+            error_count: errors.len(),
+            groups,
+            errors,
+        }
+    }
+
+    /// Rows with at least one error
+    pub fn row_indices(errors: &[ReportEntry]) -> BTreeSet<usize> {
+        errors.iter().filter(|e| !e.rule_error).map(|e| e.row_index).collect()
+    }
+
+    pub fn write(&self, path: &Path, format: ReportFormat) -> Result<()> {
+        let content = match format {
+            ReportFormat::Csv => self.to_csv()?,
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Html => self.to_html(),
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// One line per error, with 1-based row numbers as shown in the grid
+    pub fn to_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["row", "column", "rule", "value", "message"])?;
+        for error in &self.errors {
+            let row = if error.rule_error { String::new() } else { (error.row_index + 1).to_string() };
+            writer.write_record([
+                row.as_str(),
+                &error.column_name,
+                &error.rule,
+                &error.value,
+                &error.message,
+            ])?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// Self-contained page with a summary per rule and column, linking to
+    /// the detail line of each row
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Validation report - {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            escape_html(&self.source),
+            REPORT_STYLE
+        );
+        let _ = write!(
+            html,
+            "<h1>Validation report</h1>\n<p>{} &middot; generated {}</p>\n<p class=\"summary\">{} errors in {} of {} rows</p>\n",
+            escape_html(&self.source),
+            self.generated_at,
+            self.error_count,
+            self.failing_rows,
+            self.total_rows
+        );
+
+        html.push_str("<h2>By rule and column</h2>\n<table>\n<tr><th>Rule</th><th>Column</th><th>Errors</th><th>Sample values</th><th>Rows</th></tr>\n");
+        for group in &self.groups {
+            let samples: Vec<String> = group
+                .samples
+                .iter()
+                .map(|v| format!("<code>{}</code>", escape_html(v)))
+                .collect();
+            let mut rows: Vec<String> = group
+                .rows
+                .iter()
+                .take(MAX_ROW_LINKS)
+                .map(|r| format!("<a href=\"#row-{0}\">{0}</a>", r + 1))
+                .collect();
+            if group.rows.len() > MAX_ROW_LINKS {
+                rows.push(format!("and {} more", group.rows.len() - MAX_ROW_LINKS));
+            }
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td class=\"count\">{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&group.rule),
+                escape_html(&group.column_name),
+                group.count,
+                samples.join(", "),
+                rows.join(" ")
+            );
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Errors</h2>\n<table>\n<tr><th>Row</th><th>Column</th><th>Rule</th><th>Value</th><th>Message</th></tr>\n");
+        let mut previous_row = None;
+        for error in &self.errors {
+            let row = if error.rule_error { String::new() } else { (error.row_index + 1).to_string() };
+            // Anchor the first line of each row for the links above
+            let id = match error.rule_error || previous_row == Some(error.row_index) {
+                true => String::new(),
+                false => format!(" id=\"row-{}\"", row),
+            };
+            if !error.rule_error {
+                previous_row = Some(error.row_index);
+            }
+            let _ = writeln!(
+                html,
+                "<tr{}><td class=\"count\">{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td></tr>",
+                id,
+                row,
+                escape_html(&error.column_name),
+                escape_html(&error.rule),
+                escape_html(&error.value),
+                escape_html(&error.message)
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+const REPORT_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#f3f3f3}td.count{text-align:right}\
+code{background:#f6f6f6;padding:0 2px}.summary{font-weight:bold}\
+tr:target{background:#fff3c4}";
+
+/// Escape text for HTML element content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(row_index: usize, rule: &str, value: &str) -> ReportEntry {
+        ReportEntry {
+            row_index,
+            column_index: 0,
+            column_name: "id".to_string(),
+            value: value.to_string(),
+            rule: rule.to_string(),
+            message: format!("bad <{}>", value),
+            rule_error: false,
+        }
+    }
+
+    #[test]
+    fn test_validation_report() {
+        let report = ValidationReport::new(
+            "data.csv",
+            10,
+            vec![entry(4, "unique", "1"), entry(2, "unique", "1"), entry(4, "required", "")],
+        );
+
+        assert_eq!(report.error_count, 3);
+        assert_eq!(report.failing_rows, 2);
+        assert_eq!(report.groups[0].rule, "unique");
+        assert_eq!(report.groups[0].count, 2);
+        assert_eq!(report.groups[0].samples, vec!["1"]);
+        assert_eq!(report.groups[0].rows, vec![2, 4]);
+
+        let csv = report.to_csv().unwrap();
+        assert!(csv.starts_with("row,column,rule,value,message\n3,id,unique,1,bad <1>\n"));
+
+        let html = report.to_html();
+        assert!(html.contains("<a href=\"#row-5\">5</a>"));
+        assert!(html.contains("id=\"row-3\""));
+        assert!(html.contains("bad &lt;1&gt;"));
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use super::data_types::{DataType, DataTypeDetector};
+use super::locale::LocaleFormat;
+use super::validation::{ValidationError, ValidationRule, ValidationRuleType, Validator};
+
+/// File format a schema is written in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaFormat {
+    /// Frictionless Table Schema, https://specs.frictionlessdata.io/table-schema/
+    #[default]
+    TableSchema,
+    /// JSON Schema describing an array of row objects
+    JsonSchema,
+}
+
+/// Columns, types and constraints of a CSV file, in Frictionless Table
+/// Schema form. Field names follow the spec, hence camelCase.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSchema {
+    pub fields: Vec<SchemaField>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", deserialize_with = "one_or_many")]
+    pub primary_key: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub foreign_keys: Vec<ForeignKey>,
+    /// Values read as empty
+    #[serde(default = "default_missing_values")]
+    pub missing_values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SchemaField {
+    pub name: String,
+    /// Table Schema type: string, number, integer, boolean, date, datetime,
+    /// time, duration, object, array, year or any
+    #[serde(rename = "type", default = "default_field_type")]
+    pub field_type: String,
+    /// Subtype such as `email`, or a strftime pattern for dates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "FieldConstraints::is_empty")]
+    pub constraints: FieldConstraints,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldConstraints {
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unique: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Must match the whole value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(rename = "enum", default, skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKey {
+    #[serde(deserialize_with = "one_or_many")]
+    pub fields: Vec<String>,
+    pub reference: ForeignKeyReference,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKeyReference {
+    /// Referenced CSV file, relative to the schema; empty for the file itself
+    #[serde(default)]
+    pub resource: String,
+    #[serde(deserialize_with = "one_or_many")]
+    pub fields: Vec<String>,
+}
+
+/// Outcome of checking data against a schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaValidation {
+    pub is_valid: bool,
+    /// Schema fields with no column of that name in the data
+    pub missing_fields: Vec<String>,
+    pub errors: Vec<ValidationError>,
+}
+
+fn default_missing_values() -> Vec<String> {
+    vec![String::new()]
+}
+
+fn default_field_type() -> String {
+    "string".to_string()
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Table Schema allows a single name wherever a list of names is expected
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(name) => vec![name],
+        OneOrMany::Many(names) => names,
+    })
+}
+
+impl FieldConstraints {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl SchemaField {
+    pub fn new(name: impl Into<String>, data_type: &DataType) -> Self {
+        let (field_type, format) = match data_type {
+            DataType::Integer => ("integer", None),
+            DataType::Float => ("number", None),
+            DataType::Currency => ("number", Some("currency")),
+            DataType::Percentage => ("number", Some("percentage")),
+            DataType::Boolean => ("boolean", None),
+            DataType::Date => ("date", None),
+            DataType::DateTime => ("datetime", None),
+            DataType::Time => ("time", None),
+            DataType::Duration => ("duration", None),
+            DataType::Email => ("string", Some("email")),
+            DataType::Url => ("string", Some("uri")),
+            DataType::Uuid => ("string", Some("uuid")),
+            DataType::IpAddress => ("string", Some("ip")),
+            DataType::Phone => ("string", Some("phone")),
+            DataType::Json => ("object", None),
+            DataType::Text => ("string", None),
+        };
+
+        Self {
+            name: name.into(),
+            field_type: field_type.to_string(),
+            format: format.map(String::from),
+            title: None,
+            description: None,
+            constraints: FieldConstraints::default(),
+        }
+    }
+
+    /// Type values of this field are checked against
+    pub fn data_type(&self) -> DataType {
+        match (self.field_type.as_str(), self.format.as_deref()) {
+            ("integer" | "year", _) => DataType::Integer,
+            ("number", Some("currency")) => DataType::Currency,
+            ("number", Some("percentage")) => DataType::Percentage,
+            ("number", _) => DataType::Float,
+            ("boolean", _) => DataType::Boolean,
+            ("date", _) => DataType::Date,
+            ("datetime", _) => DataType::DateTime,
+            ("time", _) => DataType::Time,
+            ("duration", _) => DataType::Duration,
+            ("object" | "array", _) => DataType::Json,
+            ("string", Some("email")) => DataType::Email,
+            ("string", Some("uri")) => DataType::Url,
+            ("string", Some("uuid")) => DataType::Uuid,
+            ("string", Some("ip" | "ipv4" | "ipv6")) => DataType::IpAddress,
+            ("string", Some("phone")) => DataType::Phone,
+            _ => DataType::Text,
+        }
+    }
+
+    /// strftime pattern declared for a date or datetime field
+    fn date_format(&self) -> Option<&str> {
+        self.format.as_deref().filter(|f| f.contains('%'))
+    }
+}
+
+impl TableSchema {
+    /// Read a Table Schema, or a JSON Schema for an array of row objects
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schema {}", path.display()))?;
+        let value: Value = serde_json::from_str(&text)
+            .with_context(|| format!("Schema {} is not valid JSON", path.display()))?;
+
+        if value.get("fields").is_some() {
+            Ok(serde_json::from_value(value)?)
+        } else if value.get("properties").is_some() || value.get("items").is_some() {
+            // Parsed from the text again so properties keep their order
+            Self::from_json_schema(&text)
+        } else {
+            bail!("{} is neither a Table Schema nor a JSON Schema", path.display())
+        }
+    }
+
+    pub fn save(&self, path: &Path, format: SchemaFormat) -> Result<()> {
+        let text = match format {
+            SchemaFormat::TableSchema => serde_json::to_string_pretty(self)?,
+            SchemaFormat::JsonSchema => serde_json::to_string_pretty(&self.to_json_schema())?,
+        };
+        fs::write(path, text).with_context(|| format!("Failed to write schema {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Draft schema from the detected types: columns with no empty value
+    /// are required, and the first column of distinct, non-empty integers,
+    /// UUIDs or text becomes the primary key
+    pub fn draft(headers: &[String], rows: &[Vec<String>], detector: &DataTypeDetector) -> Self {
+        let inferences = detector.infer_column_types(rows, headers.len(), 1000);
+
+        let fields: Vec<SchemaField> = headers
+            .iter()
+            .zip(&inferences)
+            .enumerate()
+            .map(|(index, (header, inference))| {
+                let mut field = SchemaField::new(header, &inference.data_type);
+                field.constraints.required =
+                    !rows.is_empty() && rows.iter().all(|row| row.get(index).is_some_and(|v| !v.trim().is_empty()));
+                field
+            })
+            .collect();
+
+        let primary_key = fields
+            .iter()
+            .enumerate()
+            .find(|(index, field)| {
+                field.constraints.required
+                    && matches!(field.data_type(), DataType::Integer | DataType::Uuid | DataType::Text)
+                    && {
+                        let mut seen = HashSet::new();
+                        rows.iter().all(|row| seen.insert(row[*index].as_str()))
+                    }
+            })
+            .map(|(_, field)| vec![field.name.clone()])
+            .unwrap_or_default();
+
+        Self {
+            fields,
+            primary_key,
+            foreign_keys: Vec::new(),
+            missing_values: default_missing_values(),
+        }
+    }
+
+    /// Validation rules enforcing the field constraints, keys and foreign
+    /// keys on columns matched by name. Fields without a column give no rules.
+    pub fn rules(&self, headers: &[String]) -> Vec<ValidationRule> {
+        let mut rules = Vec::new();
+        let position = |name: &String| headers.iter().position(|h| h == name);
+        let names_json = |names: &[String]| serde_json::to_string(names).unwrap_or_default();
+
+        if self.primary_key.len() > 1 {
+            if let Some(column_index) = position(&self.primary_key[0]) {
+                rules.push(ValidationRule {
+                    rule_type: ValidationRuleType::CompositeUnique,
+                    column_index,
+                    column_name: self.primary_key[0].clone(),
+                    parameters: [("columns".to_string(), names_json(&self.primary_key))].into(),
+                    error_message: None,
+                });
+            }
+        }
+
+        for foreign_key in &self.foreign_keys {
+            let Some(column_index) = foreign_key.fields.first().and_then(position) else {
+                continue;
+            };
+            rules.push(ValidationRule {
+                rule_type: ValidationRuleType::ForeignKey,
+                column_index,
+                column_name: foreign_key.fields[0].clone(),
+                parameters: [
+                    ("columns".to_string(), names_json(&foreign_key.fields)),
+                    ("file".to_string(), foreign_key.reference.resource.clone()),
+                    ("reference_columns".to_string(), names_json(&foreign_key.reference.fields)),
+                ]
+                .into(),
+                error_message: None,
+            });
+        }
+
+        for field in &self.fields {
+            let Some(column_index) = headers.iter().position(|h| *h == field.name) else {
+                continue;
+            };
+            let rule = |rule_type: ValidationRuleType, parameters: &[(&str, String)]| ValidationRule {
+                rule_type,
+                column_index,
+                column_name: field.name.clone(),
+                parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+                error_message: None,
+            };
+
+            let constraints = &field.constraints;
+            let is_key = self.primary_key.len() == 1 && self.primary_key[0] == field.name;
+            let in_key = self.primary_key.contains(&field.name);
+
+            if constraints.required || in_key {
+                rules.push(rule(ValidationRuleType::Required, &[]));
+            }
+            if constraints.unique || is_key {
+                rules.push(rule(ValidationRuleType::Unique, &[]));
+            }
+
+            let data_type = field.data_type();
+            if data_type != DataType::Text {
+                let mut parameters = vec![("type", json_text(&json!(data_type)))];
+                if let Some(format) = field.date_format() {
+                    parameters.push(("format", format.to_string()));
+                }
+                rules.push(rule(ValidationRuleType::Type, &parameters));
+            }
+
+            let bounds: Vec<(&str, String)> = [("min", &constraints.minimum), ("max", &constraints.maximum)]
+                .into_iter()
+                .filter_map(|(key, bound)| {
+                    let bound = json_text(bound.as_ref()?);
+                    bound.parse::<f64>().is_ok().then_some((key, bound))
+                })
+                .collect();
+            if !bounds.is_empty() {
+                rules.push(rule(ValidationRuleType::Range, &bounds));
+            }
+
+            let lengths: Vec<(&str, String)> = [("min_length", constraints.min_length), ("max_length", constraints.max_length)]
+                .into_iter()
+                .filter_map(|(key, length)| Some((key, length?.to_string())))
+                .collect();
+            if !lengths.is_empty() {
+                rules.push(rule(ValidationRuleType::Length, &lengths));
+            }
+
+            if let Some(pattern) = &constraints.pattern {
+                rules.push(rule(ValidationRuleType::Pattern, &[("pattern", format!("^(?:{})$", pattern))]));
+            }
+
+            if let Some(values) = &constraints.enum_values {
+                let values: Vec<String> = values.iter().map(json_text).collect();
+                rules.push(rule(ValidationRuleType::Enum, &[("values", serde_json::to_string(&values).unwrap_or_default())]));
+            }
+        }
+
+        rules
+    }
+
+    /// Check `rows` against the schema, reading values with the file's
+    /// `locale`. Foreign keys to other files are read relative to
+    /// `base_dir`, normally the schema's directory.
+    pub fn validate(
+        &self,
+        headers: &[String],
+        rows: &[Vec<String>],
+        locale: &LocaleFormat,
+        base_dir: Option<&Path>,
+    ) -> SchemaValidation {
+        let missing_fields: Vec<String> = self
+            .fields
+            .iter()
+            .filter(|field| !headers.contains(&field.name))
+            .map(|field| field.name.clone())
+            .collect();
+
+        let mut validator = Validator::new(self.rules(headers)).with_locale(locale.clone());
+        if let Some(dir) = base_dir {
+            validator = validator.with_base_dir(dir);
+        }
+
+        // Declared missing values such as `NA` count as empty
+        let errors = if self.missing_values.iter().any(|v| !v.is_empty()) {
+            let normalized: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|v| if self.missing_values.contains(v) { String::new() } else { v.clone() })
+                        .collect()
+                })
+                .collect();
+            validator.validate(&normalized, headers)
+        } else {
+            validator.validate(rows, headers)
+        };
+
+        SchemaValidation {
+            is_valid: missing_fields.is_empty() && errors.is_empty(),
+            missing_fields,
+            errors,
+        }
+    }
+
+    /// The schema as a JSON Schema for an array of row objects. Keys and
+    /// foreign keys are kept as `primaryKey` and `foreignKeys` keywords.
+    pub fn to_json_schema(&self) -> JsonSchemaDocument {
+        let properties = self
+            .fields
+            .iter()
+            .map(|field| {
+                let (property_type, format) = match (field.field_type.as_str(), field.format.clone()) {
+                    ("date", format) => ("string", format.or(Some("date".to_string()))),
+                    ("datetime", format) => ("string", format.or(Some("date-time".to_string()))),
+                    ("time", _) => ("string", Some("time".to_string())),
+                    ("duration", _) => ("string", Some("duration".to_string())),
+                    ("year", _) => ("integer", None),
+                    (field_type @ ("integer" | "number" | "boolean" | "object" | "array"), format) => (field_type, format),
+                    (_, format) => ("string", format),
+                };
+                let constraints = &field.constraints;
+                let property = JsonSchemaProperty {
+                    property_type: Some(json!(property_type)),
+                    format,
+                    title: field.title.clone(),
+                    description: field.description.clone(),
+                    minimum: constraints.minimum.clone(),
+                    maximum: constraints.maximum.clone(),
+                    min_length: constraints.min_length,
+                    max_length: constraints.max_length,
+                    pattern: constraints.pattern.clone(),
+                    enum_values: constraints.enum_values.clone(),
+                    unique: constraints.unique,
+                };
+                (field.name.clone(), property)
+            })
+            .collect();
+
+        JsonSchemaDocument {
+            schema: Some("https://json-schema.org/draft/2020-12/schema".to_string()),
+            document_type: Some(json!("array")),
+            items: Some(Box::new(JsonSchemaDocument {
+                document_type: Some(json!("object")),
+                properties,
+                required: self
+                    .fields
+                    .iter()
+                    .filter(|f| f.constraints.required)
+                    .map(|f| f.name.clone())
+                    .collect(),
+                ..Default::default()
+            })),
+            primary_key: self.primary_key.clone(),
+            foreign_keys: self.foreign_keys.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Read a JSON Schema for an array of row objects, or for one row
+    pub fn from_json_schema(text: &str) -> Result<Self> {
+        let document: JsonSchemaDocument = serde_json::from_str(text)?;
+        let mut primary_key = document.primary_key.clone();
+        let mut foreign_keys = document.foreign_keys.clone();
+        let row = match document.items {
+            Some(items) => {
+                primary_key.extend(items.primary_key.iter().cloned());
+                foreign_keys.extend(items.foreign_keys.iter().cloned());
+                *items
+            }
+            None => document,
+        };
+        if row.properties.is_empty() {
+            bail!("JSON Schema has no properties");
+        }
+
+        let fields = row
+            .properties
+            .into_iter()
+            .map(|(name, property)| {
+                let property_type = match &property.property_type {
+                    Some(Value::String(t)) => t.clone(),
+                    // `["string", "null"]` for nullable columns
+                    Some(Value::Array(types)) => types
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .find(|t| *t != "null")
+                        .unwrap_or("string")
+                        .to_string(),
+                    _ => "string".to_string(),
+                };
+                let (field_type, format) = match (property_type.as_str(), property.format) {
+                    ("string", Some(format)) => match format.as_str() {
+                        "date" => ("date", None),
+                        "date-time" => ("datetime", None),
+                        "time" => ("time", None),
+                        "duration" => ("duration", None),
+                        custom if custom.contains("%H") => ("datetime", Some(format)),
+                        custom if custom.contains('%') => ("date", Some(format)),
+                        _ => ("string", Some(format)),
+                    },
+                    (field_type @ ("integer" | "number" | "boolean" | "object" | "array"), format) => (field_type, format),
+                    (_, format) => ("string", format),
+                };
+
+                SchemaField {
+                    field_type: field_type.to_string(),
+                    format,
+                    title: property.title,
+                    description: property.description,
+                    constraints: FieldConstraints {
+                        required: row.required.contains(&name),
+                        unique: property.unique,
+                        minimum: property.minimum,
+                        maximum: property.maximum,
+                        min_length: property.min_length,
+                        max_length: property.max_length,
+                        pattern: property.pattern,
+                        enum_values: property.enum_values,
+                    },
+                    name,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            fields,
+            primary_key,
+            foreign_keys,
+            missing_values: default_missing_values(),
+        })
+    }
+}
+
+/// Text of a JSON scalar as it would appear in a cell
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JSON Schema document, limited to the keywords that map onto Table Schema
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSchemaDocument {
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    schema: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    document_type: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    items: Option<Box<JsonSchemaDocument>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "ordered_properties")]
+    properties: Vec<(String, JsonSchemaProperty)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    required: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", deserialize_with = "one_or_many")]
+    primary_key: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    foreign_keys: Vec<ForeignKey>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSchemaProperty {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    property_type: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minimum: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    maximum: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(rename = "enum", default, skip_serializing_if = "Option::is_none")]
+    enum_values: Option<Vec<Value>>,
+    /// Not a JSON Schema keyword; kept so Table Schema round-trips
+    #[serde(default, skip_serializing_if = "is_false")]
+    unique: bool,
+}
+
+/// `properties` as a list, so columns keep the order they are written in
+mod ordered_properties {
+    use std::fmt;
+    use serde::de::{MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserializer, Serializer};
+    use super::JsonSchemaProperty;
+
+    pub fn serialize<S: Serializer>(properties: &[(String, JsonSchemaProperty)], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(properties.len()))?;
+        for (name, property) in properties {
+            map.serialize_entry(name, property)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, JsonSchemaProperty)>, D::Error> {
+        struct PropertiesVisitor;
+
+        impl<'de> Visitor<'de> for PropertiesVisitor {
+            type Value = Vec<(String, JsonSchemaProperty)>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of property schemas")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut properties = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    properties.push(entry);
+                }
+                Ok(properties)
+            }
+        }
+
+        deserializer.deserialize_map(PropertiesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_table_schema_validation() {
+        let schema: TableSchema = serde_json::from_str(r#"{
+            "fields": [
+                {"name": "id", "type": "integer"},
+                {"name": "status", "type": "string", "constraints": {"enum": ["open", "closed"]}},
+                {"name": "score", "type": "number", "constraints": {"minimum": 0, "maximum": 10}},
+                {"name": "due", "type": "date", "format": "%d.%m.%Y"},
+                {"name": "owner", "constraints": {"required": true}}
+            ],
+            "primaryKey": "id",
+            "missingValues": ["", "NA"]
+        }"#).unwrap();
+
+        let headers = strings(&["id", "status", "score", "due"]);
+        let rows = vec![
+            strings(&["1", "open", "5", "31.12.2024"]),
+            strings(&["1", "done", "11", "2024-12-31"]),
+            strings(&["x", "NA", "NA", ""]),
+        ];
+
+        let result = schema.validate(&headers, &rows, &LocaleFormat::default(), None);
+        assert!(!result.is_valid);
+        assert_eq!(result.missing_fields, vec!["owner".to_string()]);
+
+        let failures: Vec<(usize, ValidationRuleType)> = result
+            .errors
+            .iter()
+            .map(|e| (e.row_index, e.rule_type.clone()))
+            .collect();
+        assert_eq!(failures.len(), 5);
+        assert!(failures.contains(&(1, ValidationRuleType::Unique)));
+        assert!(failures.contains(&(1, ValidationRuleType::Enum)));
+        assert!(failures.contains(&(1, ValidationRuleType::Range)));
+        assert!(failures.contains(&(1, ValidationRuleType::Type)));
+        assert!(failures.contains(&(2, ValidationRuleType::Type)));
+    }
+
+    #[test]
+    fn test_json_schema_round_trip_keeps_column_order() {
+        let text = r#"{
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "zeta": {"type": "string", "format": "email"},
+                    "alpha": {"type": ["integer", "null"], "minimum": 1},
+                    "when": {"type": "string", "format": "date-time"}
+                },
+                "required": ["zeta"]
+            }
+        }"#;
+
+        let schema = TableSchema::from_json_schema(text).unwrap();
+        let names: Vec<&str> = schema.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["zeta", "alpha", "when"]);
+        assert_eq!(schema.fields[0].data_type(), DataType::Email);
+        assert!(schema.fields[0].constraints.required);
+        assert_eq!(schema.fields[1].data_type(), DataType::Integer);
+        assert_eq!(schema.fields[2].data_type(), DataType::DateTime);
+
+        let written = serde_json::to_string(&schema.to_json_schema()).unwrap();
+        assert_eq!(TableSchema::from_json_schema(&written).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_draft_infers_types_and_primary_key() {
+        let headers = strings(&["name", "id", "joined"]);
+        let rows = vec![
+            strings(&["Ann", "1", "2024-01-02"]),
+            strings(&["Ann", "2", ""]),
+        ];
+
+        let schema = TableSchema::draft(&headers, &rows, &DataTypeDetector::new());
+        assert_eq!(schema.fields[1].field_type, "integer");
+        assert_eq!(schema.fields[2].field_type, "date");
+        assert!(!schema.fields[2].constraints.required);
+        assert_eq!(schema.primary_key, vec!["id".to_string()]);
+        assert!(schema.validate(&headers, &rows, &LocaleFormat::default(), None).is_valid);
+    }
+
+    #[test]
+    fn test_draft_accepts_its_own_data_in_the_file_locale() {
+        let german = LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+        };
+        let headers = strings(&["count", "price"]);
+        let rows = vec![
+            strings(&["1.234", "1.234,56"]),
+            strings(&["12", "0,5"]),
+        ];
+
+        let schema = TableSchema::draft(&headers, &rows, &DataTypeDetector::new().with_locale(german.clone()));
+        assert_eq!(schema.fields[0].field_type, "integer");
+        assert_eq!(schema.fields[1].field_type, "number");
+        assert!(schema.validate(&headers, &rows, &german, None).is_valid);
+        assert!(!schema.validate(&headers, &rows, &LocaleFormat::default(), None).is_valid);
+    }
+
+    #[test]
+    fn test_constraints_count_characters_and_read_the_locale() {
+        let schema: TableSchema = serde_json::from_str(r#"{
+            "fields": [
+                {"name": "city", "constraints": {"maxLength": 3}},
+                {"name": "amount", "type": "number", "constraints": {"maximum": 1000}}
+            ]
+        }"#).unwrap();
+        let german = LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+        };
+        let headers = strings(&["city", "amount"]);
+        let rows = vec![strings(&["東京都", "999,5"]), strings(&["大阪府市", "1.234,56"])];
+
+        let result = schema.validate(&headers, &rows, &german, None);
+        let failures: Vec<(usize, ValidationRuleType)> =
+            result.errors.iter().map(|e| (e.row_index, e.rule_type.clone())).collect();
+        assert_eq!(failures, vec![(1, ValidationRuleType::Length), (1, ValidationRuleType::Range)]);
+    }
+}
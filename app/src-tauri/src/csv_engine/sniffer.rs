@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+use crate::csv_engine::data_types::{DataType, DataTypeDetector};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelimiterCandidate {
+    pub delimiter: String,
+    /// Most common number of fields per row with this delimiter
+    pub field_count: usize,
+    /// Share of sample rows that have exactly `field_count` fields
+    pub consistency: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SniffResult {
+    pub delimiter: String,
+    pub delimiter_confidence: f64,
+    pub has_headers: bool,
+    pub header_confidence: f64,
+    pub candidates: Vec<DelimiterCandidate>,
+}
+
+pub struct Sniffer {
+    candidates: Vec<String>,
+    quote: u8,
+    comment: Option<u8>,
+    sample_rows: usize,
+}
+
+impl Default for Sniffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sniffer {
+    pub fn new() -> Self {
+        Self {
+            candidates: vec![",", "\t", ";", "|", "^"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            quote: b'"',
+            comment: None,
+            sample_rows: 50,
+        }
+    }
+
+    /// Also consider these delimiters, which may be longer than one character
+    pub fn with_extra_candidates(mut self, extra: &[String]) -> Self {
+        for delimiter in extra {
+            if !delimiter.is_empty() && !self.candidates.contains(delimiter) {
+                self.candidates.push(delimiter.clone());
+            }
+        }
+        self
+    }
+
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Skip lines starting with `comment`, as the reader does
+    pub fn with_comment(mut self, comment: Option<u8>) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    pub fn with_sample_rows(mut self, sample_rows: usize) -> Self {
+        self.sample_rows = sample_rows;
+        self
+    }
+
+    /// Sniff delimiter and header presence from the beginning of a file.
+    /// `complete` should be false when `text` was cut off mid-file, so the
+    /// last (possibly partial) record is ignored.
+    pub fn sniff(&self, text: &str, complete: bool) -> SniffResult {
+        let mut candidates: Vec<DelimiterCandidate> = self
+            .candidates
+            .iter()
+            .map(|delimiter| {
+                let mut rows = split_records(text, delimiter, self.quote, self.comment, self.sample_rows + 1);
+                if !complete && rows.len() > 1 {
+                    rows.pop();
+                }
+                rows.truncate(self.sample_rows);
+                Self::evaluate(delimiter, &rows)
+            })
+            .collect();
+
+        // Highest consistency wins. Among equally consistent candidates a
+        // longer delimiter beats its own prefix (`||` over `|`), then the
+        // one that splits rows into more fields is the more plausible one
+        candidates.sort_by(|a, b| {
+            Self::score(b)
+                .partial_cmp(&Self::score(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.delimiter.len().cmp(&a.delimiter.len()))
+                .then(b.field_count.cmp(&a.field_count))
+        });
+
+        let best = candidates.first().cloned();
+        let delimiter = best
+            .as_ref()
+            .filter(|c| c.field_count > 1)
+            .map(|c| c.delimiter.clone())
+            .unwrap_or_else(|| ",".to_string());
+
+        // A strong runner-up makes the choice less certain. Pieces of the
+        // chosen delimiter are not real alternatives, so they are skipped
+        let runner_up = candidates
+            .iter()
+            .skip(1)
+            .find(|c| !delimiter.contains(c.delimiter.as_str()));
+        let delimiter_confidence = match (best, runner_up) {
+            (Some(best), _) if best.field_count <= 1 => 0.0,
+            (Some(best), Some(runner_up)) => {
+                Self::score(&best) * (1.0 - 0.5 * Self::score(runner_up))
+            }
+            (Some(best), None) => Self::score(&best),
+            _ => 0.0,
+        };
+
+        let mut rows = split_records(text, &delimiter, self.quote, self.comment, self.sample_rows + 1);
+        if !complete && rows.len() > 1 {
+            rows.pop();
+        }
+        rows.truncate(self.sample_rows);
+        let (has_headers, header_confidence) = detect_headers(&rows);
+
+        SniffResult {
+            delimiter,
+            delimiter_confidence,
+            has_headers,
+            header_confidence,
+            candidates,
+        }
+    }
+
+    fn evaluate(delimiter: &str, rows: &[Vec<String>]) -> DelimiterCandidate {
+        let mut counts: std::collections::HashMap<usize, usize> = std::collections::HashMap::new();
+        for row in rows {
+            *counts.entry(row.len()).or_insert(0) += 1;
+        }
+
+        let (field_count, matching) = counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)))
+            .unwrap_or((0, 0));
+
+        let consistency = if rows.is_empty() {
+            0.0
+        } else {
+            matching as f64 / rows.len() as f64
+        };
+
+        DelimiterCandidate {
+            delimiter: delimiter.to_string(),
+            field_count,
+            consistency,
+        }
+    }
+
+    fn score(candidate: &DelimiterCandidate) -> f64 {
+        if candidate.field_count > 1 {
+            candidate.consistency
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Split text into records, honoring quoted fields that contain the
+/// delimiter or line breaks and skipping lines that start with `comment`.
+/// Stops after `limit` records.
+pub fn split_records(text: &str, delimiter: &str, quote: u8, comment: Option<u8>, limit: usize) -> Vec<Vec<String>> {
+    split_records_with_offsets(text, delimiter, quote, comment, limit)
+        .0
+        .into_iter()
+        .map(|(_, record)| record)
+        .collect()
+}
+
+/// Like `split_records`, but also returns the byte offset at which each
+/// record starts and whether the text ended inside an open quote.
+pub fn split_records_with_offsets(
+    text: &str,
+    delimiter: &str,
+    quote: u8,
+    comment: Option<u8>,
+    limit: usize,
+) -> (Vec<(usize, Vec<String>)>, bool) {
+    let bytes = text.as_bytes();
+    let delim = delimiter.as_bytes();
+    let mut records = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field: Vec<u8> = Vec::new();
+    let mut in_quotes = false;
+    let mut record_start = 0;
+    let mut i = 0;
+
+    if delim.is_empty() {
+        return (records, false);
+    }
+
+    while i < bytes.len() && records.len() < limit {
+        let b = bytes[i];
+
+        if in_quotes {
+            if b == quote {
+                if bytes.get(i + 1) == Some(&quote) {
+                    field.push(quote);
+                    i += 2;
+                    continue;
+                }
+                in_quotes = false;
+            } else {
+                field.push(b);
+            }
+            i += 1;
+            continue;
+        }
+
+        if i == record_start && Some(b) == comment {
+            i = bytes[i..].iter().position(|&c| c == b'\n').map_or(bytes.len(), |end| i + end + 1);
+            record_start = i;
+        } else if b == quote && field.is_empty() {
+            in_quotes = true;
+            i += 1;
+        } else if bytes[i..].starts_with(delim) {
+            record.push(String::from_utf8_lossy(&field).into_owned());
+            field.clear();
+            i += delim.len();
+        } else if b == b'\n' || b == b'\r' {
+            if b == b'\r' && bytes.get(i + 1) == Some(&b'\n') {
+                i += 1;
+            }
+            i += 1;
+            if record.is_empty() && field.is_empty() {
+                record_start = i;
+                continue; // Blank line
+            }
+            record.push(String::from_utf8_lossy(&field).into_owned());
+            field.clear();
+            records.push((record_start, std::mem::take(&mut record)));
+            record_start = i;
+        } else {
+            field.push(b);
+            i += 1;
+        }
+    }
+
+    if records.len() < limit && (!record.is_empty() || !field.is_empty() || in_quotes) {
+        record.push(String::from_utf8_lossy(&field).into_owned());
+        records.push((record_start, record));
+    }
+
+    (records, in_quotes)
+}
+
+/// Decide whether the first row is a header by comparing the type of each of
+/// its cells with the dominant type of the same column in the remaining rows.
+/// Returns the decision and a confidence between 0.5 and 1.0.
+pub fn detect_headers(rows: &[Vec<String>]) -> (bool, f64) {
+    let (first, rest) = match rows.split_first() {
+        Some((first, rest)) if !rest.is_empty() => (first, rest),
+        _ => return (true, 0.5),
+    };
+
+    let detector = DataTypeDetector::new();
+    let mut total = 0.0;
+
+    for (col_idx, value) in first.iter().enumerate() {
+        let value = value.trim();
+        let column: Vec<&str> = rest
+            .iter()
+            .filter_map(|row| row.get(col_idx))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .collect();
+
+        total += if value.is_empty() {
+            -1.0 // Header cells are rarely blank
+        } else {
+            let first_type = detector.detect_value_type(value);
+            let column_values: Vec<String> = column.iter().map(|v| v.to_string()).collect();
+            let column_type = detector.detect_column_type(&column_values);
+
+            if matches!(first_type, DataType::Integer | DataType::Float) {
+                -1.0
+            } else if column_type != DataType::Text {
+                if first_type == column_type { -1.0 } else { 1.0 }
+            } else if column.contains(&value) {
+                -0.5
+            } else {
+                let lengths: Vec<usize> = column.iter().map(|v| v.chars().count()).collect();
+                let min = lengths.iter().min().copied().unwrap_or(0);
+                let max = lengths.iter().max().copied().unwrap_or(0);
+                let len = value.chars().count();
+                if len < min || len > max { 0.5 } else { 0.0 }
+            }
+        };
+    }
+
+    // Repeated names in the first row make it unlikely to be a header
+    let mut distinct = std::collections::HashSet::new();
+    if first.iter().any(|v| !v.trim().is_empty() && !distinct.insert(v.trim())) {
+        total -= 1.0;
+    }
+
+    let score = if first.is_empty() { 0.0 } else { total / first.len() as f64 };
+    let score = score.clamp(-1.0, 1.0);
+
+    (score >= 0.0, 0.5 + score.abs() / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delimiter_ignores_quoted_commas() {
+        let text = "name;note\n\"Smith, J\";\"a, b, c\"\n\"Doe, K\";\"x, y\"\n";
+        let result = Sniffer::new().sniff(text, true);
+        assert_eq!(result.delimiter, ";");
+        assert!(result.delimiter_confidence > 0.5);
+    }
+
+    #[test]
+    fn test_multi_char_delimiter() {
+        let text = "a||b||c\n1||2||3\n4||5||6\n";
+        let result = Sniffer::new()
+            .with_extra_candidates(&["||".to_string()])
+            .sniff(text, true);
+        assert_eq!(result.delimiter, "||");
+    }
+
+    #[test]
+    fn test_detect_headers() {
+        let with_header = split_records("id,price,date\n1,9.99,2024-01-01\n2,5.00,2024-02-01\n", ",", b'"', None, 10);
+        assert!(detect_headers(&with_header).0);
+
+        let without_header = split_records("1,9.99,2024-01-01\n2,5.00,2024-02-01\n", ",", b'"', None, 10);
+        let (has_headers, confidence) = detect_headers(&without_header);
+        assert!(!has_headers);
+        assert!(confidence > 0.9);
+    }
+
+    #[test]
+    fn test_split_records_handles_quoted_newlines() {
+        let records = split_records("a,\"line1\nline2\"\r\nb,c", ",", b'"', None, 10);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0][1], "line1\nline2");
+    }
+
+    #[test]
+    fn test_comment_lines_are_skipped() {
+        let text = "# exported 2024-01-01, by the nightly job, with notes\nid;name\n1;Ann\n2;Bob\n";
+        let result = Sniffer::new().with_comment(Some(b'#')).sniff(text, true);
+        assert_eq!(result.delimiter, ";");
+        assert_eq!(result.delimiter_confidence, 1.0);
+        assert_eq!(split_records(text, ";", b'"', Some(b'#'), 10)[0], vec!["id", "name"]);
+    }
+}
@@ -1,9 +1,11 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::Read;
 use std::path::Path;
 use csv;
 use encoding_rs::{Encoding, UTF_8};
 use anyhow::{Result, Context};
+use crate::csv_engine::compression::{self, Compression};
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::encoding::DecodingReader;
 
 pub struct StreamingReader {
     path: std::path::PathBuf,
@@ -11,6 +13,9 @@ pub struct StreamingReader {
     has_headers: bool,
     encoding: &'static Encoding,
     chunk_size: usize,
+    compression: Option<Compression>,
+    archive_member: Option<String>,
+    dialect: CsvDialect,
 }
 
 impl StreamingReader {
@@ -21,6 +26,9 @@ impl StreamingReader {
             has_headers: true,
             encoding: UTF_8,
             chunk_size: 1000,
+            compression: None,
+            archive_member: None,
+            dialect: CsvDialect::default(),
         }
     }
 
@@ -29,6 +37,11 @@ impl StreamingReader {
         self
     }
 
+    pub fn with_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
     pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
         self.encoding = encoding;
         self
@@ -39,30 +52,52 @@ impl StreamingReader {
         self
     }
 
+    /// Skip detection of the file's compression
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Quoting, comments and empty-row handling, as the file is opened with
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Member to stream when the file is a zip archive
+    pub fn with_archive_member(mut self, member: String) -> Self {
+        self.archive_member = Some(member);
+        self
+    }
+
+    fn compression(&self) -> Result<Compression> {
+        match self.compression {
+            Some(compression) => Ok(compression),
+            None => Compression::detect(&self.path),
+        }
+    }
+
+    fn open(&self) -> Result<Box<dyn Read>> {
+        compression::open(&self.path, self.compression()?, self.archive_member.as_deref())
+            .context("Failed to open file for streaming")
+    }
+
+    /// CSV reader over the decompressed, decoded file, with the dialect the
+    /// file is opened with. Ragged rows are kept as they are.
+    fn csv_reader(&self, has_headers: bool) -> Result<csv::Reader<DecodingReader<Box<dyn Read>>>> {
+        Ok(self.dialect
+            .reader_builder(self.delimiter, has_headers)
+            .flexible(true)
+            .from_reader(DecodingReader::new(self.open()?, self.encoding)))
+    }
+
     pub fn read_headers(&self) -> Result<Vec<String>> {
-        let file = File::open(&self.path)
-            .context("Failed to open file for streaming")?;
-        let reader = BufReader::new(file);
-
-        let mut lines = reader.lines();
-        if let Some(first_line) = lines.next() {
-            let line = first_line?;
-            let decoded = if self.encoding == UTF_8 {
-                line
-            } else {
-                let bytes = line.as_bytes();
-                let (decoded, _, _) = self.encoding.decode(bytes);
-                decoded.to_string()
-            };
-
-            let mut rdr = csv::ReaderBuilder::new()
-                .delimiter(self.delimiter)
-                .has_headers(false)
-                .from_reader(decoded.as_bytes());
-
-            if let Some(result) = rdr.records().next() {
-                let record = result?;
-                return Ok(record.iter().map(|s| s.to_string()).collect());
+        let mut reader = self.csv_reader(false)?;
+        let mut record = csv::StringRecord::new();
+
+        while reader.read_record(&mut record).context("Failed to read CSV record")? {
+            if !self.dialect.should_skip(&record) {
+                return Ok(record.iter().map(|field| field.to_string()).collect());
             }
         }
 
@@ -73,25 +108,22 @@ impl StreamingReader {
     where
         F: FnMut(Vec<Vec<String>>) -> Result<bool>,
     {
-        let file = File::open(&self.path)?;
-        let mut reader = csv::ReaderBuilder::new()
-            .delimiter(self.delimiter)
-            .has_headers(self.has_headers)
-            .from_reader(file);
+        let mut reader = self.csv_reader(self.has_headers)?;
 
         let mut chunk = Vec::with_capacity(self.chunk_size);
+        let mut record = csv::StringRecord::new();
 
-        for result in reader.records() {
-            let record = result?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            chunk.push(row);
+        while reader.read_record(&mut record).context("Failed to read CSV record")? {
+            if self.dialect.should_skip(&record) {
+                continue;
+            }
+            chunk.push(record.iter().map(|field| field.to_string()).collect());
 
             if chunk.len() >= self.chunk_size {
-                let should_continue = callback(chunk.clone())?;
+                let should_continue = callback(std::mem::take(&mut chunk))?;
                 if !should_continue {
                     break;
                 }
-                chunk.clear();
             }
         }
 
@@ -102,30 +134,131 @@ impl StreamingReader {
         Ok(())
     }
 
+    /// Rows `stream_chunks` yields, without keeping them
     pub fn count_rows(&self) -> Result<usize> {
-        let file = File::open(&self.path)?;
-        let reader = BufReader::new(file);
+        let mut reader = self.csv_reader(self.has_headers)?;
+        let mut record = csv::StringRecord::new();
         let mut count = 0;
 
-        for line in reader.lines() {
-            let _ = line?;
-            count += 1;
-        }
-
-        if self.has_headers && count > 0 {
-            count -= 1;
+        while reader.read_record(&mut record).context("Failed to read CSV record")? {
+            if !self.dialect.should_skip(&record) {
+                count += 1;
+            }
         }
 
         Ok(count)
     }
 
+    /// Estimate the memory needed to load the file as `Vec<Vec<String>>`
+    /// from the row width and cell sizes of a sample of records
     pub fn estimate_memory_usage(&self) -> Result<u64> {
-        let metadata = std::fs::metadata(&self.path)?;
-        let file_size = metadata.len();
+        const SAMPLE_RECORDS: u64 = 1000;
+
+        // Compressed files hold more text than their size on disk, so
+        // count the bytes of the decompressed stream
+        let data_size = match self.compression()? {
+            Compression::None => std::fs::metadata(&self.path)?.len(),
+            _ => std::io::copy(&mut self.open()?, &mut std::io::sink())?,
+        };
+
+        let mut reader = self.csv_reader(false)?;
+        let mut record = csv::StringRecord::new();
+        let mut records = 0u64;
+        let mut fields = 0u64;
+        while records < SAMPLE_RECORDS && reader.read_record(&mut record).context("Failed to read CSV record")? {
+            records += 1;
+            fields += record.len() as u64;
+        }
+
+        if records == 0 {
+            return Ok(0);
+        }
+
+        // Rows the file holds at the sampled density, and what each costs:
+        // a Vec header per row, a String header per cell, plus the text
+        let record_bytes = reader.position().byte();
+        let estimated_rows = data_size / (record_bytes / records).max(1);
+        let fields_per_row = fields / records;
+        let row_overhead = std::mem::size_of::<Vec<String>>() as u64
+            + fields_per_row * std::mem::size_of::<String>() as u64;
+
+        Ok(estimated_rows * row_overhead + data_size)
+    }
+}
 
-        let overhead_factor = 2;
-        let estimated_memory = file_size * overhead_factor;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(estimated_memory)
+    #[test]
+    fn test_stream_chunks_reads_with_the_dialect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        std::fs::write(&path, "# exported\nid;name\n1;'a;b'\n\n2;c;extra\n").unwrap();
+
+        let dialect = CsvDialect {
+            quote_char: "'".to_string(),
+            comment_prefix: "#".to_string(),
+            skip_empty_rows: true,
+            ..CsvDialect::default()
+        };
+        let mut rows = Vec::new();
+        StreamingReader::new(&path)
+            .with_delimiter(b';')
+            .with_headers(false)
+            .with_dialect(dialect)
+            .with_chunk_size(2)
+            .stream_chunks(|chunk| {
+                rows.extend(chunk);
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(rows, vec![
+            vec!["id", "name"],
+            vec!["1", "a;b"],
+            vec!["2", "c", "extra"],
+        ]);
+    }
+
+    #[test]
+    fn test_headers_and_row_count_follow_the_dialect() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        std::fs::write(&path, "# exported\nid;name\n1;'a\nb'\n\n2;c\n").unwrap();
+
+        let reader = StreamingReader::new(&path)
+            .with_delimiter(b';')
+            .with_dialect(CsvDialect {
+                quote_char: "'".to_string(),
+                comment_prefix: "#".to_string(),
+                skip_empty_rows: true,
+                ..CsvDialect::default()
+            });
+
+        assert_eq!(reader.read_headers().unwrap(), vec!["id", "name"]);
+        assert_eq!(reader.count_rows().unwrap(), 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_memory_estimate_uses_the_decompressed_size() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let csv = "id,name\n".to_string() + &"1,aaaaaaaaaa\n".repeat(10_000);
+        let plain = dir.path().join("data.csv");
+        std::fs::write(&plain, &csv).unwrap();
+        let gzipped = dir.path().join("data.csv.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(&gzipped).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(csv.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let plain_estimate = StreamingReader::new(&plain).estimate_memory_usage().unwrap();
+        let gzipped_estimate = StreamingReader::new(&gzipped).estimate_memory_usage().unwrap();
+        assert!(std::fs::metadata(&gzipped).unwrap().len() < csv.len() as u64 / 10);
+        assert_eq!(gzipped_estimate, plain_estimate);
+    }
+}
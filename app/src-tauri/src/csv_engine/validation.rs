@@ -1,16 +1,41 @@
 use serde::{Deserialize, Serialize};
 use regex::Regex;
-use std::collections::HashMap;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use super::data_types::{ColumnTypeOverride, DataType, DataTypeDetector};
+use super::expression::Expression;
+use super::locale::LocaleFormat;
+use super::reader::CsvReader;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ValidationRuleType {
     Range,
     Length,
     Pattern,
+    /// Rows must satisfy the `expression` parameter, e.g.
+    /// `status == "shipped" -> tracking_no != ""`; see `Expression`
     Custom,
     Required,
     Unique,
+    /// Values must read as the `type` parameter, a lowercase `DataType`,
+    /// with an optional strftime `format` for dates
+    Type,
+    /// Values must be one of the `values` parameter, a JSON array of
+    /// strings, and/or the lines of `values_file`. `ignore_case` = `true`
+    /// compares without case.
+    Enum,
+    /// The `columns` parameter, a JSON array of column names, must be
+    /// unique together
+    CompositeUnique,
+    /// The column must not be empty in rows satisfying the `condition`
+    /// expression, e.g. `country == "JP"`
+    RequiredIf,
+    /// Values of `columns` (a JSON array, default this column) must appear in
+    /// `reference_columns` (default the same names) of the CSV `file`, or
+    /// of this data when `file` is empty
+    ForeignKey,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,7 +47,7 @@ pub struct ValidationRule {
     pub error_message: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ValidationError {
     pub row_index: usize,
     pub column_index: usize,
@@ -30,15 +55,175 @@ pub struct ValidationError {
     pub value: String,
     pub rule_type: ValidationRuleType,
     pub message: String,
+    /// The rule itself could not be applied, e.g. its expression does not
+    /// parse; `row_index` then refers to no row
+    #[serde(default)]
+    pub rule_error: bool,
 }
 
 pub struct Validator {
     rules: Vec<ValidationRule>,
+    base_dir: Option<PathBuf>,
+    /// Keys read from referenced files and values read from `values_file`,
+    /// or why they could not be read, so a validator kept for live
+    /// validation reads each file once
+    references: Mutex<HashMap<(PathBuf, Vec<String>), ReferencedKeys>>,
+    value_files: Mutex<HashMap<PathBuf, FileValues>>,
+    locale: LocaleFormat,
+}
+
+/// Keys of the referenced columns, `None` when a column is missing
+type ReferencedKeys = Result<Arc<Option<HashSet<String>>>, String>;
+
+/// Lines of a `values_file`, or why it could not be read
+type FileValues = Result<Arc<Vec<String>>, String>;
+
+/// Cells of `indices` joined into one key, or `None` when all are empty
+pub(crate) fn composite_key(row: &[String], indices: &[usize]) -> Option<String> {
+    let values: Vec<&str> = indices
+        .iter()
+        .map(|&i| row.get(i).map(|v| v.as_str()).unwrap_or(""))
+        .collect();
+    (!values.iter().all(|v| v.is_empty())).then(|| values.join("\u{1f}"))
 }
 
 impl Validator {
     pub fn new(rules: Vec<ValidationRule>) -> Self {
-        Self { rules }
+        Self {
+            rules,
+            base_dir: None,
+            references: Mutex::new(HashMap::new()),
+            value_files: Mutex::new(HashMap::new()),
+            locale: LocaleFormat::default(),
+        }
+    }
+
+    /// Number and date format values are read with, normally the file's
+    pub fn with_locale(mut self, locale: LocaleFormat) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Directory relative `file` and `values_file` parameters are read from
+    pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    fn resolve(&self, file: &str) -> PathBuf {
+        match &self.base_dir {
+            Some(dir) if Path::new(file).is_relative() => dir.join(file),
+            _ => PathBuf::from(file),
+        }
+    }
+
+    /// A parameter holding a JSON array of strings
+    pub(crate) fn list_parameter(rule: &ValidationRule, key: &str) -> Option<Vec<String>> {
+        rule.parameters.get(key).and_then(|v| serde_json::from_str(v).ok())
+    }
+
+    /// Indices of the named columns, `None` if any is missing
+    pub(crate) fn column_indices(names: &[String], headers: &[String]) -> Option<Vec<usize>> {
+        names.iter().map(|name| headers.iter().position(|h| h == name)).collect()
+    }
+
+    /// Keys of `columns` in the CSV `file`
+    fn referenced_keys(&self, file: &str, columns: &[String]) -> ReferencedKeys {
+        let path = self.resolve(file);
+        let cache_key = (path.clone(), columns.to_vec());
+        if let Some(keys) = self.references.lock().unwrap().get(&cache_key) {
+            return keys.clone();
+        }
+
+        let keys = CsvReader::new().read_file(&path).map_err(|e| e.to_string()).map(|reference| {
+            Arc::new(Self::column_indices(columns, &reference.headers).map(|indices| {
+                reference.rows.iter().filter_map(|row| composite_key(row, &indices)).collect()
+            }))
+        });
+        self.references.lock().unwrap().insert(cache_key, keys.clone());
+        keys
+    }
+
+    /// Non-empty lines of `file`, the allowed values of an `Enum` rule
+    fn file_values(&self, file: &str) -> FileValues {
+        let path = self.resolve(file);
+        if let Some(values) = self.value_files.lock().unwrap().get(&path) {
+            return values.clone();
+        }
+
+        let values = std::fs::read_to_string(&path).map_err(|e| e.to_string()).map(|text| {
+            Arc::new(text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).map(String::from).collect())
+        });
+        self.value_files.lock().unwrap().insert(path, values.clone());
+        values
+    }
+
+    /// Error for a repeated value of a `Unique` or `CompositeUnique` rule
+    pub(crate) fn duplicate_error(
+        rule: &ValidationRule,
+        headers: &[String],
+        row_idx: usize,
+        value: String,
+        first_row: usize,
+    ) -> ValidationError {
+        let message = rule.error_message.clone().unwrap_or_else(|| match rule.rule_type {
+            ValidationRuleType::CompositeUnique => format!(
+                "Duplicate ({}) for ({}) (first seen at row {})",
+                value,
+                Self::list_parameter(rule, "columns").unwrap_or_default().join(", "),
+                first_row + 1
+            ),
+            _ => format!("Duplicate value '{}' (first seen at row {})", value, first_row + 1),
+        });
+
+        ValidationError {
+            row_index: row_idx,
+            column_index: rule.column_index,
+            column_name: headers.get(rule.column_index).cloned().unwrap_or_default(),
+            value,
+            rule_type: rule.rule_type.clone(),
+            message,
+            rule_error: false,
+        }
+    }
+
+    /// Error for a rule that cannot be applied to any row, reported once
+    pub(crate) fn rule_error(rule: &ValidationRule, headers: &[String], message: String) -> ValidationError {
+        ValidationError {
+            row_index: 0,
+            column_index: rule.column_index,
+            column_name: headers.get(rule.column_index).cloned().unwrap_or_default(),
+            value: String::new(),
+            rule_type: rule.rule_type.clone(),
+            message,
+            rule_error: true,
+        }
+    }
+
+    /// Error for a `ForeignKey` value missing from the referenced columns
+    pub(crate) fn missing_reference_error(
+        rule: &ValidationRule,
+        headers: &[String],
+        row_idx: usize,
+        value: String,
+    ) -> ValidationError {
+        let file = rule.parameters.get("file").map(|f| f.as_str()).unwrap_or("");
+        let reference_names = Self::list_parameter(rule, "reference_columns")
+            .or_else(|| Self::list_parameter(rule, "columns"))
+            .unwrap_or_else(|| vec![rule.column_name.clone()]);
+        let message = rule.error_message.clone().unwrap_or_else(|| {
+            format!("'{}' not found in {}({})", value, file, reference_names.join(", "))
+        });
+
+        ValidationError {
+            row_index: row_idx,
+            column_index: rule.column_index,
+            column_name: headers.get(rule.column_index).cloned().unwrap_or_default(),
+            value,
+            rule_type: ValidationRuleType::ForeignKey,
+            message,
+            rule_error: false,
+        }
     }
 
     pub fn validate(&self, data: &[Vec<String>], headers: &[String]) -> Vec<ValidationError> {
@@ -61,8 +246,23 @@ impl Validator {
                 ValidationRuleType::Unique => {
                     errors.extend(self.validate_unique(data, headers, rule));
                 }
+                ValidationRuleType::Type => {
+                    errors.extend(self.validate_type(data, headers, rule));
+                }
+                ValidationRuleType::Enum => {
+                    errors.extend(self.validate_enum(data, headers, rule));
+                }
+                ValidationRuleType::CompositeUnique => {
+                    errors.extend(self.validate_composite_unique(data, headers, rule));
+                }
+                ValidationRuleType::RequiredIf => {
+                    errors.extend(self.validate_required_if(data, headers, rule));
+                }
+                ValidationRuleType::ForeignKey => {
+                    errors.extend(self.validate_foreign_key(data, headers, rule));
+                }
                 ValidationRuleType::Custom => {
-                    // Custom rules would be handled by user-defined logic
+                    errors.extend(self.validate_custom(data, headers, rule));
                 }
             }
         }
@@ -84,7 +284,7 @@ impl Validator {
 
         for (row_idx, row) in data.iter().enumerate() {
             if let Some(value) = row.get(col_idx) {
-                if let Ok(num) = value.parse::<f64>() {
+                if let Some(num) = self.locale.parse_number(value) {
                     let mut is_valid = true;
 
                     if let Some(min_val) = min {
@@ -109,6 +309,7 @@ impl Validator {
                             message: rule.error_message.clone().unwrap_or_else(|| {
                                 format!("Value {} is out of range", value)
                             }),
+                            rule_error: false,
                         });
                     }
                 }
@@ -132,7 +333,8 @@ impl Validator {
 
         for (row_idx, row) in data.iter().enumerate() {
             if let Some(value) = row.get(col_idx) {
-                let len = value.len();
+                // In characters, as schema minLength and maxLength count them
+                let len = value.chars().count();
                 let mut is_valid = true;
 
                 if let Some(min) = min_len {
@@ -157,6 +359,7 @@ impl Validator {
                         message: rule.error_message.clone().unwrap_or_else(|| {
                             format!("Length {} is invalid", len)
                         }),
+                        rule_error: false,
                     });
                 }
             }
@@ -188,6 +391,7 @@ impl Validator {
                                 message: rule.error_message.clone().unwrap_or_else(|| {
                                     format!("Value '{}' does not match pattern", value)
                                 }),
+                                rule_error: false,
                             });
                         }
                     }
@@ -219,6 +423,7 @@ impl Validator {
                         message: rule.error_message.clone().unwrap_or_else(|| {
                             "Required field is empty".to_string()
                         }),
+                        rule_error: false,
                     });
                 }
             }
@@ -241,16 +446,7 @@ impl Validator {
             if let Some(value) = row.get(col_idx) {
                 if !value.is_empty() {
                     if let Some(&first_row) = seen.get(value) {
-                        errors.push(ValidationError {
-                            row_index: row_idx,
-                            column_index: col_idx,
-                            column_name: headers.get(col_idx).cloned().unwrap_or_default(),
-                            value: value.clone(),
-                            rule_type: ValidationRuleType::Unique,
-                            message: rule.error_message.clone().unwrap_or_else(|| {
-                                format!("Duplicate value '{}' (first seen at row {})", value, first_row + 1)
-                            }),
-                        });
+                        errors.push(Self::duplicate_error(rule, headers, row_idx, value.clone(), first_row));
                     } else {
                         seen.insert(value.clone(), row_idx);
                     }
@@ -260,6 +456,269 @@ impl Validator {
 
         errors
     }
+
+    fn validate_type(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let col_idx = rule.column_index;
+
+        let data_type = rule
+            .parameters
+            .get("type")
+            .and_then(|t| serde_json::from_value::<DataType>(serde_json::Value::String(t.clone())).ok());
+        let Some(data_type) = data_type else {
+            return errors;
+        };
+
+        let mut expected = ColumnTypeOverride::new(data_type);
+        expected.date_format = rule.parameters.get("format").cloned();
+        let detector = DataTypeDetector::new().with_locale(self.locale.clone());
+
+        for (row_idx, row) in data.iter().enumerate() {
+            if let Some(value) = row.get(col_idx) {
+                if let Some(message) = expected.check(value, &detector) {
+                    errors.push(ValidationError {
+                        row_index: row_idx,
+                        column_index: col_idx,
+                        column_name: headers.get(col_idx).cloned().unwrap_or_default(),
+                        value: value.clone(),
+                        rule_type: ValidationRuleType::Type,
+                        message: rule.error_message.clone().unwrap_or(message),
+                        rule_error: false,
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn validate_custom(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let col_idx = rule.column_index;
+
+        let Some(source) = rule.parameters.get("expression") else {
+            return errors;
+        };
+        let expression = match Expression::compile(source, headers) {
+            Ok(expression) => expression,
+            Err(e) => {
+                errors.push(Self::rule_error(rule, headers, format!("Invalid expression {}: {}", source, e)));
+                return errors;
+            }
+        };
+
+        for (row_idx, row) in data.iter().enumerate() {
+            let message = match expression.evaluate(row, &self.locale) {
+                Ok(true) => continue,
+                Ok(false) => rule.error_message.clone().unwrap_or_else(|| {
+                    format!("Row does not satisfy {}", source)
+                }),
+                Err(e) => format!("Could not evaluate {}: {}", source, e),
+            };
+            errors.push(ValidationError {
+                row_index: row_idx,
+                column_index: col_idx,
+                column_name: headers.get(col_idx).cloned().unwrap_or_default(),
+                value: row.get(col_idx).cloned().unwrap_or_default(),
+                rule_type: ValidationRuleType::Custom,
+                message,
+                rule_error: false,
+            });
+        }
+
+        errors
+    }
+
+    fn validate_enum(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let col_idx = rule.column_index;
+
+        let mut allowed = Self::list_parameter(rule, "values").unwrap_or_default();
+        if let Some(file) = rule.parameters.get("values_file") {
+            match self.file_values(file) {
+                Ok(values) => allowed.extend(values.iter().cloned()),
+                Err(e) => {
+                    errors.push(Self::rule_error(rule, headers, format!("Could not read allowed values from {}: {}", file, e)));
+                    return errors;
+                }
+            }
+        }
+
+        let ignore_case = rule.parameters.get("ignore_case").is_some_and(|v| v == "true");
+        let normalize = |value: &str| if ignore_case { value.to_lowercase() } else { value.to_string() };
+        let allowed_set: HashSet<String> = allowed.iter().map(|v| normalize(v)).collect();
+
+        for (row_idx, row) in data.iter().enumerate() {
+            if let Some(value) = row.get(col_idx) {
+                if !value.is_empty() && !allowed_set.contains(&normalize(value)) {
+                    errors.push(ValidationError {
+                        row_index: row_idx,
+                        column_index: col_idx,
+                        column_name: headers.get(col_idx).cloned().unwrap_or_default(),
+                        value: value.clone(),
+                        rule_type: ValidationRuleType::Enum,
+                        message: rule.error_message.clone().unwrap_or_else(|| {
+                            format!("Value '{}' is not one of: {}", value, allowed.join(", "))
+                        }),
+                        rule_error: false,
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn validate_composite_unique(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        let Some(names) = Self::list_parameter(rule, "columns") else {
+            errors.push(Self::rule_error(rule, headers, "The columns parameter must be a JSON array of column names".to_string()));
+            return errors;
+        };
+        let Some(indices) = Self::column_indices(&names, headers) else {
+            errors.push(Self::rule_error(rule, headers, format!("Columns ({}) not found in this file", names.join(", "))));
+            return errors;
+        };
+        let mut seen = HashMap::new();
+
+        for (row_idx, row) in data.iter().enumerate() {
+            let Some(key) = composite_key(row, &indices) else {
+                continue;
+            };
+            match seen.get(&key) {
+                Some(&first_row) => {
+                    let value = key.replace('\u{1f}', ", ");
+                    errors.push(Self::duplicate_error(rule, headers, row_idx, value, first_row));
+                }
+                None => {
+                    seen.insert(key, row_idx);
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn validate_required_if(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let col_idx = rule.column_index;
+
+        let Some(source) = rule.parameters.get("condition") else {
+            errors.push(Self::rule_error(rule, headers, "The condition parameter is missing".to_string()));
+            return errors;
+        };
+        let condition = match Expression::compile(source, headers) {
+            Ok(condition) => condition,
+            Err(e) => {
+                errors.push(Self::rule_error(rule, headers, format!("Invalid condition {}: {}", source, e)));
+                return errors;
+            }
+        };
+
+        for (row_idx, row) in data.iter().enumerate() {
+            let value = row.get(col_idx).cloned().unwrap_or_default();
+            if !value.trim().is_empty() {
+                continue;
+            }
+
+            let message = match condition.evaluate(row, &self.locale) {
+                Ok(false) => continue,
+                Ok(true) => rule.error_message.clone().unwrap_or_else(|| {
+                    format!("Required when {}", source)
+                }),
+                Err(e) => format!("Could not evaluate {}: {}", source, e),
+            };
+            errors.push(ValidationError {
+                row_index: row_idx,
+                column_index: col_idx,
+                column_name: headers.get(col_idx).cloned().unwrap_or_default(),
+                value,
+                rule_type: ValidationRuleType::RequiredIf,
+                message,
+                rule_error: false,
+            });
+        }
+
+        errors
+    }
+
+    fn validate_foreign_key(
+        &self,
+        data: &[Vec<String>],
+        headers: &[String],
+        rule: &ValidationRule,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        let names = Self::list_parameter(rule, "columns").unwrap_or_else(|| vec![rule.column_name.clone()]);
+        let reference_names = Self::list_parameter(rule, "reference_columns").unwrap_or_else(|| names.clone());
+        let Some(indices) = Self::column_indices(&names, headers) else {
+            errors.push(Self::rule_error(rule, headers, format!("Columns ({}) not found in this file", names.join(", "))));
+            return errors;
+        };
+        let file = rule.parameters.get("file").map(|f| f.as_str()).unwrap_or("");
+
+        // Keys of the referenced columns, from another file or this data
+        let referenced: Arc<Option<HashSet<String>>> = if file.is_empty() {
+            Arc::new(Self::column_indices(&reference_names, headers).map(|ref_indices| {
+                data.iter().filter_map(|row| composite_key(row, &ref_indices)).collect()
+            }))
+        } else {
+            match self.referenced_keys(file, &reference_names) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    errors.push(Self::rule_error(rule, headers, format!("Could not read referenced file {}: {}", file, e)));
+                    return errors;
+                }
+            }
+        };
+        let Some(referenced) = referenced.as_ref() else {
+            let message = format!(
+                "Referenced columns ({}) not found in {}",
+                reference_names.join(", "),
+                if file.is_empty() { "this file" } else { file }
+            );
+            errors.push(Self::rule_error(rule, headers, message));
+            return errors;
+        };
+
+        for (row_idx, row) in data.iter().enumerate() {
+            let Some(key) = composite_key(row, &indices) else {
+                continue;
+            };
+            if !referenced.contains(&key) {
+                errors.push(Self::missing_reference_error(rule, headers, row_idx, key.replace('\u{1f}', ", ")));
+            }
+        }
+
+        errors
+    }
 }
 
 #[cfg(test)]
@@ -335,4 +794,134 @@ mod tests {
         let errors = validator.validate(&data, &headers);
         assert_eq!(errors.len(), 1); // Duplicate "1" at row 2
     }
+
+    #[test]
+    fn test_validate_custom_expression() {
+        let mut params = HashMap::new();
+        params.insert("expression".to_string(), "end_date >= start_date".to_string());
+
+        let rule = ValidationRule {
+            rule_type: ValidationRuleType::Custom,
+            column_index: 1,
+            column_name: "end_date".to_string(),
+            parameters: params,
+            error_message: Some("Ends before it starts".to_string()),
+        };
+
+        let validator = Validator::new(vec![rule]);
+        let data = vec![
+            vec!["2024-01-01".to_string(), "2024-01-31".to_string()],
+            vec!["2024-02-01".to_string(), "2024-01-31".to_string()],
+        ];
+        let headers = vec!["start_date".to_string(), "end_date".to_string()];
+
+        let errors = validator.validate(&data, &headers);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].row_index, 1);
+        assert_eq!(errors[0].message, "Ends before it starts");
+    }
+
+    fn rule(rule_type: ValidationRuleType, column_index: usize, column_name: &str, parameters: &[(&str, &str)]) -> ValidationRule {
+        ValidationRule {
+            rule_type,
+            column_index,
+            column_name: column_name.to_string(),
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            error_message: None,
+        }
+    }
+
+    fn rows(values: &[&[&str]]) -> Vec<Vec<String>> {
+        values.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn test_custom_rule_reports_bad_expression_and_reads_the_locale() {
+        let headers = vec!["amount".to_string()];
+        let data = rows(&[&["1.234,5"], &["12,5"]]);
+
+        let broken = Validator::new(vec![rule(ValidationRuleType::Custom, 0, "amount", &[("expression", "amount >")])]);
+        let errors = broken.validate(&data, &headers);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].rule_error);
+        assert!(errors[0].message.starts_with("Invalid expression amount >"));
+
+        let german = LocaleFormat {
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            date_format: String::new(),
+        };
+        let errors = Validator::new(vec![rule(ValidationRuleType::Custom, 0, "amount", &[("expression", "amount > 100")])])
+            .with_locale(german)
+            .validate(&data, &headers);
+        assert_eq!(errors.iter().map(|e| e.row_index).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_validate_composite_unique_and_required_if() {
+        let headers = vec!["order".to_string(), "line".to_string(), "country".to_string(), "state".to_string()];
+        let data = rows(&[
+            &["1", "1", "US", "CA"],
+            &["1", "2", "JP", ""],
+            &["1", "1", "US", ""],
+        ]);
+
+        let validator = Validator::new(vec![
+            rule(ValidationRuleType::CompositeUnique, 0, "order", &[("columns", r#"["order", "line"]"#)]),
+            rule(ValidationRuleType::RequiredIf, 3, "state", &[("condition", r#"country == "US""#)]),
+            rule(ValidationRuleType::Enum, 2, "country", &[("values", r#"["us", "jp"]"#), ("ignore_case", "true")]),
+        ]);
+
+        let errors = validator.validate(&data, &headers);
+        let found: Vec<(usize, usize, ValidationRuleType)> = errors
+            .iter()
+            .map(|e| (e.row_index, e.column_index, e.rule_type.clone()))
+            .collect();
+        assert_eq!(found, vec![
+            (2, 0, ValidationRuleType::CompositeUnique),
+            (2, 3, ValidationRuleType::RequiredIf),
+        ]);
+        assert_eq!(errors[0].value, "1, 1");
+    }
+
+    #[test]
+    fn test_misconfigured_rules_report_rule_errors() {
+        let headers = vec!["order".to_string(), "state".to_string()];
+        let data = rows(&[&["1", ""], &["1", ""]]);
+
+        let validator = Validator::new(vec![
+            rule(ValidationRuleType::CompositeUnique, 0, "order", &[("columns", r#"["order", "line"]"#)]),
+            rule(ValidationRuleType::CompositeUnique, 0, "order", &[]),
+            rule(ValidationRuleType::RequiredIf, 1, "state", &[]),
+            rule(ValidationRuleType::RequiredIf, 1, "state", &[("condition", "country ==")]),
+            rule(ValidationRuleType::ForeignKey, 0, "order", &[("reference_columns", r#"["id"]"#)]),
+        ]);
+
+        let errors = validator.validate(&data, &headers);
+        assert_eq!(errors.len(), 5);
+        assert!(errors.iter().all(|e| e.rule_error && e.value.is_empty()));
+        assert_eq!(errors[0].message, "Columns (order, line) not found in this file");
+    }
+
+    #[test]
+    fn test_validate_foreign_key_against_another_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("customers.csv"), "id,name\nC1,Ann\nC2,Bob\n").unwrap();
+
+        let headers = vec!["order".to_string(), "customer".to_string()];
+        let data = rows(&[&["1", "C1"], &["2", "C9"], &["3", ""]]);
+
+        let validator = Validator::new(vec![rule(
+            ValidationRuleType::ForeignKey,
+            1,
+            "customer",
+            &[("file", "customers.csv"), ("reference_columns", r#"["id"]"#)],
+        )])
+        .with_base_dir(dir.path());
+
+        let errors = validator.validate(&data, &headers);
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].row_index, errors[0].column_index), (1, 1));
+        assert_eq!(errors[0].value, "C9");
+    }
 }
@@ -1,26 +1,38 @@
-use std::fs::File;
-use std::io::Write;
 use std::path::Path;
-use csv;
 use encoding_rs::{Encoding, UTF_8};
 use anyhow::{Result, Context};
 use crate::csv_engine::reader::CsvData;
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::encoding;
+use crate::csv_engine::compression::{self, Compression};
 
 pub struct CsvWriter {
-    delimiter: u8,
+    delimiter: String,
     encoding: &'static Encoding,
+    dialect: CsvDialect,
+    compression: Compression,
+    archive_member: Option<String>,
 }
 
 impl CsvWriter {
     pub fn new() -> Self {
         Self {
-            delimiter: b',',
+            delimiter: ",".to_string(),
             encoding: UTF_8,
+            dialect: CsvDialect::default(),
+            compression: Compression::None,
+            archive_member: None,
         }
     }
 
     pub fn with_delimiter(mut self, delimiter: u8) -> Self {
-        self.delimiter = delimiter;
+        self.delimiter = (delimiter as char).to_string();
+        self
+    }
+
+    /// Accepts multi-character delimiters such as `||`; empty falls back to `,`
+    pub fn with_delimiter_str(mut self, delimiter: &str) -> Self {
+        self.delimiter = if delimiter.is_empty() { ",".to_string() } else { delimiter.to_string() };
         self
     }
 
@@ -29,61 +41,92 @@ impl CsvWriter {
         self
     }
 
-    pub fn write_file(&self, path: &Path, data: &CsvData) -> Result<()> {
-        let mut wtr = csv::WriterBuilder::new()
-            .delimiter(self.delimiter)
-            .from_writer(vec![]);
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
 
-        if !data.headers.is_empty() {
-            wtr.write_record(&data.headers)
-                .context("Failed to write CSV headers")?;
-        }
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
 
-        for row in &data.rows {
-            wtr.write_record(row)
-                .context("Failed to write CSV row")?;
-        }
+    /// Member to replace when writing into a zip archive; defaults to the
+    /// archive name with a `.csv` extension
+    pub fn with_archive_member(mut self, member: Option<String>) -> Self {
+        self.archive_member = member;
+        self
+    }
 
-        let csv_bytes = wtr.into_inner()
-            .context("Failed to get CSV bytes")?;
+    pub fn write_file(&self, path: &Path, data: &CsvData) -> Result<()> {
+        // Generated column names of a headerless file are not written back
+        let headers = if !data.headers.is_empty() && data.metadata.has_headers {
+            Some(&data.headers)
+        } else {
+            None
+        };
+
+        let csv_bytes = self.format_records(headers.into_iter().chain(data.rows.iter()))?;
 
         let encoded_data = if self.encoding == UTF_8 {
             csv_bytes
         } else {
             let text = String::from_utf8(csv_bytes)
                 .context("Failed to convert CSV to string")?;
-            let (encoded, _, _) = self.encoding.encode(&text);
-            encoded.to_vec()
+            encoding::encode(self.encoding, &text)?
         };
 
-        let mut file = File::create(path)
-            .context("Failed to create output file")?;
-        file.write_all(&encoded_data)
-            .context("Failed to write to file")?;
-
-        Ok(())
+        compression::write(path, self.compression, self.archive_member.as_deref(), &encoded_data)
     }
 
     pub fn append_rows(&self, path: &Path, rows: &[Vec<String>]) -> Result<()> {
-        let file = std::fs::OpenOptions::new()
-            .write(true)
-            .append(true)
-            .open(path)
-            .context("Failed to open file for appending")?;
-
-        let mut wtr = csv::WriterBuilder::new()
-            .delimiter(self.delimiter)
-            .has_headers(false)
-            .from_writer(file);
-
-        for row in rows {
-            wtr.write_record(row)
-                .context("Failed to append row")?;
+        let csv_bytes = self.format_records(rows.iter())?;
+        compression::append(path, self.compression, &csv_bytes)
+    }
+
+    fn format_records<'a>(&self, records: impl Iterator<Item = &'a Vec<String>>) -> Result<Vec<u8>> {
+        if let [delimiter] = self.delimiter.as_bytes() {
+            let mut wtr = self.dialect
+                .writer_builder(*delimiter)
+                .has_headers(false)
+                .from_writer(vec![]);
+
+            for record in records {
+                wtr.write_record(record)
+                    .context("Failed to write CSV row")?;
+            }
+
+            return wtr.into_inner().context("Failed to get CSV bytes");
         }
 
-        wtr.flush()
-            .context("Failed to flush writer")?;
+        // The csv crate only supports single-byte delimiters
+        let quote = self.dialect.quote() as char;
+        let mut content = String::new();
+        for record in records {
+            let fields: Vec<String> = record
+                .iter()
+                .map(|field| {
+                    if field.contains(&self.delimiter)
+                        || field.contains(quote)
+                        || field.contains('\n')
+                        || field.contains('\r')
+                    {
+                        let escaped = if self.dialect.double_quote {
+                            field.replace(quote, &format!("{}{}", quote, quote))
+                        } else {
+                            let escape = self.dialect.escape().map(|e| e as char).unwrap_or('\\');
+                            field.replace(quote, &format!("{}{}", escape, quote))
+                        };
+                        format!("{}{}{}", quote, escaped, quote)
+                    } else {
+                        field.clone()
+                    }
+                })
+                .collect();
+            content.push_str(&fields.join(&self.delimiter));
+            content.push('\n');
+        }
 
-        Ok(())
+        Ok(content.into_bytes())
     }
-}
\ No newline at end of file
+}
@@ -9,8 +9,10 @@ mod settings;
 mod ai;
 mod ai_script;
 mod chat;
+#[cfg(test)]
+mod benches;
 
-use state::{AppStateInner, ScriptExecutorState};
+use state::{AppStateInner, CsvLoadState, ScriptExecutorState};
 use tokio::sync::Mutex;
 use settings::SettingsManager;
 use commands::settings::SettingsState;
@@ -40,11 +42,13 @@ fn main() {
     let app_state = Mutex::new(AppStateInner::new());
     let settings_state = SettingsState(Mutex::new(SettingsManager::new()));
     let script_executor_state = ScriptExecutorState::new();
+    let csv_load_state = CsvLoadState::new();
 
     tauri::Builder::default()
         .manage(app_state)
         .manage(settings_state)
         .manage(script_executor_state)
+        .manage(csv_load_state)
         .setup(|app| {
             // Handle file open from command line arguments (macOS - app not running)
             let args: Vec<String> = std::env::args().collect();
@@ -85,6 +89,17 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::file::open_file_in_new_window,
             commands::csv::open_csv_file,
+            commands::csv::open_fixed_width_file,
+            commands::csv::infer_fixed_width_spec,
+            commands::csv::sniff_csv_file,
+            commands::csv::list_archive_members,
+            commands::csv::start_csv_load,
+            commands::csv::cancel_csv_load,
+            commands::csv::open_csv_file_mapped,
+            commands::csv::get_mapped_rows,
+            commands::csv::update_mapped_cell,
+            commands::csv::detect_mapped_column_types,
+            commands::csv::save_mapped_file,
             commands::csv::parse_csv_from_text,
             commands::csv::save_csv_file,
             commands::csv::save_csv_file_as,
@@ -105,11 +120,33 @@ fn main() {
             commands::csv::sort_csv_data,
             commands::csv::save_sort_state,
             commands::csv::load_sort_state,
+            commands::csv::set_column_type,
+            commands::csv::set_file_locale,
+            commands::csv::load_column_types,
             commands::csv::save_view_state,
             commands::csv::load_view_state,
             commands::csv::move_row,
             commands::csv::move_column,
             commands::csv::validate_with_rules,
+            commands::csv::start_live_validation,
+            commands::csv::live_validate_cell,
+            commands::csv::stop_live_validation,
+            commands::csv::export_validation_report,
+            commands::csv::export_failing_rows,
+            commands::csv::set_quality_config,
+            commands::csv::export_quality_report,
+            commands::csv::record_quality_snapshot,
+            commands::csv::get_quality_trend,
+            commands::csv::profile_columns,
+            commands::csv::profile_file,
+            commands::csv::find_fuzzy_duplicates,
+            commands::csv::merge_fuzzy_duplicates,
+            commands::csv::check_rule_expression,
+            commands::csv::load_schema,
+            commands::csv::save_schema,
+            commands::csv::set_schema_path,
+            commands::csv::generate_schema_draft,
+            commands::csv::validate_with_schema,
             commands::csv::generate_quality_report,
             commands::csv::cleanse_data,
             commands::csv::export_data,
@@ -6,6 +6,13 @@ use anyhow::Result;
 use chrono;
 use crate::commands::csv::SortState;
 use crate::chat::ChatHistory;
+use crate::csv_engine::dialect::CsvDialect;
+use crate::csv_engine::fixed_width::FixedWidthSpec;
+use crate::csv_engine::compression::Compression;
+use crate::csv_engine::data_types::ColumnTypeOverride;
+use crate::csv_engine::locale::LocaleFormat;
+use crate::csv_engine::quality::QualityConfig;
+use crate::csv_engine::quality_trend::QualitySnapshot;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViewState {
@@ -42,6 +49,31 @@ pub struct CsvMetadata {
     pub view_state: Option<ViewState>,
     #[serde(default)]
     pub chat_history: Option<ChatHistory>,
+    #[serde(default)]
+    pub fixed_width_spec: Option<FixedWidthSpec>,
+    #[serde(default)]
+    pub dialect: Option<CsvDialect>,
+    #[serde(default)]
+    pub compression: Compression,
+    /// CSV member read from and written back to when the file is a zip archive
+    #[serde(default)]
+    pub archive_member: Option<String>,
+    /// Types declared by the user, keyed by column name
+    #[serde(default)]
+    pub column_types: HashMap<String, ColumnTypeOverride>,
+    /// Number and date format of this file, overriding the app settings
+    #[serde(default)]
+    pub locale: Option<LocaleFormat>,
+    /// Schema file the data is validated against on open and save,
+    /// relative to the CSV file unless absolute
+    #[serde(default)]
+    pub schema_path: Option<String>,
+    /// Weights and thresholds quality reports for this file are scored with
+    #[serde(default)]
+    pub quality_config: Option<QualityConfig>,
+    /// Quality of past versions of the file, oldest first
+    #[serde(default)]
+    pub quality_history: Vec<QualitySnapshot>,
 }
 
 impl CsvMetadata {
@@ -71,6 +103,15 @@ impl CsvMetadata {
             sort_state: None,
             view_state: None,
             chat_history: None,
+            fixed_width_spec: None,
+            dialect: None,
+            compression: Compression::None,
+            archive_member: None,
+            column_types: HashMap::new(),
+            locale: None,
+            schema_path: None,
+            quality_config: None,
+            quality_history: Vec::new(),
         })
     }
 
@@ -93,8 +134,56 @@ impl CsvMetadata {
             sort_state: None,
             view_state: None,
             chat_history: None,
+            fixed_width_spec: None,
+            dialect: None,
+            compression: Compression::None,
+            archive_member: None,
+            column_types: HashMap::new(),
+            locale: None,
+            schema_path: None,
+            quality_config: None,
+            quality_history: Vec::new(),
         }
     }
+
+    /// Declared type of each column in `headers`, `None` where the type is detected
+    pub fn column_type_overrides(&self, headers: &[String]) -> Vec<Option<ColumnTypeOverride>> {
+        headers
+            .iter()
+            .map(|header| self.column_types.get(header).cloned())
+            .collect()
+    }
+
+    /// Take over the settings commands write to the `.csvmeta` file
+    /// directly, such as declared types, the locale, the schema and the
+    /// quality config and history, so saving the editor's copy does not
+    /// put back what it was opened with. `headers` are the columns being written; a
+    /// declaration whose column was renamed in the editor moves with it
+    /// and one whose column was deleted is dropped.
+    pub fn keep_saved_settings(&mut self, saved: CsvMetadata, headers: &[String]) {
+        let mut column_types = HashMap::new();
+        for (name, column_type) in saved.column_types.iter() {
+            if headers.contains(name) || self.column_types.contains_key(name) {
+                column_types.insert(name.clone(), column_type.clone());
+                continue;
+            }
+
+            let renamed = self.column_types.iter().find(|(new_name, new_type)| {
+                *new_type == column_type
+                    && headers.contains(new_name)
+                    && !saved.column_types.contains_key(*new_name)
+                    && !column_types.contains_key(*new_name)
+            });
+            if let Some((new_name, _)) = renamed {
+                column_types.insert(new_name.clone(), column_type.clone());
+            }
+        }
+        self.column_types = column_types;
+        self.locale = saved.locale;
+        self.schema_path = saved.schema_path;
+        self.quality_config = saved.quality_config;
+        self.quality_history = saved.quality_history;
+    }
 }
 
 pub struct MetadataManager {
@@ -138,6 +227,15 @@ impl MetadataManager {
         Ok(())
     }
 
+    /// Save the editor's copy of a file's metadata, keeping what commands
+    /// stored since it was opened (see `CsvMetadata::keep_saved_settings`)
+    pub fn save_editor_metadata(&mut self, csv_path: &Path, metadata: &mut CsvMetadata, headers: &[String]) -> Result<()> {
+        if let Ok(saved) = self.load_metadata(csv_path) {
+            metadata.keep_saved_settings(saved, headers);
+        }
+        self.save_metadata(csv_path, metadata)
+    }
+
     fn get_metadata_path(csv_path: &Path) -> std::path::PathBuf {
         let mut meta_path = csv_path.to_path_buf();
         let extension = format!("{}.csvmeta", csv_path.extension().unwrap_or_default().to_string_lossy());
@@ -194,4 +292,75 @@ impl MetadataManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csv_engine::data_types::DataType;
+    use crate::csv_engine::quality_trend::{quality_trend, record_snapshot, ColumnSnapshot, TrendThresholds};
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_saving_keeps_declared_types_and_follows_renames() {
+        let mut saved = CsvMetadata::from_pasted_data();
+        saved.column_types.insert("id".to_string(), ColumnTypeOverride::new(DataType::Integer));
+        saved.column_types.insert("price".to_string(), ColumnTypeOverride::new(DataType::Float));
+        saved.column_types.insert("note".to_string(), ColumnTypeOverride::new(DataType::Text));
+        saved.column_types.insert("when".to_string(), ColumnTypeOverride::new(DataType::Date));
+
+        // Opened before `when` was declared and `flag` was cleared; since
+        // then `price` was renamed to `amount` and `note` deleted
+        let mut editor = CsvMetadata::from_pasted_data();
+        editor.column_types.insert("id".to_string(), ColumnTypeOverride::new(DataType::Integer));
+        editor.column_types.insert("amount".to_string(), ColumnTypeOverride::new(DataType::Float));
+        editor.column_types.insert("flag".to_string(), ColumnTypeOverride::new(DataType::Boolean));
+
+        saved.quality_config = Some(QualityConfig::default());
+        editor.keep_saved_settings(saved, &headers(&["id", "amount", "when", "flag"]));
+        assert!(editor.quality_config.is_some());
+
+        let mut names: Vec<&String> = editor.column_types.keys().collect();
+        names.sort();
+        assert_eq!(names, ["amount", "id", "when"]);
+        assert_eq!(editor.column_types["amount"].data_type, DataType::Float);
+    }
+
+    #[test]
+    fn test_quality_trend_survives_saving_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "id\n1\n").unwrap();
+
+        let mut manager = MetadataManager::new();
+        let mut editor = manager.load_metadata(&path).unwrap();
+
+        // Recorded after the file was opened, as `record_quality_snapshot` does
+        let mut metadata = manager.load_metadata(&path).unwrap();
+        record_snapshot(&mut metadata.quality_history, QualitySnapshot {
+            taken_at: String::new(),
+            file_modified: String::new(),
+            file_size: 5,
+            content_hash: "a".to_string(),
+            total_rows: 1,
+            score: Some(1.0),
+            duplicates: 0,
+            columns: vec![ColumnSnapshot {
+                column_name: "id".to_string(),
+                completeness: 1.0,
+                distinct_count: 1,
+                type_consistency: 1.0,
+                outlier_count: 0,
+            }],
+        });
+        manager.save_metadata(&path, &metadata).unwrap();
+
+        manager.save_editor_metadata(&path, &mut editor, &headers(&["id"])).unwrap();
+
+        let history = manager.load_metadata(&path).unwrap().quality_history;
+        let trend = quality_trend(&history, &TrendThresholds::default());
+        assert_eq!(trend.columns[0].points.len(), 1);
+    }
+}
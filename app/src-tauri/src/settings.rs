@@ -11,6 +11,10 @@ pub struct ImportExportSettings {
     pub skip_empty_rows: bool,
     pub quote_character: String,
     pub escape_character: String,
+    #[serde(default = "default_double_quote")]
+    pub double_quote: bool,
+    #[serde(default)]
+    pub comment_character: String,
     pub date_format: String,
     pub datetime_format: String,
     pub decimal_separator: String,
@@ -21,6 +25,10 @@ pub struct ImportExportSettings {
     pub backup_directory: Option<String>,
 }
 
+fn default_double_quote() -> bool {
+    true
+}
+
 impl Default for ImportExportSettings {
     fn default() -> Self {
         Self {
@@ -31,6 +39,8 @@ impl Default for ImportExportSettings {
             skip_empty_rows: false,
             quote_character: "\"".to_string(),
             escape_character: "\\".to_string(),
+            double_quote: true,
+            comment_character: String::new(),
             date_format: "%Y-%m-%d".to_string(),
             datetime_format: "%Y-%m-%d %H:%M:%S".to_string(),
             decimal_separator: ".".to_string(),
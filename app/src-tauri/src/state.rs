@@ -1,7 +1,12 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use tokio::sync::Mutex;
 use crate::metadata::MetadataManager;
 use crate::ai_script::executor::ScriptExecutor;
+use crate::csv_engine::column_store::ColumnStore;
+use crate::csv_engine::live_validation::LiveValidator;
 
 #[derive(Clone)]
 pub struct CsvData {
@@ -14,6 +19,10 @@ pub struct AppStateInner {
     pub metadata_manager: MetadataManager,
     pub csv_data: Option<CsvData>,
     pub has_unsaved_changes: bool,
+    /// Memory-mapped backend used instead of `csv_data` for very large files
+    pub column_store: Option<ColumnStore>,
+    /// Rules registered for the open document, re-checked as cells change
+    pub live_validator: Option<LiveValidator>,
 }
 
 // Keep the same type alias pattern for backwards compatibility
@@ -26,6 +35,8 @@ impl AppStateInner {
             metadata_manager: MetadataManager::new(),
             csv_data: None,
             has_unsaved_changes: false,
+            column_store: None,
+            live_validator: None,
         }
     }
 }
@@ -37,4 +48,19 @@ impl ScriptExecutorState {
     pub fn new() -> Self {
         Self(Mutex::new(ScriptExecutor::new()))
     }
-}
\ No newline at end of file
+}
+
+// Cancellation flags of files being loaded in the background, by load id
+pub struct CsvLoadState(pub Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>);
+
+impl CsvLoadState {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(HashMap::new())))
+    }
+}
+
+impl Default for CsvLoadState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -73,6 +73,7 @@ export function AiAssistant() {
           headers: data.headers,
           rows: data.rows,
           maxRows: 10000,
+          metadata: data.metadata,
         },
       }) as any;
 
@@ -111,6 +112,7 @@ export function AiAssistant() {
           headers: data.headers,
           rows: data.rows,
           maxRows: 10000,
+          metadata: data.metadata,
         },
       }) as any;
 
@@ -24,7 +24,8 @@ import {
   TableRow,
 } from '@/components/ui/table';
 import { Loader2, AlertCircle, CheckCircle2, Plus, Trash2 } from 'lucide-react';
-import type { CsvData } from '@/types/csv';
+import type { CsvData, ValidationRule, ValidationError } from '@/types/csv';
+import { useCsvStore } from '../store/csvStore';
 
 interface CustomValidationProps {
   isOpen: boolean;
@@ -32,23 +33,6 @@ interface CustomValidationProps {
   csvData: CsvData;
 }
 
-interface ValidationRule {
-  rule_type: 'range' | 'length' | 'pattern' | 'required' | 'unique';
-  column_index: number;
-  column_name: string;
-  parameters: Record<string, string>;
-  error_message?: string;
-}
-
-interface ValidationError {
-  row_index: number;
-  column_index: number;
-  column_name: string;
-  value: string;
-  rule_type: string;
-  message: string;
-}
-
 export const CustomValidation: React.FC<CustomValidationProps> = ({
   isOpen,
   onClose,
@@ -58,6 +42,7 @@ export const CustomValidation: React.FC<CustomValidationProps> = ({
   const [errors, setErrors] = useState<ValidationError[]>([]);
   const [isValidating, setIsValidating] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const { liveValidationRules, startLiveValidation, stopLiveValidation } = useCsvStore();
 
   // New rule form state
   const [selectedColumn, setSelectedColumn] = useState<number>(0);
@@ -130,6 +115,28 @@ export const CustomValidation: React.FC<CustomValidationProps> = ({
     }
   };
 
+  // Keep the rules applied while editing, highlighting failing cells in the grid
+  const handleLiveValidation = async () => {
+    if (liveValidationRules) {
+      await stopLiveValidation();
+      return;
+    }
+    if (rules.length === 0) {
+      setError('Please add at least one validation rule');
+      return;
+    }
+
+    setIsValidating(true);
+    setError(null);
+    try {
+      setErrors(await startLiveValidation(rules));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to start live validation');
+    } finally {
+      setIsValidating(false);
+    }
+  };
+
   const getRuleTypeLabel = (type: string): string => {
     const labels: Record<string, string> = {
       range: 'Range',
@@ -345,6 +352,14 @@ export const CustomValidation: React.FC<CustomValidationProps> = ({
                 'Validate Data'
               )}
             </Button>
+            <Button
+              variant="outline"
+              onClick={handleLiveValidation}
+              disabled={isValidating || (!liveValidationRules && rules.length === 0)}
+              className="w-full"
+            >
+              {liveValidationRules ? 'Stop Highlighting Errors' : 'Highlight Errors While Editing'}
+            </Button>
 
             {/* Error Alert */}
             {error && (
@@ -390,7 +405,7 @@ export const CustomValidation: React.FC<CustomValidationProps> = ({
                       <TableBody>
                         {errors.slice(0, 100).map((err, idx) => (
                           <TableRow key={idx}>
-                            <TableCell>{err.row_index + 1}</TableCell>
+                            <TableCell>{err.rule_error ? '—' : err.row_index + 1}</TableCell>
                             <TableCell>{err.column_name}</TableCell>
                             <TableCell className="font-mono text-xs">{err.value}</TableCell>
                             <TableCell>
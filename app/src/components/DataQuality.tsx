@@ -142,6 +142,8 @@ export const DataQuality: React.FC<DataQualityProps> = ({
   };
 
   const handleRemoveOutliers = async () => {
+    if (!report) return;
+    const columns = [...new Set(report.outliers.outlier_details.map((o) => o.column_index))];
     setIsCleansing(true);
     setError(null);
     try {
@@ -149,7 +151,7 @@ export const DataQuality: React.FC<DataQualityProps> = ({
         data: csvData,
         options: {
           action: 'removeoutliers',
-          column_indices: null,
+          column_indices: columns,
           parameters: {},
         },
       });
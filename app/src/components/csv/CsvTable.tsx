@@ -1,5 +1,6 @@
 import React, { useEffect, useRef, useState } from "react";
 import { useVirtualizer } from "@tanstack/react-virtual";
+import { listen } from "@tauri-apps/api/event";
 import { useCsvStore } from "../../store/csvStore";
 import { cn } from "../../lib/utils";
 import { ColumnMenu } from "../ColumnMenu";
@@ -12,6 +13,7 @@ import { useDragAndDrop } from "../../hooks/useDragAndDrop";
 import { Button } from "../ui/Button";
 import { Input } from "../ui/input";
 import { Plus } from "lucide-react";
+import type { ValidationDiff } from "../../types/csv";
 import styles from "./CsvTable.module.css";
 
 export function CsvTable() {
@@ -56,6 +58,8 @@ export function CsvTable() {
     searchResults,
     currentSearchIndex,
     setScrollToCell,
+    cellValidationErrors,
+    applyValidationDiff,
   } = useCsvStore();
 
   const [editValue, setEditValue] = useState("");
@@ -144,6 +148,17 @@ export function CsvTable() {
     }
   }, [editingCell, data]);
 
+  // Mark cells as live validation reports errors added and cleared by edits
+  useEffect(() => {
+    const unlisten = listen<ValidationDiff>("validation-diff", (event) => {
+      applyValidationDiff(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [applyValidationDiff]);
+
   // Focus and select text when header editing starts
   useEffect(() => {
     if (editingHeaderColumn !== null && headerInputRef.current) {
@@ -1126,6 +1141,12 @@ export function CsvTable() {
                     hasSearchResults &&
                     searchResultIndex === currentSearchIndex;
 
+                  const validationErrors =
+                    cellValidationErrors[
+                      `${virtualRow.index}:${virtualColumn.index}`
+                    ];
+                  const hasValidationError = !!validationErrors;
+
                   return (
                     <div
                       key={`${virtualRow.index}-${virtualColumn.index}`}
@@ -1154,6 +1175,11 @@ export function CsvTable() {
                             !isEditing,
                           "bg-orange-300 border-orange-500 border-2 z-[30] ring-2 ring-orange-400":
                             isCurrentSearchResult && !isEditing,
+                          "bg-red-50 ring-1 ring-inset ring-red-400":
+                            hasValidationError &&
+                            !isSelected &&
+                            !isEditing &&
+                            !isSearchResult,
                         }
                       )}
                       style={{
@@ -1203,7 +1229,15 @@ export function CsvTable() {
                       ) : (
                         <span
                           className="truncate w-full block"
-                          title={cellValue.length > 20 ? cellValue : undefined}
+                          title={
+                            hasValidationError
+                              ? validationErrors
+                                  .map((error) => error.message)
+                                  .join("\n")
+                              : cellValue.length > 20
+                              ? cellValue
+                              : undefined
+                          }
                         >
                           {cellValue}
                         </span>
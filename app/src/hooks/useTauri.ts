@@ -1,6 +1,6 @@
 import { invoke } from '@tauri-apps/api/tauri';
 import { open, save } from '@tauri-apps/api/dialog';
-import type { CsvData, CsvMetadata, SortState, ViewState } from '../types/csv';
+import type { CsvData, CsvMetadata, SortState, ViewState, ValidationRule, ValidationError } from '../types/csv';
 
 export interface SaveOptions {
   format?: 'csv' | 'tsv';
@@ -232,6 +232,28 @@ class TauriAPI {
       throw new Error(`Failed to copy selection to clipboard: ${error}`);
     }
   }
+
+  async startLiveValidation(data: CsvData, rules: ValidationRule[]): Promise<ValidationError[]> {
+    try {
+      return await invoke<ValidationError[]>('start_live_validation', { data, rules });
+    } catch (error) {
+      console.error('Failed to start live validation:', error);
+      throw new Error(`Failed to start live validation: ${error}`);
+    }
+  }
+
+  // The errors the edit added and cleared arrive as a "validation-diff" event
+  async liveValidateCell(rowIndex: number, columnIndex: number, value: string): Promise<void> {
+    await invoke('live_validate_cell', { rowIndex, columnIndex, value });
+  }
+
+  async stopLiveValidation(): Promise<void> {
+    try {
+      await invoke('stop_live_validation');
+    } catch (error) {
+      console.error('Failed to stop live validation:', error);
+    }
+  }
 }
 
 export const tauriAPI = new TauriAPI();
@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { devtools } from 'zustand/middleware';
-import type { CsvData, CsvCell, CsvSelection, ViewportRange, FilterConfig, SortConfig, HistoryAction, SortState } from '../types/csv';
+import type { CsvData, CsvCell, CsvSelection, ViewportRange, FilterConfig, SortConfig, HistoryAction, SortState, ValidationRule, ValidationError, ValidationDiff } from '../types/csv';
 import { applyFilter } from '../utils/filtering';
 
 interface CsvState {
@@ -62,9 +62,12 @@ interface CsvState {
   aiPendingChanges: any | null;
 
   // Validation state
-  cellValidationErrors: Record<string, string>; // key: "row:column", value: error message
-  setCellValidationError: (row: number, column: number, error: string | null) => void;
+  cellValidationErrors: Record<string, ValidationError[]>; // key: "row:column"
+  liveValidationRules: ValidationRule[] | null; // rules re-checked as cells are edited
   getCellValidationError: (row: number, column: number) => string | null;
+  startLiveValidation: (rules: ValidationRule[]) => Promise<ValidationError[]>;
+  stopLiveValidation: () => Promise<void>;
+  applyValidationDiff: (diff: ValidationDiff) => void;
 
   // Actions
   setData: (data: CsvData, filePath?: string) => void;
@@ -156,6 +159,17 @@ interface CsvState {
   createNewCsv: () => void;
 }
 
+// Errors of rows by cell, leaving out rules that could not be applied
+function groupCellErrors(errors: ValidationError[]): Record<string, ValidationError[]> {
+  const grouped: Record<string, ValidationError[]> = {};
+  for (const error of errors) {
+    if (error.rule_error) continue;
+    const key = `${error.row_index}:${error.column_index}`;
+    (grouped[key] = grouped[key] || []).push(error);
+  }
+  return grouped;
+}
+
 export const useCsvStore = create<CsvState>()(
   devtools(
     (set, get) => ({
@@ -205,6 +219,7 @@ export const useCsvStore = create<CsvState>()(
       aiPendingChanges: null,
 
       cellValidationErrors: {},
+      liveValidationRules: null,
 
       // Actions
       setData: async (data, filePath) => {
@@ -1404,6 +1419,50 @@ export const useCsvStore = create<CsvState>()(
 
       clearAiMessages: () => set({ aiMessages: [], aiPendingChanges: null }),
 
+      getCellValidationError: (row, column) => {
+        const errors = get().cellValidationErrors[`${row}:${column}`];
+        return errors ? errors.map(error => error.message).join('\n') : null;
+      },
+
+      startLiveValidation: async (rules) => {
+        const state = get();
+        if (!state.data) return [];
+
+        const { tauriAPI } = await import('../hooks/useTauri');
+        const errors = await tauriAPI.startLiveValidation(state.data, rules);
+        set({
+          cellValidationErrors: groupCellErrors(errors),
+          liveValidationRules: rules
+        });
+        return errors;
+      },
+
+      stopLiveValidation: async () => {
+        set({ cellValidationErrors: {}, liveValidationRules: null });
+        const { tauriAPI } = await import('../hooks/useTauri');
+        await tauriAPI.stopLiveValidation();
+      },
+
+      applyValidationDiff: (diff) => {
+        const cellValidationErrors = { ...get().cellValidationErrors };
+        for (const error of diff.cleared) {
+          const key = `${error.row_index}:${error.column_index}`;
+          const remaining = (cellValidationErrors[key] || []).filter(
+            existing => existing.rule_type !== error.rule_type || existing.message !== error.message
+          );
+          if (remaining.length > 0) {
+            cellValidationErrors[key] = remaining;
+          } else {
+            delete cellValidationErrors[key];
+          }
+        }
+        for (const error of diff.added) {
+          const key = `${error.row_index}:${error.column_index}`;
+          cellValidationErrors[key] = [...(cellValidationErrors[key] || []), error];
+        }
+        set({ cellValidationErrors });
+      },
+
       markSaved: () => set({ hasUnsavedChanges: false }),
 
       reset: () => set({
@@ -1431,7 +1490,8 @@ export const useCsvStore = create<CsvState>()(
         currentSort: { columns: [] },
         aiMessages: [],
         aiPendingChanges: null,
-        cellValidationErrors: {}
+        cellValidationErrors: {},
+        liveValidationRules: null
       }),
 
       createNewCsv: () => {
@@ -1477,4 +1537,71 @@ export const useCsvStore = create<CsvState>()(
     }),
     { name: 'csv-store' }
   )
-);
\ No newline at end of file
+);
+
+// Edits re-check only the cells they changed. Beyond this many changed cells,
+// or when rows or columns were added, removed or moved, live validation is
+// started again for the new data.
+const MAX_LIVE_CELL_EDITS = 1000;
+
+function changedCells(before: CsvData, after: CsvData): CsvCell[] | null {
+  if (
+    before.rows.length !== after.rows.length ||
+    before.headers.length !== after.headers.length ||
+    before.headers.some((header, index) => header !== after.headers[index])
+  ) {
+    return null;
+  }
+
+  const cells: CsvCell[] = [];
+  for (let row = 0; row < after.rows.length; row++) {
+    const beforeRow = before.rows[row];
+    const afterRow = after.rows[row];
+    if (beforeRow === afterRow) continue;
+
+    const width = Math.max(beforeRow.length, afterRow.length);
+    for (let column = 0; column < width; column++) {
+      const value = afterRow[column] ?? '';
+      if ((beforeRow[column] ?? '') !== value) {
+        cells.push({ row, column, value });
+        if (cells.length > MAX_LIVE_CELL_EDITS) return null;
+      }
+    }
+  }
+  return cells;
+}
+
+// Edits are sent one after another, in the order they were made
+let liveValidationQueue: Promise<void> = Promise.resolve();
+
+useCsvStore.subscribe((state, prev) => {
+  const rules = state.liveValidationRules;
+  if (!rules || state.data === prev.data) return;
+
+  if (!state.data || state.currentFilePath !== prev.currentFilePath) {
+    state.stopLiveValidation();
+    return;
+  }
+
+  const cells = prev.data ? changedCells(prev.data, state.data) : null;
+  liveValidationQueue = liveValidationQueue.then(async () => {
+    const { tauriAPI } = await import('../hooks/useTauri');
+    if (cells !== null) {
+      try {
+        for (const cell of cells) {
+          await tauriAPI.liveValidateCell(cell.row, cell.column, cell.value);
+        }
+        return;
+      } catch {
+        // Commands that add, remove or reorder rows stop live validation
+        // on the backend; it is started again below
+      }
+    }
+
+    if (useCsvStore.getState().liveValidationRules === rules) {
+      await useCsvStore.getState().startLiveValidation(rules).catch(error => {
+        console.warn('Failed to restart live validation:', error);
+      });
+    }
+  });
+});
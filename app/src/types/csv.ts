@@ -87,4 +87,27 @@ export interface ViewState {
   columnWidths: Record<number, number>;
   viewportRange?: ViewportRange;
   defaultColumnWidth?: number;
-}
\ No newline at end of file
+}
+export interface ValidationRule {
+  rule_type: 'range' | 'length' | 'pattern' | 'required' | 'unique';
+  column_index: number;
+  column_name: string;
+  parameters: Record<string, string>;
+  error_message?: string;
+}
+
+export interface ValidationError {
+  row_index: number;
+  column_index: number;
+  column_name: string;
+  value: string;
+  rule_type: string;
+  message: string;
+  rule_error?: boolean;
+}
+
+// Errors a cell edit added and cleared, sent as the "validation-diff" event
+export interface ValidationDiff {
+  added: ValidationError[];
+  cleared: ValidationError[];
+}